@@ -0,0 +1,263 @@
+use crate::{TelValue, VCard, ValueDataType};
+
+const GIVEN_NAMES: &[&str] = &[
+    "Alex", "Bente", "Carla", "David", "Emil", "Frida", "Gustav", "Hanna", "Ivo", "Jana", "Kai",
+    "Lena", "Mats", "Nora", "Oskar", "Paula",
+];
+
+const SURNAMES: &[&str] = &[
+    "Berg", "Dahl", "Eriksen", "Falk", "Holm", "Lund", "Moen", "Nilsen", "Olsen", "Strand", "Vik",
+    "Wold",
+];
+
+const STREETS: &[&str] = &[
+    "Main Street",
+    "Station Road",
+    "Park Lane",
+    "Church Street",
+    "Mill Road",
+    "High Street",
+];
+
+const CITIES: &[&str] = &[
+    "Springfield",
+    "Riverside",
+    "Fairview",
+    "Greenville",
+    "Kingston",
+    "Oakdale",
+];
+
+/// Returns a copy of `card` without personal data, e.g. to share a real-world vcard as test fixture.
+///
+/// The values of FN, N, NICKNAME, PHOTO, ADR, TEL, EMAIL, URL and NOTE are replaced with synthetic
+/// placeholders, like random names and addresses below `example.com`. Phone numbers and postal codes
+/// keep their format, only the digits are replaced. All other properties, e.g. KIND, ORG and CATEGORIES,
+/// the number of properties and their parameters are kept.
+///
+/// The placeholders are derived from `seed`, so the same card and seed always give the same result.
+///
+/// ```
+/// use vcard::*;
+///
+/// let card = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\n\
+///     EMAIL;TYPE=work:heinrich@tosafjord.example\r\n\
+///     ORG:Tosafjord Inc.\r\n\
+///     END:VCARD\r\n")?;
+///
+/// let anonymized = anonymize(card.clone(), 42);
+/// assert!(!anonymized.to_string().contains("Heinrich"));
+/// assert_eq!(card.org, anonymized.org);
+/// assert_eq!(anonymized, anonymize(card, 42));
+/// # Ok::<(), VCardError>(())
+/// ```
+pub fn anonymize(mut card: VCard, seed: u64) -> VCard {
+    let mut rng = Rng(seed);
+    let given_name = rng.pick(GIVEN_NAMES);
+    let surname = rng.pick(SURNAMES);
+
+    card.fn_property
+        .update_values(|f| f.value = format!("{} {}", given_name, surname));
+    card.n.update_values(|n| {
+        replace_all(&mut n.surenames, || surname.to_string());
+        replace_all(&mut n.given_names, || given_name.to_string());
+        replace_all(&mut n.additional_names, || {
+            rng.pick(GIVEN_NAMES).to_string()
+        });
+        if let Some(sort_as) = &mut n.sort_as {
+            replace_all(sort_as, || surname.to_string());
+        }
+    });
+    card.nickname
+        .update_values(|n| replace_all(&mut n.value, || rng.pick(GIVEN_NAMES).to_string()));
+    card.photo.update_values(|p| {
+        p.value = format!("https://example.com/photos/{}.jpg", rng.next() % 10000)
+            .parse()
+            .unwrap_or_default()
+    });
+
+    card.adr.update_values(|a| {
+        replace_all(&mut a.po_box, || rng.digits("1234"));
+        replace_all(&mut a.extended_address, || {
+            format!("Suite {}", rng.next() % 100 + 1)
+        });
+        replace_all(&mut a.street, || {
+            format!("{} {}", rng.next() % 200 + 1, rng.pick(STREETS))
+        });
+        replace_all(&mut a.city, || rng.pick(CITIES).to_string());
+        for postal_code in a.postal_code.iter_mut() {
+            *postal_code = rng.digits(postal_code);
+        }
+        // the label is stored escaped, so the lines are separated by `\n` instead of a line break
+        if a.label.is_some() {
+            a.label = Some(
+                a.street
+                    .iter()
+                    .chain(a.city.iter())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("\\n"),
+            );
+        }
+        if a.geo.is_some() {
+            a.geo = Some("geo:0,0".into());
+        }
+    });
+    card.tel.update_values(|t| {
+        let number = rng.digits(t.value.as_str());
+        t.value = match t.value {
+            TelValue::Uri(_) => TelValue::new(number, Some(&ValueDataType::Uri)),
+            TelValue::Text(_) => number.into(),
+        };
+    });
+
+    let mut index = 0;
+    card.email.update_values(|e| {
+        index += 1;
+        let suffix = if index > 1 {
+            index.to_string()
+        } else {
+            String::new()
+        };
+        e.value = format!(
+            "{}.{}{}@example.com",
+            given_name.to_lowercase(),
+            surname.to_lowercase(),
+            suffix
+        );
+    });
+    card.url.update_values(|u| {
+        u.value = format!("https://example.com/{}", rng.next() % 10000);
+    });
+    card.note
+        .update_values(|n| n.value = "Lorem ipsum dolor sit amet.".into());
+
+    card
+}
+
+// replaces all non-empty values, empty components stay empty to keep the structure
+fn replace_all(values: &mut [String], mut placeholder: impl FnMut() -> String) {
+    for value in values.iter_mut().filter(|v| !v.is_empty()) {
+        *value = placeholder();
+    }
+}
+
+// SplitMix64, a small deterministic pseudo random number generator.
+// See https://prng.di.unimi.it/splitmix64.c
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn pick<'a>(&mut self, values: &[&'a str]) -> &'a str {
+        values[(self.next() % values.len() as u64) as usize]
+    }
+
+    // replaces every digit of `value` with a random digit, e.g. `+49 176 1010-1520` becomes `+83 402 9917-0364`
+    fn digits(&mut self, value: &str) -> String {
+        value
+            .chars()
+            .map(|c| match c {
+                '0'..='9' => char::from(b'0' + (self.next() % 10) as u8),
+                c => c,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_anonymize() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\n\
+            KIND:individual\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            N:vom Tosafjord;Heinrich;Kasper;Dr.;\r\n\
+            NICKNAME:Heiner\r\n\
+            ADR;TYPE=home;LABEL=\"Fjordweg 1\\n12345 Tosafjord\":;;Fjordweg 1;Tosafjord;;12345;Norway\r\n\
+            TEL;VALUE=uri;TYPE=cell:tel:+49-176-1010-1520\r\n\
+            EMAIL;TYPE=work:heinrich@tosafjord.example\r\n\
+            EMAIL;TYPE=home:heiner@example.com\r\n\
+            ORG:Tosafjord Inc.\r\n\
+            CATEGORIES:friends\r\n\
+            URL:https://tosafjord.example/heinrich\r\n\
+            NOTE:met at the fjord\r\n\
+            END:VCARD\r\n",
+        )?;
+        let anonymized = anonymize(card.clone(), 7);
+        let raw = anonymized.to_string();
+        for personal in &[
+            "Heinrich",
+            "Tosafjord;",
+            "Kasper",
+            "Heiner",
+            "Fjordweg",
+            "1010",
+            "fjord\r\n",
+        ] {
+            assert!(!raw.contains(personal), "{} in {}", personal, raw);
+        }
+
+        assert_eq!(card.kind, anonymized.kind);
+        assert_eq!(card.org, anonymized.org);
+        assert_eq!(card.categories, anonymized.categories);
+        assert_eq!(2, anonymized.email.total_count());
+        assert_eq!(
+            vec![&Some(vec![EmailType::Work]), &Some(vec![EmailType::Home])],
+            anonymized
+                .email
+                .iter()
+                .map(|e| &e.type_param)
+                .collect::<Vec<_>>()
+        );
+        let n = &anonymized.n.values()[0];
+        assert_eq!(vec!["Dr.".to_string()], n.honorific_prefixes);
+        let tel = anonymized.tel.iter().next().unwrap();
+        assert!(matches!(tel.value, TelValue::Uri(_)));
+        assert_eq!("tel:+NN-NNN-NNNN-NNNN", mask_digits(tel.value.as_str()));
+        let adr = anonymized.adr.iter().next().unwrap();
+        assert_eq!(vec!["Norway".to_string()], adr.country);
+        assert_eq!("NNNNN", mask_digits(&adr.postal_code[0]));
+
+        assert_eq!(
+            Some(format!("{}\\n{}", adr.street[0], adr.city[0])),
+            adr.label
+        );
+
+        // the anonymized vcard can be read again and has the same properties and parameters
+        let reparsed = VCard::parse(&raw)?;
+        assert_eq!(anonymized, reparsed);
+        let structure = |card: &VCard| {
+            card.to_string()
+                .lines()
+                .map(|line| {
+                    let name = line.split(':').next().unwrap_or_default();
+                    name.split(';')
+                        .map(|p| p.split('=').next().unwrap_or_default().to_string())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(structure(&card), structure(&reparsed));
+
+        assert_eq!(anonymized, anonymize(card.clone(), 7));
+        assert_ne!(anonymized, anonymize(card, 8));
+        Ok(())
+    }
+
+    fn mask_digits(value: &str) -> String {
+        value
+            .chars()
+            .map(|c| if c.is_ascii_digit() { 'N' } else { c })
+            .collect()
+    }
+}
@@ -0,0 +1,255 @@
+use std::{collections::HashMap, iter::FromIterator, path::Path};
+
+use crate::{VCard, VCardError};
+
+/// A list of vcards with lookups by email address, phone number and UID.
+///
+/// Email addresses are compared case-insensitive, phone numbers by their digits,
+/// so `tel:+49-176-1010-1520` and `+49 176 10101520` are the same number.
+/// If several vcards share a key, lookups return the vcard that was inserted first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VCardCollection {
+    cards: Vec<VCard>,
+    by_email: HashMap<String, usize>,
+    by_tel: HashMap<String, usize>,
+    by_uid: HashMap<String, usize>,
+}
+
+impl VCardCollection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses all `.vcf` files of `path`. Files may contain several vcards.
+    ///
+    /// The files are read in the order of their names, subdirectories are ignored.
+    pub fn load_dir(path: &Path) -> Result<Self, VCardError> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            let is_vcf = path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("vcf"));
+            if is_vcf && path.is_file() {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        let mut result = Self::new();
+        for file in files {
+            for card in VCard::parse_all(&std::fs::read_to_string(file)?)? {
+                result.insert(card);
+            }
+        }
+        Ok(result)
+    }
+
+    /// adds `card` to the collection and returns its index.
+    pub fn insert(&mut self, card: VCard) -> usize {
+        let index = self.cards.len();
+        self.cards.push(card);
+        self.index_card(index);
+        index
+    }
+
+    /// removes the vcard at `index` and returns it. The indices of the following vcards are shifted by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> VCard {
+        let card = self.cards.remove(index);
+        self.rebuild_indexes();
+        card
+    }
+
+    /// removes the vcard with the UID `uid` and returns it.
+    pub fn remove_by_uid(&mut self, uid: &str) -> Option<VCard> {
+        let index = *self.by_uid.get(uid)?;
+        Some(self.remove(index))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&VCard> {
+        self.cards.get(index)
+    }
+
+    pub fn find_by_email(&self, email: &str) -> Option<&VCard> {
+        self.find(&self.by_email, &email.to_lowercase())
+    }
+
+    pub fn find_by_tel(&self, tel: &str) -> Option<&VCard> {
+        self.find(&self.by_tel, &normalize_tel(tel))
+    }
+
+    pub fn find_by_uid(&self, uid: &str) -> Option<&VCard> {
+        self.find(&self.by_uid, uid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, VCard> {
+        self.cards.iter()
+    }
+
+    pub fn into_cards(self) -> Vec<VCard> {
+        self.cards
+    }
+
+    fn find(&self, index: &HashMap<String, usize>, key: &str) -> Option<&VCard> {
+        index.get(key).map(|i| &self.cards[*i])
+    }
+
+    fn index_card(&mut self, index: usize) {
+        let card = &self.cards[index];
+        for email in card.email.iter() {
+            self.by_email
+                .entry(email.value.to_lowercase())
+                .or_insert(index);
+        }
+        for tel in card.tel.iter() {
+            let tel = normalize_tel(tel.value.as_str());
+            if !tel.is_empty() {
+                self.by_tel.entry(tel).or_insert(index);
+            }
+        }
+        if let Some(uid) = &card.uid {
+            self.by_uid.entry(uid.value.clone()).or_insert(index);
+        }
+    }
+
+    fn rebuild_indexes(&mut self) {
+        self.by_email.clear();
+        self.by_tel.clear();
+        self.by_uid.clear();
+        for index in 0..self.cards.len() {
+            self.index_card(index);
+        }
+    }
+}
+
+impl FromIterator<VCard> for VCardCollection {
+    fn from_iter<I: IntoIterator<Item = VCard>>(iter: I) -> Self {
+        let mut result = Self::new();
+        for card in iter {
+            result.insert(card);
+        }
+        result
+    }
+}
+
+impl<'a> IntoIterator for &'a VCardCollection {
+    type Item = &'a VCard;
+    type IntoIter = std::slice::Iter<'a, VCard>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cards.iter()
+    }
+}
+
+// reduces a phone number like `tel:+49-176-1010-1520;ext=1` to its digits and the leading `+`
+pub(crate) fn normalize_tel(tel: &str) -> String {
+    let tel = tel.trim();
+    let tel = tel
+        .get(..4)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("tel:"))
+        .map_or(tel, |_| &tel[4..]);
+    let number = tel.split(';').next().unwrap_or("");
+    number
+        .chars()
+        .enumerate()
+        .filter(|(i, c)| c.is_ascii_digit() || (*i == 0 && *c == '+'))
+        .map(|(_, c)| c)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::*;
+
+    #[test]
+    fn test_load_dir() -> Result<(), VCardError> {
+        let mut dir = PathBuf::new();
+        dir.push(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/good_vcards"
+        ));
+        let collection = VCardCollection::load_dir(&dir)?;
+        assert_eq!(3, collection.len());
+
+        let fn_of = |card: Option<&VCard>| {
+            card.and_then(|c| c.fn_property.get_prefered_value())
+                .map(|f| f.value.clone())
+        };
+        assert_eq!(
+            Some("Judith".to_string()),
+            fn_of(collection.find_by_email("TEST2@example.com"))
+        );
+        assert_eq!(
+            Some("Judith".to_string()),
+            fn_of(collection.find_by_tel("tel:+49-123-456789"))
+        );
+        assert_eq!(
+            Some("Heinrich vom Tosafjord".to_string()),
+            fn_of(collection.find_by_tel("0176 10101520"))
+        );
+        assert!(collection.find_by_email("nobody@example.com").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let card = |uid: &str, email: &str| {
+            VCard::new(VersionValue::V4)
+                .uid(Uid {
+                    value: uid.into(),
+                    ..Default::default()
+                })
+                .email(Email {
+                    value: email.into(),
+                    ..Default::default()
+                })
+                .build_unchecked()
+        };
+        let mut collection: VCardCollection = vec![
+            card("1", "first@example.com"),
+            card("2", "second@example.com"),
+            card("3", "third@example.com"),
+        ]
+        .into_iter()
+        .collect();
+
+        let removed = collection.remove_by_uid("1").unwrap();
+        assert_eq!(
+            "first@example.com",
+            removed.email.iter().next().unwrap().value
+        );
+        assert!(collection.find_by_uid("1").is_none());
+        assert!(collection.find_by_email("first@example.com").is_none());
+
+        // the indexes point to the shifted vcards
+        let third = collection.find_by_email("third@example.com").unwrap();
+        assert_eq!("3", third.uid.as_ref().unwrap().value);
+        assert_eq!(2, collection.insert(card("4", "fourth@example.com")));
+        assert_eq!(
+            "fourth@example.com",
+            collection
+                .find_by_uid("4")
+                .unwrap()
+                .email
+                .iter()
+                .next()
+                .unwrap()
+                .value
+        );
+        assert!(collection.remove_by_uid("1").is_none());
+    }
+}
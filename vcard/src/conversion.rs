@@ -0,0 +1,905 @@
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+};
+
+use crate::{
+    add_property, content_line_to_proprietary, unquote_param_value, ContentLine, Property, VCard,
+    VCardError, VCardProperties, Version, VersionValue, FN,
+};
+
+// properties of vCard 4.0 that do not exist in vCard 3.0 and the proprietary names that are used instead.
+// Properties without a replacement are dropped.
+const V4_ONLY_PROPERTIES: &[(&str, Option<&str>)] = &[
+    ("KIND", Some("X-ADDRESSBOOKSERVER-KIND")),
+    ("MEMBER", Some("X-ADDRESSBOOKSERVER-MEMBER")),
+    ("ANNIVERSARY", Some("X-ANNIVERSARY")),
+    ("GENDER", Some("X-GENDER")),
+    ("CLIENTPIDMAP", None),
+    ("XML", None),
+    ("LANG", None),
+    ("RELATED", None),
+];
+
+// properties of vCard 3.0 that do not exist in vCard 4.0, they are written as X- properties.
+// See https://datatracker.ietf.org/doc/html/rfc6350#appendix-A.2
+const V3_ONLY_PROPERTIES: &[&str] = &[
+    "AGENT",
+    "CLASS",
+    "LABEL",
+    "MAILER",
+    "NAME",
+    "PROFILE",
+    "SORT-STRING",
+];
+
+// parameters of vCard 4.0 that do not exist in vCard 3.0
+const V4_ONLY_PARAMETERS: &[&str] = &["ALTID", "PID", "CALSCALE", "SORT-AS", "GEO", "TZ"];
+
+// properties that carry the media type in the TYPE parameter in vCard 3.0 and the top level media type
+pub(crate) const MEDIA_PROPERTIES: &[(&str, &str)] = &[
+    ("PHOTO", "image"),
+    ("LOGO", "image"),
+    ("SOUND", "audio"),
+    ("KEY", "application"),
+];
+
+// values of the TYPE parameter of MEDIA_PROPERTIES that are formats, e.g. `JPEG` for `image/jpeg`
+const MEDIA_FORMATS: &[&str] = &[
+    "JPEG", "PNG", "GIF", "BMP", "TIFF", "WEBP", "BASIC", "MPEG", "WAV", "OGG", "PGP", "X509",
+];
+
+// returns true if `type_value` is the format of a binary value like `JPEG`
+pub(crate) fn is_media_format(type_value: &str) -> bool {
+    MEDIA_FORMATS
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(type_value))
+}
+
+/// Describes a single change that was made while converting a vcard to another version,
+/// see `VCard::upgrade_to_v4` and `VCard::downgrade_to_v3`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionNote {
+    /// name of the affected property, e.g. `TEL`
+    pub property: String,
+    pub description: String,
+}
+
+/// A note of `VCard::downgrade_to_v3`.
+pub type DowngradeNote = ConversionNote;
+
+impl VCard {
+    /// Serializes this vcard as `version`, no matter which version this vcard has.
+    ///
+    /// `Display` writes the properties as they are. Properties and parameters that do not exist in the target
+    /// version are converted to their counterparts or dropped:
+    /// - 3.0: `PREF=1` becomes `TYPE=pref`, `MEDIATYPE` becomes `TYPE=JPEG`, GEO is written as `lat;lon`,
+    ///   TZ is written as utc-offset or text, the LABEL parameter becomes a LABEL property and
+    ///   KIND, MEMBER, ANNIVERSARY and GENDER are written as X- properties. Other 4.0 only properties are dropped.
+    ///   Only the alternative with the lowest PREF is written for properties with an ALTID.
+    /// - 4.0: `TYPE=pref` becomes `PREF=1`, `TYPE=JPEG` becomes `MEDIATYPE=image/jpeg` and GEO is written as `geo:` URI.
+    ///   Phone numbers of a 3.0 vcard are written as `tel:` URI, inline binary values like PHOTO as `data:` URI,
+    ///   BDAY and REV in the basic ISO 8601 format and 3.0 only properties like MAILER are written
+    ///   as X- properties. A missing FN is built from N.
+    pub fn to_string_as(&self, version: VersionValue) -> String {
+        ConvertedVCard(self, &version).to_string()
+    }
+
+    /// Converts this vcard into a vCard 4.0, see https://datatracker.ietf.org/doc/html/rfc6350#appendix-A
+    ///
+    /// The conversion is the same as in `to_string_as`. All changes are described by the returned notes,
+    /// including properties that had to be dropped.
+    pub fn upgrade_to_v4(self) -> (VCard, Vec<ConversionNote>) {
+        if self.version.value == VersionValue::V4 {
+            return (self, Vec::new());
+        }
+        self.convert(VersionValue::V4)
+    }
+
+    /// Converts this vcard into a vCard 3.0 for consumers that do not support vCard 4.0.
+    ///
+    /// The conversion is the same as in `to_string_as`. 3.0 only properties like LABEL are stored as `LegacyProperty`.
+    /// All changes are described by the returned notes, including properties that had to be dropped.
+    pub fn downgrade_to_v3(&self) -> (VCard, Vec<DowngradeNote>) {
+        self.convert(VersionValue::V3)
+    }
+
+    // converts this vcard line by line and parses the result again
+    fn convert(&self, version: VersionValue) -> (VCard, Vec<ConversionNote>) {
+        let mut converter = Converter::new(&self.version.value, &version);
+        let mut raw = String::new();
+        // writing into a string cannot fail
+        let _ = converter.write(self, &mut raw);
+
+        let mut result = VCard {
+            version: Version {
+                value: version.clone(),
+            },
+            ..Default::default()
+        };
+        for raw_line in raw.split_terminator("\r\n") {
+            let line = match ContentLine::parse(raw_line) {
+                Ok(line) => line,
+                Err(e) => {
+                    converter.drop_line(raw_line, e);
+                    continue;
+                }
+            };
+            let prop = match Property::try_from(&line) {
+                Ok(Property::Begin { .. } | Property::End { .. } | Property::Version(_)) => {
+                    continue
+                }
+                Ok(prop) => prop,
+                Err(e) => {
+                    // e.g. values that are invalid in the target version
+                    converter.notes.push(ConversionNote {
+                        property: line.name.to_uppercase(),
+                        description: format!("stored as proprietary property: {}", e),
+                    });
+                    result
+                        .proprietary_properties
+                        .push(content_line_to_proprietary(&line));
+                    continue;
+                }
+            };
+            if let Err(e) = add_property(&mut result, prop) {
+                converter.drop_line(raw_line, e);
+            }
+        }
+        (result, converter.notes)
+    }
+
+    pub(crate) fn write_as(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        version: &VersionValue,
+    ) -> fmt::Result {
+        Converter::new(&self.version.value, version).write(self, f)
+    }
+}
+
+// converts the content lines of a vcard from one version into another and records all changes
+struct Converter<'a> {
+    from: &'a VersionValue,
+    to: &'a VersionValue,
+    notes: Vec<ConversionNote>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(from: &'a VersionValue, to: &'a VersionValue) -> Self {
+        Self {
+            from,
+            to,
+            notes: Vec::new(),
+        }
+    }
+
+    fn note(&mut self, line: &Line, description: String) {
+        self.notes.push(ConversionNote {
+            property: line.name.to_uppercase(),
+            description,
+        });
+    }
+
+    fn drop_line(&mut self, raw_line: &str, e: VCardError) {
+        let property = match ContentLine::parse(raw_line) {
+            Ok(line) => line.name.to_uppercase(),
+            Err(_) => raw_line.into(),
+        };
+        self.notes.push(ConversionNote {
+            property,
+            description: format!("dropped: {}", e),
+        });
+    }
+
+    fn write(&mut self, vcard: &VCard, w: &mut impl fmt::Write) -> fmt::Result {
+        // the TYPE values are written in the case of the target version, see `Display for VCard`
+        let raw = match self.to {
+            VersionValue::V4 => VCardProperties(vcard).to_string(),
+            _ => format!("{:#}", VCardProperties(vcard)),
+        };
+        let mut lines: Vec<(&str, Option<Line>)> = raw
+            .split_terminator("\r\n")
+            .map(|raw_line| {
+                let line = ContentLine::parse(raw_line).ok().map(|l| Line::from(&l));
+                (raw_line, line)
+            })
+            .collect();
+        if *self.to == VersionValue::V3 {
+            self.drop_alternatives(&mut lines);
+        }
+
+        for (raw_line, line) in lines {
+            let line = match line {
+                Some(line) => line,
+                None => {
+                    self.notes.push(ConversionNote {
+                        property: raw_line.into(),
+                        description: "dropped: not a valid content line".into(),
+                    });
+                    continue;
+                }
+            };
+
+            if line.is("VERSION") {
+                write!(w, "VERSION:{}\r\n", self.to.as_ref())?;
+                // N is required in vCard 3.0
+                if *self.to == VersionValue::V3 && vcard.n.values().is_empty() {
+                    self.notes.push(ConversionNote {
+                        property: "N".into(),
+                        description: "added N:;;;; because N is required in vCard 3.0".into(),
+                    });
+                    write!(w, "N:;;;;\r\n")?;
+                }
+                // FN is required in vCard 4.0
+                if *self.to == VersionValue::V4 && *self.from != VersionValue::V4 {
+                    if let Some(fn_property) = self.fn_from_n(vcard) {
+                        write!(w, "{}", fn_property)?;
+                    }
+                }
+                continue;
+            }
+
+            let lines = match self.to {
+                VersionValue::V3 => self.convert_to_v3(line),
+                VersionValue::V4 => vec![self.convert_to_v4(line)],
+                // writing vCard 2.1 is not supported, the lines are written as they are
+                VersionValue::V21 => vec![line],
+            };
+            for line in lines {
+                write!(w, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+
+    // vCard 3.0 allows vcards without FN, the formatted name is built from N in that case
+    fn fn_from_n(&mut self, vcard: &VCard) -> Option<FN> {
+        if !vcard.fn_property.is_empty() {
+            return None;
+        }
+        let value = vcard.n.values().first()?.to_display_name();
+        if value.is_empty() {
+            return None;
+        }
+        self.notes.push(ConversionNote {
+            property: "FN".into(),
+            description: format!("added FN:{} built from N", value),
+        });
+        Some(FN {
+            value,
+            ..Default::default()
+        })
+    }
+
+    // vCard 3.0 does not know ALTID, so only one alternative of each property is kept.
+    // The alternative with the lowest PREF wins, the first one if there is no PREF.
+    fn drop_alternatives(&mut self, lines: &mut Vec<(&str, Option<Line>)>) {
+        let alternatives: Vec<Option<(String, String, u8)>> = lines
+            .iter()
+            .map(|(_, line)| {
+                let line = line.as_ref()?;
+                let altid = line.param("ALTID").filter(|a| !a.is_empty())?;
+                let pref = line
+                    .param("PREF")
+                    .and_then(|p| p.parse().ok())
+                    .unwrap_or(100);
+                Some((line.name.to_uppercase(), altid, pref))
+            })
+            .collect();
+
+        let mut index = 0;
+        lines.retain(|(_, line)| {
+            let i = index;
+            index += 1;
+            let (name, altid, _) = match &alternatives[i] {
+                Some(alternative) => alternative,
+                None => return true,
+            };
+            let best = alternatives
+                .iter()
+                .enumerate()
+                .filter_map(|(j, a)| a.as_ref().map(|a| (j, a)))
+                .filter(|(_, (n, a, _))| n == name && a == altid)
+                .min_by_key(|(_, (_, _, pref))| *pref)
+                .map(|(j, _)| j);
+            if best == Some(i) {
+                return true;
+            }
+            if let Some(line) = line {
+                self.notes.push(ConversionNote {
+                    property: name.clone(),
+                    description: format!("dropped alternative {} with ALTID={}", line.value, altid),
+                });
+            }
+            false
+        });
+    }
+
+    fn convert_to_v3(&mut self, mut line: Line) -> Vec<Line> {
+        if let Some((name, replacement)) = V4_ONLY_PROPERTIES.iter().find(|(name, _)| line.is(name))
+        {
+            match replacement {
+                Some(replacement) => {
+                    self.note(
+                        &line,
+                        format!(
+                            "{} does not exist in vCard 3.0 and was written as {}",
+                            name, replacement
+                        ),
+                    );
+                    line.name = replacement.to_string();
+                }
+                None => {
+                    self.note(
+                        &line,
+                        format!("{} does not exist in vCard 3.0 and was dropped", name),
+                    );
+                    return Vec::new();
+                }
+            }
+        }
+
+        for key in V4_ONLY_PARAMETERS {
+            for value in line.remove_param(key) {
+                self.note(&line, format!("dropped parameter {}={}", key, value));
+            }
+        }
+        // vCard 3.0 only knows whether a property is preferred or not
+        for pref in line.remove_param("PREF") {
+            if pref == "1" {
+                self.note(&line, "replaced PREF=1 with TYPE=pref".into());
+                line.params.push(("TYPE".into(), "pref".into()));
+            } else {
+                self.note(&line, format!("dropped parameter PREF={}", pref));
+            }
+        }
+        for media_type in line.remove_param("MEDIATYPE") {
+            match media_type.split_once('/') {
+                Some((_, subtype)) if line.media_type().is_some() => {
+                    let subtype = subtype.to_uppercase();
+                    self.note(
+                        &line,
+                        format!("replaced MEDIATYPE={} with TYPE={}", media_type, subtype),
+                    );
+                    line.params.push(("TYPE".into(), subtype));
+                }
+                _ => self.note(&line, format!("dropped parameter MEDIATYPE={}", media_type)),
+            }
+        }
+
+        if line.is("GEO") {
+            if let Some(uri) = line
+                .value
+                .get(..4)
+                .filter(|s| s.eq_ignore_ascii_case("geo:"))
+            {
+                let coordinates = line.value[uri.len()..].split(';').next().unwrap_or("");
+                let value = coordinates.replace(',', ";");
+                self.note(&line, format!("replaced {} with {}", line.value, value));
+                line.value = value;
+                line.remove_param("VALUE");
+            }
+        }
+        if line.is("TZ") {
+            line.remove_param("VALUE");
+            if !is_utc_offset(&line.value) {
+                line.params.push(("VALUE".into(), "text".into()));
+            }
+        }
+
+        let mut result = Vec::new();
+        if line.is("ADR") {
+            // the LABEL parameter replaces the LABEL property of vCard 3.0
+            for label in line.remove_param("LABEL") {
+                self.note(
+                    &line,
+                    "replaced the LABEL parameter with a LABEL property".into(),
+                );
+                result.push(Line {
+                    group: line.group.clone(),
+                    name: "LABEL".into(),
+                    params: line
+                        .params
+                        .iter()
+                        .filter(|(k, _)| k.eq_ignore_ascii_case("TYPE"))
+                        .cloned()
+                        .collect(),
+                    value: label,
+                });
+            }
+        }
+        result.insert(0, line);
+        result
+    }
+
+    fn convert_to_v4(&mut self, mut line: Line) -> Line {
+        if let Some(name) = V3_ONLY_PROPERTIES.iter().find(|name| line.is(name)) {
+            let replacement = format!("X-{}", name);
+            self.note(
+                &line,
+                format!(
+                    "{} does not exist in vCard 4.0 and was written as {}",
+                    name, replacement
+                ),
+            );
+            line.name = replacement;
+        }
+
+        let preferred = !line
+            .remove_types(|t| t.eq_ignore_ascii_case("pref"))
+            .is_empty();
+        if preferred && !line.has_param("PREF") {
+            self.note(&line, "replaced TYPE=pref with PREF=1".into());
+            line.params.push(("PREF".into(), "1".into()));
+        }
+
+        if let Some(media_type) = line.media_type() {
+            let formats = line.remove_types(is_media_format);
+            if let Some(format) = formats.first() {
+                if !line.has_param("MEDIATYPE") {
+                    let media_type = format!("{}/{}", media_type, format.to_lowercase());
+                    self.note(
+                        &line,
+                        format!("replaced TYPE={} with MEDIATYPE={}", format, media_type),
+                    );
+                    line.params.push(("MEDIATYPE".into(), media_type));
+                }
+            }
+        }
+
+        if line.is("GEO") {
+            if let Some((lat, lon)) = line.value.split_once(';') {
+                if lat.trim().parse::<f64>().is_ok() && lon.trim().parse::<f64>().is_ok() {
+                    let value = format!("geo:{},{}", lat.trim(), lon.trim());
+                    self.note(&line, format!("replaced {} with {}", line.value, value));
+                    line.value = value;
+                }
+            }
+        }
+
+        // phone numbers are text in vCard 3.0 but should be URIs in vCard 4.0
+        if *self.from != VersionValue::V4 && line.is("TEL") && !line.has_param("VALUE") {
+            if let Some(uri) = to_tel_uri(&line.value) {
+                self.note(&line, format!("replaced {} with {}", line.value, uri));
+                line.value = uri;
+                line.params.push(("VALUE".into(), "uri".into()));
+            }
+        }
+        // vCard 4.0 only allows the basic format of ISO 8601, e.g. 19960415 instead of 1996-04-15
+        if *self.from != VersionValue::V4 && (line.is("BDAY") || line.is("REV")) {
+            if let Some(value) = to_basic_date_and_or_time(&line.value) {
+                if value != line.value {
+                    self.note(&line, format!("replaced {} with {}", line.value, value));
+                    line.value = value;
+                }
+            }
+        }
+        line
+    }
+}
+
+struct ConvertedVCard<'a>(&'a VCard, &'a VersionValue);
+
+impl Display for ConvertedVCard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_as(f, self.1)
+    }
+}
+
+// an owned content line that can be modified during the conversion
+struct Line {
+    group: Option<String>,
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl From<&ContentLine<'_>> for Line {
+    fn from(line: &ContentLine<'_>) -> Self {
+        Self {
+            group: line.group.map(String::from),
+            name: line.name.into(),
+            params: line
+                .params
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            value: line.value.into(),
+        }
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(group) = &self.group {
+            write!(f, "{}.", group)?;
+        }
+        write!(f, "{}", self.name)?;
+        for (k, v) in self.params.iter() {
+            write!(f, ";{}={}", k, v)?;
+        }
+        write!(f, ":{}\r\n", self.value)
+    }
+}
+
+impl Line {
+    fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    // returns the unquoted value of the first parameter named `key`
+    fn param(&self, key: &str) -> Option<String> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| unquote_param_value(v).into_owned())
+    }
+
+    fn has_param(&self, key: &str) -> bool {
+        self.params.iter().any(|(k, _)| k.eq_ignore_ascii_case(key))
+    }
+
+    // removes all parameters named `key` and returns their unquoted values
+    fn remove_param(&mut self, key: &str) -> Vec<String> {
+        let mut removed = Vec::new();
+        self.params.retain(|(k, v)| {
+            if k.eq_ignore_ascii_case(key) {
+                removed.push(unquote_param_value(v).into_owned());
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    // removes all TYPE values that match `predicate` and returns them
+    fn remove_types(&mut self, predicate: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut removed = Vec::new();
+        for (k, v) in self.params.iter_mut() {
+            if !k.eq_ignore_ascii_case("TYPE") {
+                continue;
+            }
+            let types = unquote_param_value(v).into_owned();
+            let (matching, rest): (Vec<&str>, Vec<&str>) =
+                types.split(',').partition(|t| predicate(t));
+            if !matching.is_empty() {
+                removed.extend(matching.into_iter().map(String::from));
+                *v = rest.join(",");
+            }
+        }
+        self.params
+            .retain(|(k, v)| !k.eq_ignore_ascii_case("TYPE") || !v.is_empty());
+        removed
+    }
+
+    // returns the top level media type if this property carries a media type
+    fn media_type(&self) -> Option<&'static str> {
+        MEDIA_PROPERTIES
+            .iter()
+            .find(|(name, _)| self.is(name))
+            .map(|(_, media_type)| *media_type)
+    }
+}
+
+// converts phone numbers like `+49 176 1010-1520` into `tel:+49-176-1010-1520`.
+// Returns `None` if `value` does not look like a phone number.
+fn to_tel_uri(value: &str) -> Option<String> {
+    let value = value.trim();
+    let digits = value.bytes().filter(u8::is_ascii_digit).count();
+    let is_phone_number = value
+        .bytes()
+        .enumerate()
+        .all(|(i, b)| b.is_ascii_digit() || b"-.() ".contains(&b) || (i == 0 && b == b'+'));
+    if digits < 3 || !is_phone_number {
+        return None;
+    }
+    Some(format!(
+        "tel:{}",
+        value.split_whitespace().collect::<Vec<_>>().join("-")
+    ))
+}
+
+// converts dates and timestamps like `1953-10-15T23:10:00Z` into `19531015T231000Z`.
+// Returns `None` if `value` is not a date or timestamp, e.g. a BDAY with a text value.
+fn to_basic_date_and_or_time(value: &str) -> Option<String> {
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    let is_date = date.bytes().all(|b| b.is_ascii_digit() || b == b'-')
+        && date.bytes().any(|b| b.is_ascii_digit());
+    let is_time = time.is_none_or(|t| {
+        t.bytes()
+            .all(|b| b.is_ascii_digit() || b"-+:Z".contains(&b))
+    });
+    if !is_date || !is_time {
+        return None;
+    }
+    // truncated dates like `--0415` are already in the basic format,
+    // a year and month like `1996-04` keeps its hyphen, see https://datatracker.ietf.org/doc/html/rfc6350#section-4.3.1
+    let is_year_month =
+        date.len() == 7 && date.find('-') == Some(4) && date.matches('-').count() == 1;
+    let mut result = if date.starts_with("--") || is_year_month {
+        date.to_string()
+    } else {
+        date.replace('-', "")
+    };
+    if let Some(time) = time {
+        result.push('T');
+        result.push_str(&time.replace(':', ""));
+    }
+    Some(result)
+}
+
+// returns true for values like `-0500`, `+01:00` or `Z`
+pub(crate) fn is_utc_offset(value: &str) -> bool {
+    if value == "Z" {
+        return true;
+    }
+    let digits = match value.strip_prefix(['+', '-']) {
+        Some(rest) => rest.replacen(':', "", 1),
+        None => return false,
+    };
+    (digits.len() == 2 || digits.len() == 4) && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_to_string_as() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/apple_icloud.vcf",
+        ));
+        let card = VCardReader::new(&testant[..]).parse_vcard()?;
+        let v3 = card.to_string_as(VersionValue::V3);
+        assert_eq!(card.to_string(), v3);
+        assert!(v3.contains("\r\nitem3.TEL;TYPE=CELL;TYPE=pref;TYPE=VOICE:017610101520\r\n"));
+        assert_eq!(card, v3.parse()?);
+
+        let v4 = card.to_string_as(VersionValue::V4);
+        assert!(v4.contains("\r\nVERSION:4.0\r\n"));
+        assert!(
+            v4.contains("\r\nitem3.TEL;TYPE=cell;TYPE=voice;PREF=1;VALUE=uri:tel:017610101520\r\n")
+        );
+
+        let card = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .kind(Kind {
+                value: KindValue::Individual,
+                ..Default::default()
+            })
+            .geo(Geo {
+                value: "geo:37.386013,-122.082932".parse()?,
+                ..Default::default()
+            })
+            .photo(Photo {
+                mediatype: Some("image/jpeg".into()),
+                pref: Some(1),
+                value: PhotoValue::Uri("http://www.example.com/pub/photos/jqpublic.jpg".parse()?),
+                ..Default::default()
+            })
+            .tz(Tz {
+                value: TzValue::Text("Europe/Berlin".into()),
+                ..Default::default()
+            })
+            .anniversary(Anniversary {
+                value: "20090808".into(),
+                ..Default::default()
+            })?
+            .build()
+            .map_err(VCardError::from)?;
+
+        let v4 = card.to_string();
+        assert_eq!(v4, card.to_string_as(VersionValue::V4));
+        assert_eq!(card, v4.parse()?);
+
+        let v3 = card.to_string_as(VersionValue::V3);
+        assert_eq!(
+            "BEGIN:VCARD\r\n\
+             VERSION:3.0\r\n\
+             N:;;;;\r\n\
+             X-ADDRESSBOOKSERVER-KIND:individual\r\n\
+             FN:Heinrich vom Tosafjord\r\n\
+             PHOTO;TYPE=pref;TYPE=JPEG:http://www.example.com/pub/photos/jqpublic.jpg\r\n\
+             X-ANNIVERSARY:20090808\r\n\
+             TZ;VALUE=text:Europe/Berlin\r\n\
+             GEO:37.386013;-122.082932\r\n\
+             END:VCARD\r\n",
+            v3
+        );
+
+        // converting back to 4.0 restores the values that have an equivalent in 3.0
+        let upgraded = v3.parse::<VCard>()?.to_string_as(VersionValue::V4);
+        assert!(upgraded.contains("\r\nGEO:geo:37.386013,-122.082932\r\n"));
+        assert!(upgraded.contains(
+            "\r\nPHOTO;PREF=1;MEDIATYPE=image/jpeg:http://www.example.com/pub/photos/jqpublic.jpg\r\n"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_to_v4() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/apple_icloud.vcf",
+        ));
+        let card = VCardReader::new(&testant[..]).parse_vcard()?;
+        let (upgraded, notes) = card.upgrade_to_v4();
+
+        assert_eq!(VersionValue::V4, upgraded.version.value);
+        let tel = upgraded.tel.get_prefered_value().unwrap();
+        assert_eq!("tel:017610101520", tel.value.as_str());
+        assert!(matches!(tel.value, TelValue::Uri(_)));
+        assert_eq!(Some(ValueDataType::Uri), tel.value_data_type);
+        assert_eq!(Some(1), tel.pref);
+        assert_eq!(Some(vec![TelType::Cell, TelType::Voice]), tel.type_param);
+        let email = upgraded.email.get_prefered_value().unwrap();
+        assert_eq!(Some(1), email.pref);
+        assert_eq!(
+            Some(vec![EmailType::Home, EmailType::Internet]),
+            email.type_param
+        );
+        let url = &upgraded.url.get("").unwrap().values()[0];
+        assert_eq!(Some(1), url.pref);
+        assert_eq!(None, url.type_param);
+        assert_eq!(vec!["item1", "item2", "item3"], upgraded.groups());
+        assert!(notes.contains(&ConversionNote {
+            property: "TEL".into(),
+            description: "replaced 017610101520 with tel:017610101520".into(),
+        }));
+        assert!(upgraded.is_valid());
+
+        let testant = b"BEGIN:VCARD\r\nVERSION:3.0\r\nN:vom Tosafjord;Heinrich;;;\r\nFN:Heinrich vom Tosafjord\r\nMAILER:PigeonMail 2.1\r\nGEO:37.386013;-122.082932\r\nPHOTO;TYPE=JPEG:http://www.example.com/pub/photos/jqpublic.jpg\r\nTEL;TYPE=HOME:+49 176 1010-1520\r\nEND:VCARD\r\n";
+        let (card, errors) = VCardReader::new(&testant[..]).parse_vcard_lenient()?;
+        assert!(errors.is_empty());
+        assert_eq!(LegacyPropertyName::Mailer, card.legacy[0].name);
+        let (upgraded, notes) = card.upgrade_to_v4();
+
+        assert_eq!("X-MAILER", upgraded.proprietary_properties[0].name);
+        assert_eq!(
+            "geo:37.386013,-122.082932",
+            upgraded.geo.get_prefered_value().unwrap().value.to_string()
+        );
+        let photo = upgraded.photo.get_prefered_value().unwrap();
+        assert_eq!(Some("image/jpeg".into()), photo.mediatype);
+        assert_eq!(None, photo.type_param);
+        assert_eq!(
+            "tel:+49-176-1010-1520",
+            upgraded.tel.get_prefered_value().unwrap().value.as_str()
+        );
+        assert_eq!(4, notes.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_v3_values() -> Result<(), Box<dyn std::error::Error>> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\n\
+            VERSION:3.0\r\n\
+            N:Doe;John;;Dr.;\r\n\
+            PHOTO;ENCODING=b;TYPE=JPEG:MIICajCCAdOgAwIBAgICBEUwDQYJKoZIhvcN\r\n\
+            BDAY:1996-04-15\r\n\
+            REV:1995-10-31T22:27:10Z\r\n\
+            END:VCARD\r\n",
+        )?;
+        let (upgraded, notes) = card.upgrade_to_v4();
+
+        assert_eq!(
+            "Dr. John Doe",
+            upgraded.fn_property.get_prefered_value().unwrap().value
+        );
+        let photo = upgraded.photo.get_prefered_value().unwrap();
+        assert_eq!(
+            "data:image/jpeg;base64,MIICajCCAdOgAwIBAgICBEUwDQYJKoZIhvcN",
+            photo.value.to_string()
+        );
+        assert_eq!(None, photo.mediatype);
+        assert_eq!("19960415", upgraded.bday.values()[0].value);
+        assert_eq!("19951031T222710Z", upgraded.rev.as_ref().unwrap().value);
+        assert_eq!(
+            Some("1996-04".to_string()),
+            super::to_basic_date_and_or_time("1996-04")
+        );
+        assert!(notes.contains(&ConversionNote {
+            property: "FN".into(),
+            description: "added FN:Dr. John Doe built from N".into(),
+        }));
+        assert!(upgraded.is_valid());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_downgrade_to_v3() -> Result<(), Box<dyn std::error::Error>> {
+        let card = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                altid: Some("1".into()),
+                language: Some("de".into()),
+                pref: Some(2),
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .fn_property(FN {
+                altid: Some("1".into()),
+                language: Some("en".into()),
+                pref: Some(1),
+                value: "Henry of Tosafjord".into(),
+                ..Default::default()
+            })
+            .gender(Gender {
+                sex: Some(Sex::Male),
+                ..Default::default()
+            })
+            .photo(Photo {
+                mediatype: Some("image/png".into()),
+                pref: Some(1),
+                value: PhotoValue::Uri("http://www.example.com/pub/photos/heinrich.png".parse()?),
+                ..Default::default()
+            })
+            .adr(Adr {
+                label: Some("am Katzenklo\\nKatzenhausen".into()),
+                street: vec!["am Katzenklo".into()],
+                city: vec!["Katzenhausen".into()],
+                ..Default::default()
+            })
+            .geo(Geo {
+                value: "geo:37.386013,-122.082932".parse()?,
+                ..Default::default()
+            })
+            .build()
+            .map_err(VCardError::from)?;
+
+        let (downgraded, notes) = card.downgrade_to_v3();
+        assert_eq!(VersionValue::V3, downgraded.version.value);
+
+        let fns: Vec<&FN> = downgraded.fn_property.iter().collect();
+        assert_eq!(1, fns.len());
+        assert_eq!("Henry of Tosafjord", fns[0].value);
+        assert_eq!(None, fns[0].altid);
+
+        let photo = downgraded.photo.get_prefered_value().unwrap();
+        assert_eq!(None, photo.mediatype);
+        assert_eq!(
+            Some(vec!["pref".to_string(), "PNG".to_string()]),
+            photo.type_param
+        );
+        assert_eq!(
+            "37.386013;-122.082932",
+            downgraded
+                .geo
+                .get_prefered_value()
+                .unwrap()
+                .value
+                .to_string()
+        );
+        assert_eq!(None, downgraded.adr.get_prefered_value().unwrap().label);
+
+        assert_eq!("X-GENDER", downgraded.proprietary_properties[0].name);
+        assert_eq!(LegacyPropertyName::Label, downgraded.legacy[0].name);
+        assert_eq!("am Katzenklo\\nKatzenhausen", downgraded.legacy[0].value);
+
+        assert!(notes.contains(&DowngradeNote {
+            property: "FN".into(),
+            description: "dropped alternative Heinrich vom Tosafjord with ALTID=1".into(),
+        }));
+        assert!(notes.contains(&DowngradeNote {
+            property: "PHOTO".into(),
+            description: "replaced MEDIATYPE=image/png with TYPE=PNG".into(),
+        }));
+        assert!(notes.contains(&DowngradeNote {
+            property: "N".into(),
+            description: "added N:;;;; because N is required in vCard 3.0".into(),
+        }));
+        Ok(())
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{escape::unescape, Adr, VCard};
+
+/// A column of the CSV export, see `to_csv`.
+///
+/// The `First` columns and all other single value columns take the value with the lowest `PREF`.
+/// `Emails` and `Phones` contain all values, see `CsvMultiValues`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CsvColumn {
+    FullName,
+    GivenName,
+    FamilyName,
+    FirstEmail,
+    Emails,
+    FirstPhone,
+    Phones,
+    FirstStreet,
+    FirstCity,
+    FirstRegion,
+    FirstPostalCode,
+    Country,
+    Organization,
+    Title,
+    Birthday,
+    Note,
+    Uid,
+}
+
+impl CsvColumn {
+    /// returns the name of the column in the header row.
+    pub fn header(&self) -> &'static str {
+        match self {
+            Self::FullName => "Full Name",
+            Self::GivenName => "Given Name",
+            Self::FamilyName => "Family Name",
+            Self::FirstEmail => "E-mail",
+            Self::Emails => "E-mails",
+            Self::FirstPhone => "Phone",
+            Self::Phones => "Phones",
+            Self::FirstStreet => "Street",
+            Self::FirstCity => "City",
+            Self::FirstRegion => "Region",
+            Self::FirstPostalCode => "Postal Code",
+            Self::Country => "Country",
+            Self::Organization => "Organization",
+            Self::Title => "Title",
+            Self::Birthday => "Birthday",
+            Self::Note => "Note",
+            Self::Uid => "UID",
+        }
+    }
+
+    // returns all values of the column. Single value columns return at most one value.
+    // Text values are unescaped, e.g. `Smith\, John` becomes `Smith, John`.
+    fn values(&self, card: &VCard) -> Vec<String> {
+        let adr = |component: fn(&Adr) -> &Vec<String>| {
+            card.preferred_adr()
+                .map(|a| component(a).join(", "))
+                .into_iter()
+                .collect()
+        };
+        let n = || card.n.values().first();
+        match self {
+            Self::FullName => card
+                .fn_property
+                .get_prefered_value()
+                .map(|f| unescape(&f.value))
+                .into_iter()
+                .collect(),
+            Self::GivenName => n().map(|n| n.given_names.join(" ")).into_iter().collect(),
+            Self::FamilyName => n().map(|n| n.surenames.join(" ")).into_iter().collect(),
+            Self::FirstEmail => card
+                .preferred_email()
+                .map(|e| unescape(&e.value))
+                .into_iter()
+                .collect(),
+            Self::Emails => card.email.iter().map(|e| unescape(&e.value)).collect(),
+            Self::FirstPhone => card
+                .preferred_tel()
+                .map(|t| t.value.as_str().to_string())
+                .into_iter()
+                .collect(),
+            Self::Phones => card
+                .tel
+                .iter()
+                .map(|t| t.value.as_str().to_string())
+                .collect(),
+            Self::FirstStreet => adr(|a| &a.street),
+            Self::FirstCity => adr(|a| &a.city),
+            Self::FirstRegion => adr(|a| &a.region),
+            Self::FirstPostalCode => adr(|a| &a.postal_code),
+            Self::Country => adr(|a| &a.country),
+            Self::Organization => card
+                .org
+                .get_prefered_value()
+                .map(|o| o.value.join(", "))
+                .into_iter()
+                .collect(),
+            Self::Title => card
+                .title
+                .get_prefered_value()
+                .map(|t| unescape(&t.value))
+                .into_iter()
+                .collect(),
+            Self::Birthday => card
+                .bday
+                .values()
+                .first()
+                .map(|b| unescape(&b.value))
+                .into_iter()
+                .collect(),
+            Self::Note => card
+                .note
+                .get_prefered_value()
+                .map(|n| unescape(&n.value))
+                .into_iter()
+                .collect(),
+            Self::Uid => card.uid.iter().map(|u| unescape(&u.value)).collect(),
+        }
+    }
+}
+
+/// Decides how columns with several values (`CsvColumn::Emails`, `CsvColumn::Phones`) are written.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CsvMultiValues {
+    /// writes all values into one field, separated by the given string.
+    Join(String),
+    /// writes one row per value. The other columns are repeated in every row.
+    RepeatRow,
+}
+
+impl Default for CsvMultiValues {
+    fn default() -> Self {
+        Self::Join("; ".into())
+    }
+}
+
+/// Exports `cards` as CSV with a header row, joining multiple values with `; `.
+/// See `to_csv_with_options`.
+///
+/// ```
+/// use vcard::*;
+///
+/// let card = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\n\
+///     EMAIL:heinrich@example.com\r\n\
+///     ORG:Tosafjord\\, Inc.\r\n\
+///     END:VCARD\r\n")?;
+///
+/// let csv = to_csv(&[card], &[CsvColumn::FullName, CsvColumn::FirstEmail, CsvColumn::Organization]);
+/// assert_eq!(
+///     "Full Name,E-mail,Organization\r\nHeinrich vom Tosafjord,heinrich@example.com,\"Tosafjord, Inc.\"\r\n",
+///     csv
+/// );
+/// # Ok::<(), VCardError>(())
+/// ```
+pub fn to_csv(cards: &[VCard], columns: &[CsvColumn]) -> String {
+    to_csv_with_options(cards, columns, &CsvMultiValues::default())
+}
+
+/// Exports `cards` as CSV as defined by RFC 4180, see https://datatracker.ietf.org/doc/html/rfc4180
+///
+/// The first row contains the headers of `columns`. Rows are separated by CRLF, fields that contain
+/// a comma, a double quote or a line break are quoted.
+pub fn to_csv_with_options(
+    cards: &[VCard],
+    columns: &[CsvColumn],
+    multi_values: &CsvMultiValues,
+) -> String {
+    let mut result = String::new();
+    write_row(&mut result, columns.iter().map(|c| c.header().to_string()));
+
+    for card in cards {
+        let values: Vec<Vec<String>> = columns.iter().map(|c| c.values(card)).collect();
+        match multi_values {
+            CsvMultiValues::Join(separator) => {
+                write_row(&mut result, values.iter().map(|v| v.join(separator)));
+            }
+            CsvMultiValues::RepeatRow => {
+                let rows = values.iter().map(Vec::len).max().unwrap_or(0).max(1);
+                for row in 0..rows {
+                    write_row(
+                        &mut result,
+                        values.iter().zip(columns).map(|(v, column)| {
+                            let index = if is_multi_value(column) { row } else { 0 };
+                            v.get(index).cloned().unwrap_or_default()
+                        }),
+                    );
+                }
+            }
+        }
+    }
+    result
+}
+
+fn is_multi_value(column: &CsvColumn) -> bool {
+    matches!(column, CsvColumn::Emails | CsvColumn::Phones)
+}
+
+fn write_row(result: &mut String, fields: impl Iterator<Item = String>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            result.push(',');
+        }
+        if field.contains(&[',', '"', '\r', '\n'][..]) {
+            result.push('"');
+            result.push_str(&field.replace('"', "\"\""));
+            result.push('"');
+        } else {
+            result.push_str(&field);
+        }
+    }
+    result.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_to_csv() -> Result<(), VCardError> {
+        let cards = VCard::parse_all(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\n\
+            EMAIL;PREF=2:heinrich@example.com\r\n\
+            EMAIL;PREF=1:heinrich@tosafjord.example\r\n\
+            ADR:;;Fjordweg 1;Tosafjord;;12345;Norway\r\n\
+            NOTE:says \"hello\"\r\n\
+            END:VCARD\r\n\
+            BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Judith\r\nEND:VCARD\r\n",
+        )?;
+        let columns = [
+            CsvColumn::FullName,
+            CsvColumn::FirstEmail,
+            CsvColumn::Emails,
+            CsvColumn::FirstCity,
+            CsvColumn::Note,
+        ];
+
+        assert_eq!(
+            "Full Name,E-mail,E-mails,City,Note\r\n\
+            Heinrich vom Tosafjord,heinrich@tosafjord.example,heinrich@example.com | heinrich@tosafjord.example,Tosafjord,\"says \"\"hello\"\"\"\r\n\
+            Judith,,,,\r\n",
+            to_csv_with_options(&cards, &columns, &CsvMultiValues::Join(" | ".into()))
+        );
+        assert_eq!(
+            "Full Name,E-mail,E-mails,City,Note\r\n\
+            Heinrich vom Tosafjord,heinrich@tosafjord.example,heinrich@example.com,Tosafjord,\"says \"\"hello\"\"\"\r\n\
+            Heinrich vom Tosafjord,heinrich@tosafjord.example,heinrich@tosafjord.example,Tosafjord,\"says \"\"hello\"\"\"\r\n\
+            Judith,,,,\r\n",
+            to_csv_with_options(&cards, &columns, &CsvMultiValues::RepeatRow)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_csv_unescapes_text() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Smith\\, John\r\n\
+            NOTE:line1\\nline2\\; end\r\n\
+            END:VCARD\r\n",
+        )?;
+        assert_eq!(
+            "Full Name,Note\r\n\"Smith, John\",\"line1\nline2; end\"\r\n",
+            to_csv(&[card], &[CsvColumn::FullName, CsvColumn::Note])
+        );
+        Ok(())
+    }
+}
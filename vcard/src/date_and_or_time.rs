@@ -0,0 +1,466 @@
+use std::{
+    fmt::{self, Display},
+    ops::Range,
+    str::FromStr,
+};
+
+use crate::{Anniversary, BDay, Rev, VCardError, ValueDataType};
+
+/// A date, time, date-time or free text value of BDAY, ANNIVERSARY or REV,
+/// see https://datatracker.ietf.org/doc/html/rfc6350#section-4.3.4
+///
+/// Truncated values like `--0412` keep the missing components as `None`, so they are written exactly as they were read.
+/// The extended format of vCard 3.0 like `1985-04-12` is accepted as well, but written in the basic format.
+///
+/// ```
+/// use vcard::*;
+///
+/// let birthday: DateAndOrTime = "--0412".parse()?;
+/// assert_eq!(
+///     DateAndOrTime::Date(Date {
+///         year: None,
+///         month: Some(4),
+///         day: Some(12),
+///     }),
+///     birthday
+/// );
+/// assert_eq!("--0412", birthday.to_string());
+/// # Ok::<(), VCardError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateAndOrTime {
+    Date(Date),
+    DateTime(Date, Time),
+    Time(Time),
+    /// a free text value like `circa 1800`, given with `VALUE=text`
+    Text(String),
+}
+
+/// The date of a `DateAndOrTime`, missing components are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Date {
+    pub year: Option<u16>,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+}
+
+/// The time of a `DateAndOrTime`, missing components are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Time {
+    pub hour: Option<u8>,
+    pub minute: Option<u8>,
+    pub second: Option<u8>,
+    pub zone: Option<Zone>,
+}
+
+/// The time zone of a `Time`, either `Z` or an offset like `-0800` or `+01`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Utc,
+    Offset {
+        hours: u8,
+        minutes: Option<u8>,
+        negative: bool,
+    },
+}
+
+impl DateAndOrTime {
+    // `VALUE=text` gives `DateAndOrTime::Text`, all other values are parsed
+    fn parse(
+        value: &str,
+        value_data_type: Option<&ValueDataType>,
+        property: &str,
+    ) -> Result<Self, VCardError> {
+        if value_data_type == Some(&ValueDataType::Text) {
+            return Ok(Self::Text(value.to_string()));
+        }
+        let result = match value.split_once('T') {
+            Some(("", time)) => parse_time(time).map(Self::Time),
+            Some((date, time)) => parse_date(date)
+                .filter(|d| d.day.is_some())
+                .zip(parse_time(time).filter(|t| t.hour.is_some()))
+                .map(|(date, time)| Self::DateTime(date, time)),
+            None => parse_date(value).map(Self::Date),
+        };
+        result.ok_or_else(|| VCardError::InvalidSyntax {
+            message: format!(
+                "{} is not a date-and-or-time like 19850412, --0412 or T102200Z",
+                value
+            ),
+            property: property.into(),
+        })
+    }
+
+    pub fn date(&self) -> Option<&Date> {
+        match self {
+            Self::Date(date) | Self::DateTime(date, _) => Some(date),
+            _ => None,
+        }
+    }
+
+    pub fn time(&self) -> Option<&Time> {
+        match self {
+            Self::Time(time) | Self::DateTime(_, time) => Some(time),
+            _ => None,
+        }
+    }
+
+    /// returns the date as `chrono::NaiveDate`, `None` if the year, month or day is missing.
+    #[cfg(feature = "chrono")]
+    pub fn to_naive_date(&self) -> Option<chrono::NaiveDate> {
+        let date = self.date()?;
+        chrono::NaiveDate::from_ymd_opt(
+            i32::from(date.year?),
+            u32::from(date.month?),
+            u32::from(date.day?),
+        )
+    }
+
+    /// returns the date-time as `chrono::DateTime`, `None` unless date, time and zone are complete.
+    #[cfg(feature = "chrono")]
+    pub fn to_date_time(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        let time = self.time()?;
+        let seconds = match time.zone? {
+            Zone::Utc => 0,
+            Zone::Offset {
+                hours,
+                minutes,
+                negative,
+            } => {
+                let seconds = i32::from(hours) * 3600 + i32::from(minutes.unwrap_or(0)) * 60;
+                if negative {
+                    -seconds
+                } else {
+                    seconds
+                }
+            }
+        };
+        self.to_naive_date()?
+            .and_hms_opt(
+                u32::from(time.hour?),
+                u32::from(time.minute?),
+                u32::from(time.second?),
+            )?
+            .and_local_timezone(chrono::FixedOffset::east_opt(seconds)?)
+            .single()
+    }
+}
+
+impl FromStr for DateAndOrTime {
+    type Err = VCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, None, "DATE-AND-OR-TIME")
+    }
+}
+
+impl Display for DateAndOrTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Date(date) => write!(f, "{}", date),
+            Self::DateTime(date, time) => write!(f, "{}T{}", date, time),
+            Self::Time(time) => write!(f, "T{}", time),
+            Self::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.year, self.month, self.day) {
+            (Some(y), Some(m), Some(d)) => write!(f, "{:04}{:02}{:02}", y, m, d),
+            (Some(y), Some(m), None) => write!(f, "{:04}-{:02}", y, m),
+            (Some(y), None, _) => write!(f, "{:04}", y),
+            (None, Some(m), Some(d)) => write!(f, "--{:02}{:02}", m, d),
+            (None, Some(m), None) => write!(f, "--{:02}", m),
+            (None, None, Some(d)) => write!(f, "---{:02}", d),
+            (None, None, None) => Ok(()),
+        }
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.hour, self.minute, self.second) {
+            (Some(h), Some(m), Some(s)) => write!(f, "{:02}{:02}{:02}", h, m, s),
+            (Some(h), Some(m), None) => write!(f, "{:02}{:02}", h, m),
+            (Some(h), None, _) => write!(f, "{:02}", h),
+            (None, Some(m), Some(s)) => write!(f, "-{:02}{:02}", m, s),
+            (None, Some(m), None) => write!(f, "-{:02}", m),
+            (None, None, Some(s)) => write!(f, "--{:02}", s),
+            (None, None, None) => Ok(()),
+        }?;
+        match &self.zone {
+            Some(zone) => write!(f, "{}", zone),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Utc => write!(f, "Z"),
+            Self::Offset {
+                hours,
+                minutes,
+                negative,
+            } => {
+                write!(f, "{}{:02}", if *negative { '-' } else { '+' }, hours)?;
+                match minutes {
+                    Some(minutes) => write!(f, "{:02}", minutes),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl BDay {
+    /// parses the value, see `DateAndOrTime`.
+    pub fn date(&self) -> Result<DateAndOrTime, VCardError> {
+        DateAndOrTime::parse(&self.value, self.value_data_type.as_ref(), "BDAY")
+    }
+}
+
+impl Anniversary {
+    /// parses the value, see `DateAndOrTime`.
+    pub fn date(&self) -> Result<DateAndOrTime, VCardError> {
+        DateAndOrTime::parse(&self.value, self.value_data_type.as_ref(), "ANNIVERSARY")
+    }
+}
+
+impl Rev {
+    /// parses the timestamp, e.g. `19951031T222710Z`.
+    pub fn date(&self) -> Result<DateAndOrTime, VCardError> {
+        DateAndOrTime::parse(&self.value, None, "REV")
+    }
+}
+
+// parses dates like `19850412`, `1985-04`, `1985`, `--0412`, `--04` and `---12`,
+// or `1985-04-12` and `--04-12` in the extended format of vCard 3.0
+fn parse_date(value: &str) -> Option<Date> {
+    let bytes = value.as_bytes();
+    let value = if value.len() == 10 && bytes[4] == b'-' && bytes[7] == b'-' {
+        value.replace('-', "")
+    } else if value.len() == 7 && value.starts_with("--") && bytes[4] == b'-' {
+        format!("{}{}", &value[..4], &value[5..])
+    } else {
+        value.to_string()
+    };
+    let part = |range: Range<usize>| value.get(range).and_then(number);
+
+    let date = if value.len() == 5 && value.starts_with("---") {
+        Date {
+            year: None,
+            month: None,
+            day: Some(part(3..5)?),
+        }
+    } else if value.starts_with("--") && (value.len() == 4 || value.len() == 6) {
+        Date {
+            year: None,
+            month: Some(part(2..4)?),
+            day: if value.len() == 6 {
+                Some(part(4..6)?)
+            } else {
+                None
+            },
+        }
+    } else if value.len() == 7 && value.as_bytes()[4] == b'-' {
+        Date {
+            year: Some(number(value.get(..4)?)?),
+            month: Some(part(5..7)?),
+            day: None,
+        }
+    } else if value.len() == 4 || value.len() == 8 {
+        Date {
+            year: Some(number(value.get(..4)?)?),
+            month: if value.len() == 8 {
+                Some(part(4..6)?)
+            } else {
+                None
+            },
+            day: if value.len() == 8 {
+                Some(part(6..8)?)
+            } else {
+                None
+            },
+        }
+    } else {
+        return None;
+    };
+    let is_valid = date.month.is_none_or(|m| (1..=12).contains(&m))
+        && date.day.is_none_or(|d| (1..=31).contains(&d));
+    Some(date).filter(|_| is_valid)
+}
+
+// parses times like `102200`, `1022`, `10`, `-2200`, `-22` and `--00`, followed by an optional zone like `Z` or `-0800`
+fn parse_time(value: &str) -> Option<Time> {
+    let leading_dashes = value.len() - value.trim_start_matches('-').len();
+    let (time, zone) = match value.strip_suffix('Z') {
+        Some(time) => (time, Some(Zone::Utc)),
+        None => match value[leading_dashes..].find(['+', '-']) {
+            Some(i) => {
+                let (time, zone) = value.split_at(leading_dashes + i);
+                (time, Some(parse_zone(zone)?))
+            }
+            None => (value, None),
+        },
+    };
+    // the extended format `14:00:00` of vCard 3.0
+    let time = time.replace(':', "");
+    let part = |range: Range<usize>| time.get(range).and_then(number);
+
+    let (hour, minute, second) = match (leading_dashes, time.len()) {
+        (0, 2) => (Some(part(0..2)?), None, None),
+        (0, 4) => (Some(part(0..2)?), Some(part(2..4)?), None),
+        (0, 6) => (Some(part(0..2)?), Some(part(2..4)?), Some(part(4..6)?)),
+        (1, 3) => (None, Some(part(1..3)?), None),
+        (1, 5) => (None, Some(part(1..3)?), Some(part(3..5)?)),
+        (2, 4) => (None, None, Some(part(2..4)?)),
+        _ => return None,
+    };
+    let is_valid = hour.is_none_or(|h| h < 24)
+        && minute.is_none_or(|m| m < 60)
+        // 60 is a leap second
+        && second.is_none_or(|s| s <= 60);
+    Some(Time {
+        hour,
+        minute,
+        second,
+        zone,
+    })
+    .filter(|_| is_valid)
+}
+
+// parses offsets like `-0800`, `+01` and `+01:00`
+fn parse_zone(value: &str) -> Option<Zone> {
+    let digits = value.get(1..)?.replace(':', "");
+    let (hours, minutes) = match digits.len() {
+        2 => (number(&digits)?, None),
+        4 => (number(digits.get(..2)?)?, Some(number(digits.get(2..)?)?)),
+        _ => return None,
+    };
+    if hours > 23 || minutes.is_some_and(|m| m > 59) {
+        return None;
+    }
+    Some(Zone::Offset {
+        hours,
+        minutes,
+        negative: value.starts_with('-'),
+    })
+}
+
+fn number<T: FromStr>(value: &str) -> Option<T> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_date_and_or_time() -> Result<(), VCardError> {
+        for value in [
+            "20180301",
+            "1985-04",
+            "1985",
+            "--0412",
+            "--04",
+            "---12",
+            "19961022T140000Z",
+            "19961022T140000-0800",
+            "--0412T1022+01",
+            "---12T10",
+            "T102200",
+            "T1022-0800",
+            "T-2200",
+            "T-22",
+            "T--00Z",
+        ] {
+            let parsed: DateAndOrTime = value.parse()?;
+            assert_eq!(value, parsed.to_string());
+        }
+
+        // the extended format of vCard 3.0
+        let parsed: DateAndOrTime = "1953-10-15T23:10:00Z".parse()?;
+        assert_eq!("19531015T231000Z", parsed.to_string());
+        assert_eq!("--0412", "--04-12".parse::<DateAndOrTime>()?.to_string());
+
+        for invalid in [
+            "",
+            "1985041",
+            "19851312",
+            "--0432",
+            "1985-04T10",
+            "T25",
+            "T1022+1",
+            "19961022T",
+            "circa 1800",
+        ] {
+            assert!(invalid.parse::<DateAndOrTime>().is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bday_date() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            BDAY:20180301\r\n\
+            ANNIVERSARY:--0412\r\n\
+            REV:19961022T140000Z\r\n\
+            END:VCARD\r\n",
+        )?;
+        assert_eq!(
+            DateAndOrTime::Date(Date {
+                year: Some(2018),
+                month: Some(3),
+                day: Some(1),
+            }),
+            card.bday.values()[0].date()?
+        );
+        // a birthday without year
+        let anniversary = card.anniversary.values()[0].date()?;
+        assert_eq!(None, anniversary.date().unwrap().year);
+        assert_eq!(Some(12), anniversary.date().unwrap().day);
+        assert_eq!(
+            Some(Zone::Utc),
+            card.rev.as_ref().unwrap().date()?.time().unwrap().zone
+        );
+        assert!(card.to_string().contains("ANNIVERSARY:--0412\r\n"));
+
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            BDAY;VALUE=text:circa 1800\r\nEND:VCARD\r\n",
+        )?;
+        assert_eq!(
+            DateAndOrTime::Text("circa 1800".into()),
+            card.bday.values()[0].date()?
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_conversions() -> Result<(), VCardError> {
+        let date: DateAndOrTime = "20180301".parse()?;
+        assert_eq!(
+            chrono::NaiveDate::from_ymd_opt(2018, 3, 1),
+            date.to_naive_date()
+        );
+        assert_eq!(None, "--0412".parse::<DateAndOrTime>()?.to_naive_date());
+
+        let timestamp: DateAndOrTime = "19961022T140000-0800".parse()?;
+        let date_time = timestamp.to_date_time().unwrap();
+        assert_eq!(-8 * 3600, date_time.offset().local_minus_utc());
+        assert_eq!(
+            chrono::NaiveDate::from_ymd_opt(1996, 10, 22).and_then(|d| d.and_hms_opt(22, 0, 0)),
+            Some(date_time.naive_utc())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{AltIDContainer, Alternative, MultiAltIDContainer, Property, VCard};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A single changed property between two vcards, see `diff`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PropertyDiff {
+    /// name of the property in uppercase, e.g. `EMAIL`. Proprietary properties keep their name as it is.
+    pub name: String,
+    pub old_value: Option<Property>,
+    pub new_value: Option<Property>,
+    pub kind: ChangeKind,
+}
+
+/// Controls which properties are compared by `diff_with_options`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct DiffOptions {
+    pub include_rev: bool,
+    pub include_prodid: bool,
+}
+
+/// Returns all changed properties between `before` and `after`, ignoring REV and PRODID.
+/// See `diff_with_options`.
+pub fn diff(before: &VCard, after: &VCard) -> Vec<PropertyDiff> {
+    diff_with_options(before, after, DiffOptions::default())
+}
+
+/// Returns all changed properties between `before` and `after`.
+///
+/// The result is ordered by the fields of `VCard`. Properties with a cardinality of `*` (EMAIL, TEL, ...)
+/// are compared as sets, so a changed entry is reported as `Removed` followed by `Added`.
+/// All other properties are compared position by position and reported as `Modified` if they differ.
+pub fn diff_with_options(before: &VCard, after: &VCard, options: DiffOptions) -> Vec<PropertyDiff> {
+    let mut result = Vec::new();
+
+    macro_rules! single {
+        ($field:ident, $variant:ident) => {
+            diff_single(
+                &mut result,
+                properties(&before.$field, Property::$variant),
+                properties(&after.$field, Property::$variant),
+            )
+        };
+    }
+    macro_rules! alternatives {
+        ($field:ident, $variant:ident) => {
+            diff_single(
+                &mut result,
+                properties(before.$field.values(), Property::$variant),
+                properties(after.$field.values(), Property::$variant),
+            )
+        };
+    }
+    macro_rules! multi {
+        ($field:ident, $variant:ident) => {
+            diff_multi(
+                &mut result,
+                sorted_values(&before.$field)
+                    .map(Property::$variant)
+                    .collect(),
+                sorted_values(&after.$field)
+                    .map(Property::$variant)
+                    .collect(),
+            )
+        };
+    }
+    macro_rules! list {
+        ($field:ident, $variant:ident) => {
+            diff_multi(
+                &mut result,
+                properties(&before.$field, Property::$variant),
+                properties(&after.$field, Property::$variant),
+            )
+        };
+    }
+
+    diff_single(
+        &mut result,
+        vec![Property::Version(before.version.clone())],
+        vec![Property::Version(after.version.clone())],
+    );
+    multi!(source, Source);
+    single!(kind, Kind);
+    multi!(xml, Xml);
+    multi!(fn_property, FN);
+    alternatives!(n, N);
+    multi!(nickname, NickName);
+    multi!(photo, Photo);
+    alternatives!(bday, BDay);
+    alternatives!(anniversary, Anniversary);
+    single!(gender, Gender);
+    multi!(adr, Adr);
+    multi!(tel, Tel);
+    multi!(email, Email);
+    multi!(impp, Impp);
+    multi!(lang, Lang);
+    multi!(tz, Tz);
+    multi!(geo, Geo);
+    multi!(title, Title);
+    multi!(role, Role);
+    multi!(logo, Logo);
+    multi!(org, Org);
+    multi!(member, Member);
+    multi!(related, Related);
+    multi!(categories, Categories);
+    multi!(note, Note);
+    if options.include_prodid {
+        single!(prodid, ProdId);
+    }
+    if options.include_rev {
+        single!(rev, Rev);
+    }
+    multi!(sound, Sound);
+    single!(uid, Uid);
+    list!(clientpidmap, ClientPidMap);
+    multi!(url, Url);
+    multi!(key, Key);
+    multi!(fburl, FbUrl);
+    multi!(caluri, CalUri);
+    multi!(caladuri, CalAdUri);
+    list!(legacy, Legacy);
+    list!(proprietary_properties, Proprietary);
+
+    result
+}
+
+fn properties<'a, T: Clone + 'a>(
+    values: impl IntoIterator<Item = &'a T>,
+    variant: fn(T) -> Property,
+) -> Vec<Property> {
+    values.into_iter().cloned().map(variant).collect()
+}
+
+// returns all values of the container ordered by their altid
+fn sorted_values<T>(container: &MultiAltIDContainer<T>) -> impl Iterator<Item = T> + '_
+where
+    T: Alternative + PartialEq + Clone + std::fmt::Debug,
+{
+    let mut containers: Vec<(&str, &AltIDContainer<T>)> = container.values().collect();
+    containers.sort_by_key(|(altid, _)| *altid);
+    containers
+        .into_iter()
+        .flat_map(|(_, c)| c.values().iter().cloned())
+}
+
+fn property_name(property: &Property) -> String {
+    match property {
+        Property::Legacy(p) => p.name.as_ref().into(),
+        Property::Proprietary(p) => p.name.clone(),
+        p => p.as_ref().to_uppercase(),
+    }
+}
+
+fn diff_single(result: &mut Vec<PropertyDiff>, before: Vec<Property>, after: Vec<Property>) {
+    let len = before.len().max(after.len());
+    let mut before = before.into_iter();
+    let mut after = after.into_iter();
+    for _ in 0..len {
+        let (old_value, new_value) = (before.next(), after.next());
+        let kind = match (&old_value, &new_value) {
+            (Some(old), Some(new)) if old == new => continue,
+            (Some(_), Some(_)) => ChangeKind::Modified,
+            (Some(_), None) => ChangeKind::Removed,
+            _ => ChangeKind::Added,
+        };
+        let name = property_name(old_value.as_ref().or(new_value.as_ref()).unwrap());
+        result.push(PropertyDiff {
+            name,
+            old_value,
+            new_value,
+            kind,
+        });
+    }
+}
+
+fn diff_multi(result: &mut Vec<PropertyDiff>, before: Vec<Property>, after: Vec<Property>) {
+    for old_value in before.iter().filter(|p| !after.contains(p)) {
+        result.push(PropertyDiff {
+            name: property_name(old_value),
+            old_value: Some(old_value.clone()),
+            new_value: None,
+            kind: ChangeKind::Removed,
+        });
+    }
+    for new_value in after.iter().filter(|p| !before.contains(p)) {
+        result.push(PropertyDiff {
+            name: property_name(new_value),
+            old_value: None,
+            new_value: Some(new_value.clone()),
+            kind: ChangeKind::Added,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_diff() -> Result<(), Box<dyn std::error::Error>> {
+        let before = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .email(Email {
+                value: "heinrich@example.com".into(),
+                ..Default::default()
+            })
+            .gender(Gender {
+                sex: Some(Sex::Male),
+                ..Default::default()
+            })
+            .rev(Rev {
+                value: "20220101T120000Z".into(),
+                ..Default::default()
+            })
+            .build()
+            .map_err(VCardError::from)?;
+
+        let after = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .email(Email {
+                value: "heinrich@tosafjord.example".into(),
+                ..Default::default()
+            })
+            .gender(Gender {
+                sex: Some(Sex::Other),
+                ..Default::default()
+            })
+            .note(Note {
+                value: "likes cats".into(),
+                ..Default::default()
+            })
+            .rev(Rev {
+                value: "20230101T120000Z".into(),
+                ..Default::default()
+            })
+            .build()
+            .map_err(VCardError::from)?;
+
+        assert!(diff(&before, &before).is_empty());
+
+        let changes: Vec<(String, ChangeKind)> = diff(&before, &after)
+            .into_iter()
+            .map(|d| (d.name, d.kind))
+            .collect();
+        assert_eq!(
+            vec![
+                ("GENDER".to_string(), ChangeKind::Modified),
+                ("EMAIL".to_string(), ChangeKind::Removed),
+                ("EMAIL".to_string(), ChangeKind::Added),
+                ("NOTE".to_string(), ChangeKind::Added),
+            ],
+            changes
+        );
+
+        let options = DiffOptions {
+            include_rev: true,
+            ..Default::default()
+        };
+        let changes = diff_with_options(&before, &after, options);
+        assert_eq!(5, changes.len());
+        assert_eq!(
+            Some(Property::Rev(Rev {
+                value: "20230101T120000Z".into(),
+                ..Default::default()
+            })),
+            changes[4].new_value
+        );
+
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use std::{io, str::Utf8Error, string::FromUtf8Error};
 use thiserror::Error;
 
+use crate::VCardValidationError;
+
 #[derive(Error, Debug)]
 #[non_exhaustive]
 pub enum VCardError {
@@ -8,6 +10,8 @@ pub enum VCardError {
     Io(#[from] io::Error),
     #[error(transparent)]
     ParseIntError(#[from] std::num::ParseIntError),
+    #[error(transparent)]
+    UrlParseError(#[from] url::ParseError),
 
     #[error(transparent)]
     FromUTF8Error(#[from] FromUtf8Error),
@@ -38,7 +42,7 @@ pub enum VCardError {
     #[error("Invalid PID parameter. Expected parameter to have the form digit[.digit] (e.g: 1 or 1.2) but got {provided}")]
     InvalidPID { provided: String },
 
-    #[error("Invalid version {0}, only version 3.0 and 4.0 are valid")]
+    #[error("Invalid version {0}, only version 2.1, 3.0 and 4.0 are valid")]
     InvalidVersion(String),
 
     #[error("Invalid gender {0}, expected one of (m,f,o,n,u)")]
@@ -50,10 +54,15 @@ pub enum VCardError {
     #[error("Exceeded maximum logical line length of {0}")]
     MaxLineLengthExceeded(u64),
 
+    #[error("Exceeded maximum number of {0} properties per vcard")]
+    MaxPropertiesExceeded(u64),
+
     #[error("first property of a vcard must be BEGIN:VCARD")]
     InvalidBeginProperty,
 
-    #[error("second property of a vcard must be VERSION:3.0 or VERSION:4.0")]
+    /// VERSION is missing or not among the first properties. `VCardReader` looks for it within the first
+    /// `VCardReader::version_search_depth` properties after BEGIN:VCARD, other parsers require it right after BEGIN:VCARD.
+    #[error("VERSION:3.0 or VERSION:4.0 must follow BEGIN:VCARD within the first properties of a vcard, see VCardReader::version_search_depth")]
     InvalidVersionProperty,
 
     #[error("last property of a vcard must be END:VCARD")]
@@ -69,4 +78,66 @@ pub enum VCardError {
     },
     #[error("invalid syntax for property {property}: {message}")]
     InvalidSyntax { message: String, property: String },
+
+    #[error("invalid jCard: {reason} - value is {value}")]
+    InvalidJCard { reason: &'static str, value: String },
+
+    #[error("invalid vcard: {}", display_validation_errors(.0))]
+    InvalidVCard(Vec<VCardValidationError>),
+
+    /// an error of `VCardReader` with the position of the property that caused it, see `VCardError::kind`.
+    #[error("line {line} (byte offset {byte_offset}): {source}")]
+    At {
+        line: u64,
+        byte_offset: u64,
+        source: Box<VCardError>,
+    },
+}
+
+impl VCardError {
+    /// returns the line number and byte offset of the property that caused this error.
+    ///
+    /// Only errors of `VCardReader` have a position, see `VCardError::At`.
+    pub fn position(&self) -> Option<(u64, u64)> {
+        match self {
+            Self::At {
+                line, byte_offset, ..
+            } => Some((*line, *byte_offset)),
+            _ => None,
+        }
+    }
+
+    /// returns the error without its position.
+    ///
+    /// All errors of `VCardReader` are wrapped in `VCardError::At`, so match on the kind of the error instead
+    /// of the error itself:
+    ///
+    /// ```
+    /// use vcard::{VCardError, VCardReader};
+    ///
+    /// let e = VCardReader::new("BEGIN:VCARD\r\nFN:Heinrich\r\nEND:VCARD\r\n".as_bytes())
+    ///     .parse_vcard()
+    ///     .unwrap_err();
+    /// assert!(matches!(e.kind(), VCardError::InvalidVersionProperty));
+    /// ```
+    pub fn kind(&self) -> &VCardError {
+        match self {
+            Self::At { source, .. } => source.kind(),
+            _ => self,
+        }
+    }
+}
+
+impl From<Vec<VCardValidationError>> for VCardError {
+    fn from(errors: Vec<VCardValidationError>) -> Self {
+        Self::InvalidVCard(errors)
+    }
+}
+
+fn display_validation_errors(errors: &[VCardValidationError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
 }
@@ -0,0 +1,54 @@
+// escapes a text value, see https://datatracker.ietf.org/doc/html/rfc6350#section-3.4
+pub(crate) fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+}
+
+// splits `value` at every `separator` that is not escaped. Escape sequences are kept, see `unescape`.
+pub(crate) fn split_unescaped(value: &str, separator: char) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == separator {
+            result.push(&value[start..i]);
+            start = i + 1;
+        }
+    }
+    result.push(&value[start..]);
+    result
+}
+
+// resolves the escape sequences of a text value, see https://datatracker.ietf.org/doc/html/rfc6350#section-3.4
+pub(crate) fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+// escapes all values and joins them with `separator`, e.g. the components of ORG
+pub(crate) fn join_escaped(values: &[String], separator: char) -> String {
+    values
+        .iter()
+        .map(|v| escape(v))
+        .collect::<Vec<_>>()
+        .join(&separator.to_string())
+}
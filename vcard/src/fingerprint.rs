@@ -0,0 +1,268 @@
+use crate::{escape::split_unescaped, ContentLine, VCard};
+
+// properties that are never part of the canonical representation
+const FRAME_PROPERTIES: &[&str] = &["BEGIN", "END", "VERSION"];
+
+/// Controls which properties are part of the canonical representation, see `VCard::to_canonical_string_with_options`.
+///
+/// REV and PRODID change without changing the content of a vcard, so they are left out by default.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct CanonicalOptions {
+    pub include_rev: bool,
+    pub include_prodid: bool,
+}
+
+impl VCard {
+    /// Returns a representation of this vcard that only depends on its content, leaving out REV and PRODID.
+    /// See `to_canonical_string_with_options`.
+    pub fn to_canonical_string(&self) -> String {
+        self.to_canonical_string_with_options(CanonicalOptions::default())
+    }
+
+    /// Returns a representation of this vcard that only depends on its content.
+    ///
+    /// The content lines of a normalized copy of this vcard are sorted, so two vcards that differ only in
+    /// the order of their properties or in their formatting, e.g. `TYPE=home,voice` and `TYPE=VOICE;TYPE=HOME`,
+    /// have the same canonical string. See `VCard::normalize`. The result is a valid vcard itself.
+    pub fn to_canonical_string_with_options(&self, options: CanonicalOptions) -> String {
+        let is_ignored = |name: &str| {
+            FRAME_PROPERTIES
+                .iter()
+                .any(|n| name.eq_ignore_ascii_case(n))
+                || (!options.include_rev && name.eq_ignore_ascii_case("REV"))
+                || (!options.include_prodid && name.eq_ignore_ascii_case("PRODID"))
+        };
+        let mut card = self.clone();
+        card.normalize();
+        let raw = card.to_string();
+        let mut lines: Vec<&str> = raw
+            .split_terminator("\r\n")
+            .filter(|raw_line| match ContentLine::parse(raw_line) {
+                Ok(line) => !is_ignored(line.name),
+                Err(_) => true,
+            })
+            .collect();
+        lines.sort_unstable();
+
+        let mut result = format!("BEGIN:VCARD\r\nVERSION:{}\r\n", self.version.value.as_ref());
+        for line in lines {
+            result.push_str(line);
+            result.push_str("\r\n");
+        }
+        result.push_str("END:VCARD\r\n");
+        result
+    }
+
+    /// Returns a hash of the content of this vcard, leaving out REV and PRODID.
+    /// See `content_hash_with_options`.
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash_with_options(CanonicalOptions::default())
+    }
+
+    /// Returns the 64-bit FNV-1a hash of `to_canonical_string_with_options`, e.g. to decide whether
+    /// a vcard has to be uploaded to a CardDAV server.
+    ///
+    /// The hash does not depend on the order of the properties and is the same on all platforms
+    /// and in all runs of a program, so it can be stored. It may change with a new version of this
+    /// crate if the serialization of a property changes. Use `fingerprint` for a cryptographic hash.
+    pub fn content_hash_with_options(&self, options: CanonicalOptions) -> u64 {
+        // see http://www.isthe.com/chongo/tech/comp/fnv/
+        self.to_canonical_string_with_options(options)
+            .bytes()
+            .fold(0xcbf29ce484222325, |hash, b| {
+                (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+            })
+    }
+
+    /// Returns true if this vcard and `other` have the same content, leaving out REV and PRODID,
+    /// e.g. to decide whether a contact has changed during a sync.
+    ///
+    /// Unlike `PartialEq`, the order of properties is not significant and whitespace in values is
+    /// normalized: leading and trailing whitespace of each component is ignored and runs of whitespace
+    /// count as a single space, because clients pad values differently.
+    pub fn eq_semantic(&self, other: &VCard) -> bool {
+        semantic_lines(self) == semantic_lines(other)
+    }
+
+    /// Returns the SHA-256 hash of `to_canonical_string`, e.g. to detect duplicate vcards.
+    #[cfg(feature = "sha2")]
+    pub fn fingerprint(&self) -> [u8; 32] {
+        use sha2::{Digest, Sha256};
+
+        Sha256::digest(self.to_canonical_string().as_bytes()).into()
+    }
+}
+
+// returns the sorted content lines of the canonical string with normalized whitespace in their values
+fn semantic_lines(card: &VCard) -> Vec<String> {
+    let raw = card.to_canonical_string();
+    let mut lines: Vec<String> = raw
+        .split_terminator("\r\n")
+        .map(|raw_line| match ContentLine::parse(raw_line) {
+            Ok(line) => {
+                let value: Vec<String> = split_unescaped(line.value, ';')
+                    .into_iter()
+                    .map(|component| component.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .collect();
+                let params = &raw_line[..raw_line.len() - line.value.len()];
+                format!("{}{}", params, value.join(";"))
+            }
+            Err(_) => raw_line.to_string(),
+        })
+        .collect();
+    lines.sort_unstable();
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_to_canonical_string() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            EMAIL;TYPE=work:work@example.com\r\n\
+            EMAIL;TYPE=home:home@example.com\r\n\
+            TEL;VALUE=uri:tel:+49-176-1010-1520\r\n\
+            REV:20211029T075016Z\r\n\
+            PRODID:-//Example//Example 1.0//EN\r\n\
+            END:VCARD\r\n",
+        )?;
+        let reordered = VCard::parse(
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            REV:20220101T000000Z\r\n\
+            TEL;VALUE=uri:tel:+49-176-1010-1520\r\n\
+            EMAIL;TYPE=home:home@example.com\r\n\
+            EMAIL;TYPE=work:work@example.com\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            END:VCARD\r\n",
+        )?;
+
+        assert_eq!(
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            EMAIL;TYPE=home:home@example.com\r\n\
+            EMAIL;TYPE=work:work@example.com\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            TEL;VALUE=uri:tel:+4917610101520\r\n\
+            END:VCARD\r\n",
+            card.to_canonical_string()
+        );
+        assert_eq!(card.to_canonical_string(), reordered.to_canonical_string());
+        let canonical = card.to_canonical_string();
+        assert_eq!(canonical, VCard::parse(&canonical)?.to_canonical_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_hash() -> Result<(), VCardError> {
+        let raw = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/good_vcards/google_2.vcf"
+        ));
+        let card = VCard::parse(raw)?;
+        assert_eq!(card.content_hash(), VCard::parse(raw)?.content_hash());
+
+        let mut changed_rev = card.clone();
+        changed_rev.set_rev(Rev {
+            value: "20220101T000000Z".into(),
+            ..Default::default()
+        });
+        assert_eq!(card.content_hash(), changed_rev.content_hash());
+        let options = CanonicalOptions {
+            include_rev: true,
+            ..Default::default()
+        };
+        assert_ne!(
+            card.content_hash_with_options(options),
+            changed_rev.content_hash_with_options(options)
+        );
+
+        let mut changed_tel = card.clone();
+        changed_tel.set_tel(Tel {
+            value: "tel:+49-123-456789".into(),
+            ..Default::default()
+        });
+        assert_ne!(card.content_hash(), changed_tel.content_hash());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_eq_semantic() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            N:vom Tosafjord;Heinrich;;;\r\n\
+            NOTE:met at the fjord\r\n\
+            REV:20211029T075016Z\r\n\
+            PRODID:-//Example//Example 1.0//EN\r\n\
+            END:VCARD\r\n",
+        )?;
+        let mut changed_rev = card.clone();
+        changed_rev.rev = Some(Rev {
+            value: "20220101T000000Z".into(),
+            ..Default::default()
+        });
+        changed_rev.prodid = None;
+        assert_ne!(card, changed_rev);
+        assert!(card.eq_semantic(&changed_rev));
+
+        let padded = VCard::parse(
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            NOTE:met at  the fjord \r\n\
+            N: vom Tosafjord ;Heinrich;;;\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            END:VCARD\r\n",
+        )?;
+        assert!(card.eq_semantic(&padded));
+
+        let mut changed_fn = card.clone();
+        changed_fn.fn_property.clear();
+        assert!(!card.eq_semantic(&changed_fn));
+        Ok(())
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_fingerprint() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            REV:20211029T075016Z\r\n\
+            END:VCARD\r\n",
+        )?;
+        let mut changed_rev = card.clone();
+        changed_rev.rev = Some(Rev {
+            value: "20220101T000000Z".into(),
+            ..Default::default()
+        });
+        assert_eq!(card.fingerprint(), changed_rev.fingerprint());
+
+        let mut changed_fn = card.clone();
+        changed_fn.fn_property.clear();
+        assert_ne!(card.fingerprint(), changed_fn.fingerprint());
+
+        // the vcards are normalized, so the formatting of TYPE does not matter
+        let tel = |line: &str| {
+            VCard::parse(&format!(
+                "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n{}\r\nEND:VCARD\r\n",
+                line
+            ))
+        };
+        let google = tel("TEL;TYPE=home,voice:+49 176 1010-1520")?;
+        let apple = tel("TEL;TYPE=VOICE;TYPE=HOME:+49-176-10101520")?;
+        assert_eq!(google.content_hash(), apple.content_hash());
+        assert_eq!(google.fingerprint(), apple.fingerprint());
+
+        Ok(())
+    }
+}
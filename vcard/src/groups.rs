@@ -0,0 +1,313 @@
+use crate::{
+    escape::{escape, unescape},
+    *,
+};
+
+macro_rules! property_ref {
+    ($($variant:ident($ty:ty)),* $(,)?) => {
+        /// A reference to a property of a `VCard` that can be part of a group, see `VCard::properties_in_group`.
+        #[derive(Debug, PartialEq, Clone, Copy)]
+        pub enum PropertyRef<'a> {
+            $($variant(&'a $ty),)*
+        }
+
+        impl<'a> PropertyRef<'a> {
+            /// returns the group of the referenced property.
+            pub fn group(&self) -> Option<&'a str> {
+                self.as_grouped().get_group()
+            }
+
+            /// returns the referenced property as trait object.
+            pub fn as_grouped(&self) -> &'a dyn GroupedProperty {
+                match *self {
+                    $(Self::$variant(p) => p,)*
+                }
+            }
+        }
+
+        $(
+        impl<'a> From<&'a $ty> for PropertyRef<'a> {
+            fn from(p: &'a $ty) -> Self {
+                Self::$variant(p)
+            }
+        }
+        )*
+    };
+}
+
+property_ref!(
+    Source(Source),
+    Kind(Kind),
+    Xml(Xml),
+    FN(FN),
+    N(N),
+    NickName(Nickname),
+    Photo(Photo),
+    BDay(BDay),
+    Adr(Adr),
+    Tel(Tel),
+    Email(Email),
+    Impp(Impp),
+    Lang(Lang),
+    Tz(Tz),
+    Geo(Geo),
+    Title(Title),
+    Role(Role),
+    Logo(Logo),
+    Org(Org),
+    Member(Member),
+    Related(Related),
+    Categories(Categories),
+    Note(Note),
+    ProdId(ProdId),
+    Rev(Rev),
+    Sound(Sound),
+    Uid(Uid),
+    ClientPidMap(ClientPidMap),
+    Url(Url),
+    Key(Key),
+    FbUrl(FbURL),
+    CalUri(CalURI),
+    CalAdUri(CalAdURI),
+    Legacy(LegacyProperty),
+    Proprietary(ProprietaryProperty),
+);
+
+// collects the properties of a field of `VCard`, see `#[derive(GroupedProperties)]`
+pub(crate) trait GroupedFields {
+    fn collect_grouped<'a>(&'a self, result: &mut Vec<PropertyRef<'a>>);
+}
+
+impl<T> GroupedFields for MultiAltIDContainer<T>
+where
+    T: Alternative + PartialEq + std::fmt::Debug,
+    for<'a> PropertyRef<'a>: From<&'a T>,
+{
+    fn collect_grouped<'a>(&'a self, result: &mut Vec<PropertyRef<'a>>) {
+        result.extend(self.iter().map(PropertyRef::from));
+    }
+}
+
+impl<T> GroupedFields for AltIDContainer<T>
+where
+    T: Alternative + std::fmt::Debug,
+    for<'a> PropertyRef<'a>: From<&'a T>,
+{
+    fn collect_grouped<'a>(&'a self, result: &mut Vec<PropertyRef<'a>>) {
+        result.extend(self.iter().map(PropertyRef::from));
+    }
+}
+
+impl<T> GroupedFields for Option<T>
+where
+    for<'a> PropertyRef<'a>: From<&'a T>,
+{
+    fn collect_grouped<'a>(&'a self, result: &mut Vec<PropertyRef<'a>>) {
+        result.extend(self.iter().map(PropertyRef::from));
+    }
+}
+
+impl<T> GroupedFields for Vec<T>
+where
+    for<'a> PropertyRef<'a>: From<&'a T>,
+{
+    fn collect_grouped<'a>(&'a self, result: &mut Vec<PropertyRef<'a>>) {
+        result.extend(self.iter().map(PropertyRef::from));
+    }
+}
+
+impl VCard {
+    /// returns all properties that belong to `group`, e.g. `item1` for `item1.ADR` and `item1.X-ABADR`.
+    ///
+    /// Group names are compared case-insensitively.
+    pub fn properties_in_group<'a>(&'a self, group: &str) -> Vec<PropertyRef<'a>> {
+        self.grouped_properties()
+            .into_iter()
+            .filter(|p| p.group().is_some_and(|g| g.eq_ignore_ascii_case(group)))
+            .collect()
+    }
+
+    /// returns all properties that belong to `group` as trait objects, see `properties_in_group`.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let card = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+    ///     item1.EMAIL:heinrich@example.com\r\n\
+    ///     item1.X-ABLABEL:Private\r\n\
+    ///     END:VCARD\r\n")?;
+    /// let lines: Vec<String> = card
+    ///     .properties_by_group("item1")
+    ///     .iter()
+    ///     .map(|p| p.to_string())
+    ///     .collect();
+    /// assert_eq!(
+    ///     vec!["item1.EMAIL:heinrich@example.com\r\n", "item1.X-ABLABEL:Private\r\n"],
+    ///     lines
+    /// );
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    pub fn properties_by_group<'a>(&'a self, group: &str) -> Vec<&'a dyn GroupedProperty> {
+        self.properties_in_group(group)
+            .iter()
+            .map(PropertyRef::as_grouped)
+            .collect()
+    }
+
+    /// returns the label that Apple and Google attach to the properties of `group` with an `X-ABLABEL` property,
+    /// e.g. `Blog` for `item1.X-ABLABEL:Blog`.
+    ///
+    /// Predefined labels like `_$!<HomePage>!$_` are returned without their wrapper, i.e. `HomePage`.
+    /// The label is unescaped, so it is the counterpart of `add_item_label`.
+    /// Group names are compared case-insensitively.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let card = VCard::parse("BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Heinrich\r\n\
+    ///     item1.URL:https://www.example.com/heinrich\r\n\
+    ///     item1.X-ABLabel:_$!<HomePage>!$_\r\n\
+    ///     END:VCARD\r\n")?;
+    /// assert_eq!(Some("HomePage".to_string()), card.label_for_group("item1"));
+    /// let url = card.url.iter().next().unwrap();
+    /// assert_eq!(Some("HomePage".to_string()), url.custom_label(&card));
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    pub fn label_for_group(&self, group: &str) -> Option<String> {
+        let label = self.proprietary_properties.iter().find(|p| {
+            p.name.eq_ignore_ascii_case("X-ABLABEL")
+                && p.group
+                    .as_deref()
+                    .is_some_and(|g| g.eq_ignore_ascii_case(group))
+        })?;
+        let value = label.value.as_str();
+        let value = value
+            .strip_prefix("_$!<")
+            .and_then(|v| v.strip_suffix(">!$_"))
+            .unwrap_or(value);
+        Some(unescape(value))
+    }
+
+    /// returns the first group name `itemN` that is not used by this vcard, starting with `item1`.
+    pub fn next_item_group(&self) -> String {
+        let groups = self.groups();
+        (1..)
+            .map(|n| format!("item{}", n))
+            .find(|name| !groups.iter().any(|g| g.eq_ignore_ascii_case(name)))
+            .unwrap_or_default()
+    }
+
+    /// adds an `X-ABLABEL` property with `label` in a new group, see `next_item_group`, and returns the group name.
+    /// Properties that are added with this group afterwards are labeled with `label`.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let mut card = VCard::parse("BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Heinrich\r\nEND:VCARD\r\n")?;
+    /// let group = card.add_item_label("Blog");
+    /// card.add_url(Url {
+    ///     group: Some(group),
+    ///     value: "https://blog.example.com".parse().unwrap(),
+    ///     ..Default::default()
+    /// });
+    /// let url = card.url.iter().next().unwrap();
+    /// assert_eq!(Some("item1"), url.group.as_deref());
+    /// assert_eq!(Some("Blog".to_string()), url.custom_label(&card));
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    pub fn add_item_label(&mut self, label: &str) -> String {
+        let group = self.next_item_group();
+        self.proprietary_properties.push(ProprietaryProperty {
+            name: "X-ABLABEL".into(),
+            group: Some(group.clone()),
+            value: escape(label),
+            parameters: Vec::new(),
+        });
+        group
+    }
+
+    /// returns all distinct group names of this vcard in alphabetical order.
+    pub fn groups(&self) -> Vec<&str> {
+        let mut groups: Vec<&str> = self
+            .grouped_properties()
+            .into_iter()
+            .filter_map(|p| p.group())
+            .collect();
+        groups.sort_unstable();
+        groups.dedup();
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_properties_in_group() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/apple_icloud.vcf",
+        ));
+        let card = VCardReader::new(&testant[..]).parse_vcard()?;
+
+        assert_eq!(vec!["item1", "item2", "item3"], card.groups());
+
+        let item1 = card.properties_in_group("ITEM1");
+        assert_eq!(2, item1.len());
+        assert!(matches!(item1[0], PropertyRef::Adr(_)));
+        match item1[1] {
+            PropertyRef::Proprietary(p) => assert_eq!("X-ABADR", p.name),
+            p => panic!("expected X-ABADR but got {:?}", p),
+        }
+
+        let item3 = card.properties_in_group("item3");
+        assert!(matches!(item3[0], PropertyRef::Tel(_)));
+        assert_eq!(
+            "item3.X-ABLABEL:_$!<Mobile>!$_\r\n",
+            item3[1].as_grouped().to_string()
+        );
+
+        assert!(card.properties_in_group("item4").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_item_labels() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/apple_icloud.vcf",
+        ));
+        let mut card = VCardReader::new(&testant[..]).parse_vcard()?;
+
+        assert_eq!(Some("HomePage".into()), card.label_for_group("ITEM2"));
+        let tel = card.tel.iter().next().unwrap();
+        assert_eq!(Some("Mobile".into()), tel.custom_label(&card));
+        // X-ABADR is no label
+        assert_eq!(None, card.adr.iter().next().unwrap().custom_label(&card));
+        assert_eq!(None, card.email.iter().next().unwrap().custom_label(&card));
+
+        assert_eq!("item4", card.next_item_group());
+        let group = card.add_item_label("Katze, Hund");
+        assert_eq!("item4", group);
+        card.add_email(Email {
+            group: Some(group),
+            value: "katze@example.com".into(),
+            ..Default::default()
+        });
+        assert_eq!("item5", card.next_item_group());
+
+        let raw = card.to_string();
+        assert!(raw.contains("\r\nitem4.X-ABLABEL:Katze\\, Hund\r\n"));
+        assert!(raw.contains("\r\nitem4.EMAIL:katze@example.com\r\n"));
+        let actual = VCard::parse(&raw)?;
+        let email = actual
+            .email
+            .iter()
+            .find(|e| e.value == "katze@example.com")
+            .unwrap();
+        assert_eq!(Some("Katze, Hund".into()), email.custom_label(&actual));
+        assert_eq!(Some("Katze, Hund".into()), actual.label_for_group("item4"));
+        Ok(())
+    }
+}
@@ -1,6 +1,55 @@
 mod errors;
+pub use errors::*;
 mod models;
 pub use models::*;
 
 mod reader;
 pub use reader::*;
+
+mod writer;
+pub use writer::*;
+
+mod validation;
+pub use validation::*;
+
+mod merge;
+pub use merge::*;
+
+mod diff;
+pub use diff::*;
+
+mod groups;
+pub use groups::*;
+
+mod parser;
+pub use parser::*;
+
+mod conversion;
+pub use conversion::*;
+
+mod escape;
+
+mod fingerprint;
+pub use fingerprint::*;
+
+mod warnings;
+pub use warnings::*;
+
+mod collection;
+pub use collection::*;
+
+mod csv;
+pub use csv::*;
+
+pub mod type_param;
+
+mod normalize;
+
+mod anonymize;
+pub use anonymize::*;
+
+mod date_and_or_time;
+pub use date_and_or_time::*;
+
+#[cfg(feature = "serde_json")]
+mod jcard;
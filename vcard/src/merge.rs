@@ -0,0 +1,641 @@
+use crate::{
+    collection::normalize_tel, join_pids, AltIDContainer, Alternative, MultiAltIDContainer,
+    Property, VCard,
+};
+
+/// Decides from which vcard the properties with a cardinality of `1` or `*1` (FN, N, BDAY, GENDER, UID, ...) are taken.
+///
+/// If the preferred vcard does not have a property, it is taken from the other vcard.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum MergeStrategy {
+    PreferSelf,
+    PreferOther,
+    /// prefers the vcard with the newer `REV`. `REV` values are compared as strings, so both vcards
+    /// should use the same timestamp format. If the revisions are equal or missing, the other vcard is preferred.
+    #[default]
+    PreferNewerRev,
+}
+
+/// Merges two vcards using `MergeStrategy::PreferNewerRev`, see `merge_with_strategy`.
+pub fn merge(base: VCard, other: VCard) -> VCard {
+    merge_with_strategy(base, other, MergeStrategy::default())
+}
+
+/// Merges two vcards into one.
+///
+/// - all properties with a cardinality of `*` (EMAIL, TEL, ADR, ...) are unioned. Entries that are present
+///   in both vcards are only kept once. Email addresses are compared case-insensitive and phone numbers
+///   by their digits, all other entries have to be equal.
+/// - FN, N, BDAY, ANNIVERSARY, KIND, GENDER, PRODID, REV and UID are taken from the vcard that is preferred
+///   by `strategy`, see `MergeStrategy`.
+/// - VERSION is taken from `base`.
+/// - proprietary properties of both vcards are appended.
+pub fn merge_with_strategy(base: VCard, other: VCard, strategy: MergeStrategy) -> VCard {
+    let prefer_other = match strategy {
+        MergeStrategy::PreferSelf => false,
+        MergeStrategy::PreferOther => true,
+        MergeStrategy::PreferNewerRev => match (&base.rev, &other.rev) {
+            (Some(b), Some(o)) if b.value != o.value => o.value > b.value,
+            (Some(_), None) => false,
+            _ => true,
+        },
+    };
+    let version = base.version.clone();
+    let (preferred, fallback) = if prefer_other {
+        (other, base)
+    } else {
+        (base, other)
+    };
+
+    VCard {
+        version,
+        source: union(preferred.source, fallback.source),
+        kind: preferred.kind.or(fallback.kind),
+        xml: union(preferred.xml, fallback.xml),
+        fn_property: non_empty_or(
+            preferred.fn_property,
+            fallback.fn_property,
+            MultiAltIDContainer::is_empty,
+        ),
+        n: non_empty_or(preferred.n, fallback.n, AltIDContainer::is_empty),
+        nickname: union(preferred.nickname, fallback.nickname),
+        photo: union(preferred.photo, fallback.photo),
+        bday: non_empty_or(preferred.bday, fallback.bday, AltIDContainer::is_empty),
+        anniversary: non_empty_or(
+            preferred.anniversary,
+            fallback.anniversary,
+            AltIDContainer::is_empty,
+        ),
+        gender: preferred.gender.or(fallback.gender),
+        adr: union(preferred.adr, fallback.adr),
+        tel: union_by(preferred.tel, fallback.tel, |a, b| {
+            let a = normalize_tel(a.value.as_str());
+            !a.is_empty() && a == normalize_tel(b.value.as_str())
+        }),
+        email: union_by(preferred.email, fallback.email, |a, b| {
+            a.value.trim().eq_ignore_ascii_case(b.value.trim())
+        }),
+        impp: union(preferred.impp, fallback.impp),
+        lang: union(preferred.lang, fallback.lang),
+        tz: union(preferred.tz, fallback.tz),
+        geo: union(preferred.geo, fallback.geo),
+        title: union(preferred.title, fallback.title),
+        role: union(preferred.role, fallback.role),
+        logo: union(preferred.logo, fallback.logo),
+        org: union(preferred.org, fallback.org),
+        member: union(preferred.member, fallback.member),
+        related: union(preferred.related, fallback.related),
+        categories: union(preferred.categories, fallback.categories),
+        note: union(preferred.note, fallback.note),
+        prodid: preferred.prodid.or(fallback.prodid),
+        rev: preferred.rev.or(fallback.rev),
+        sound: union(preferred.sound, fallback.sound),
+        uid: preferred.uid.or(fallback.uid),
+        clientpidmap: union_vec(preferred.clientpidmap, fallback.clientpidmap),
+        url: union(preferred.url, fallback.url),
+        key: union(preferred.key, fallback.key),
+        fburl: union(preferred.fburl, fallback.fburl),
+        caluri: union(preferred.caluri, fallback.caluri),
+        caladuri: union(preferred.caladuri, fallback.caladuri),
+        legacy: union_vec(preferred.legacy, fallback.legacy),
+        proprietary_properties: union_vec(
+            preferred.proprietary_properties,
+            fallback.proprietary_properties,
+        ),
+    }
+}
+
+impl VCard {
+    /// Merges `other` into a copy of this vcard, see `merge_with_strategy`.
+    pub fn merge(&self, other: &VCard, strategy: MergeStrategy) -> VCard {
+        merge_with_strategy(self.clone(), other.clone(), strategy)
+    }
+}
+
+fn union<T>(base: MultiAltIDContainer<T>, other: MultiAltIDContainer<T>) -> MultiAltIDContainer<T>
+where
+    T: Alternative + PartialEq + std::fmt::Debug,
+{
+    union_by(base, other, T::eq)
+}
+
+// adds all entries of `other` to `base`, unless `base` already has an entry that is the same according to `same`.
+fn union_by<T>(
+    mut base: MultiAltIDContainer<T>,
+    other: MultiAltIDContainer<T>,
+    same: impl Fn(&T, &T) -> bool,
+) -> MultiAltIDContainer<T>
+where
+    T: Alternative + PartialEq + std::fmt::Debug,
+{
+    for item in other {
+        if !base.iter().any(|b| same(b, &item)) {
+            base.add_value(item);
+        }
+    }
+    base
+}
+
+// returns `preferred`, or `fallback` if `preferred` has no values
+fn non_empty_or<C>(preferred: C, fallback: C, is_empty: fn(&C) -> bool) -> C {
+    if is_empty(&preferred) {
+        fallback
+    } else {
+        preferred
+    }
+}
+
+fn union_vec<T: PartialEq>(mut base: Vec<T>, other: Vec<T>) -> Vec<T> {
+    for item in other {
+        if !base.contains(&item) {
+            base.push(item);
+        }
+    }
+    base
+}
+
+/// A property that was changed differently in `local` and `remote`, see `merge_three_way`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MergeConflict {
+    /// name of the property in uppercase, e.g. `GENDER`.
+    pub name: String,
+    pub base: Vec<Property>,
+    pub local: Vec<Property>,
+    pub remote: Vec<Property>,
+}
+
+/// The result of `merge_three_way`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MergeResult {
+    pub merged: VCard,
+    /// properties that were changed on both sides. `merged` contains the `remote` value of these properties.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Merges the changes of `local` and `remote` relative to their common ancestor `base`,
+/// e.g. to resolve a conflict while syncing with a CardDAV server.
+///
+/// - properties with a cardinality of `*` (EMAIL, TEL, ADR, ...) are merged entry by entry. Entries added
+///   on either side are kept, entries removed on either side are removed. If both sides changed the entry
+///   with the same PID (or the same ALTID if it has no PID) to different values, a `MergeConflict`
+///   is reported and the `remote` entry is used.
+/// - all other properties (VERSION, KIND, N, BDAY, ANNIVERSARY, GENDER, PRODID, UID) are taken from the side
+///   that changed them. If both sides changed them to different values, a `MergeConflict` is reported
+///   and the `remote` value is used.
+/// - REV is never reported as conflict, the newer `REV` is used. `REV` values are compared as strings.
+pub fn merge_three_way(base: &VCard, local: &VCard, remote: &VCard) -> MergeResult {
+    let mut conflicts = Vec::new();
+
+    macro_rules! single {
+        ($field:ident, $name:literal, $to_properties:expr) => {
+            merge_single(
+                $name,
+                &base.$field,
+                &local.$field,
+                &remote.$field,
+                $to_properties,
+                &mut conflicts,
+            )
+        };
+    }
+    macro_rules! option {
+        ($field:ident, $name:literal, $variant:ident) => {
+            single!($field, $name, |v: &Option<_>| {
+                v.iter().cloned().map(Property::$variant).collect()
+            })
+        };
+    }
+    macro_rules! alternatives {
+        ($field:ident, $name:literal, $variant:ident) => {
+            single!($field, $name, |v: &AltIDContainer<_>| {
+                v.iter().cloned().map(Property::$variant).collect()
+            })
+        };
+    }
+    macro_rules! multi {
+        ($field:ident, $name:literal, $variant:ident) => {
+            multi!($field, $name, $variant, |v| v.pid.as_deref().map(join_pids))
+        };
+        ($field:ident, $name:literal, $variant:ident, $pid:expr) => {
+            merge_container(
+                $name,
+                &base.$field,
+                &local.$field,
+                &remote.$field,
+                $pid,
+                Property::$variant,
+                &mut conflicts,
+            )
+        };
+    }
+    macro_rules! list {
+        ($field:ident) => {
+            merge_vec(&base.$field, &local.$field, &remote.$field)
+        };
+    }
+
+    let rev = match (&local.rev, &remote.rev) {
+        (Some(l), Some(r)) if l.value > r.value => local.rev.clone(),
+        (l, None) => l.clone(),
+        (_, r) => r.clone(),
+    };
+
+    let merged = VCard {
+        version: single!(version, "VERSION", |v| vec![Property::Version(v.clone())]),
+        source: multi!(source, "SOURCE", Source),
+        kind: option!(kind, "KIND", Kind),
+        xml: multi!(xml, "XML", Xml, |_| None),
+        fn_property: multi!(fn_property, "FN", FN, |_| None),
+        n: alternatives!(n, "N", N),
+        nickname: multi!(nickname, "NICKNAME", NickName),
+        photo: multi!(photo, "PHOTO", Photo),
+        bday: alternatives!(bday, "BDAY", BDay),
+        anniversary: alternatives!(anniversary, "ANNIVERSARY", Anniversary),
+        gender: option!(gender, "GENDER", Gender),
+        adr: multi!(adr, "ADR", Adr),
+        tel: multi!(tel, "TEL", Tel),
+        email: multi!(email, "EMAIL", Email),
+        impp: multi!(impp, "IMPP", Impp),
+        lang: multi!(lang, "LANG", Lang),
+        tz: multi!(tz, "TZ", Tz),
+        geo: multi!(geo, "GEO", Geo),
+        title: multi!(title, "TITLE", Title),
+        role: multi!(role, "ROLE", Role),
+        logo: multi!(logo, "LOGO", Logo),
+        org: multi!(org, "ORG", Org),
+        member: multi!(member, "MEMBER", Member),
+        related: multi!(related, "RELATED", Related),
+        categories: multi!(categories, "CATEGORIES", Categories),
+        note: multi!(note, "NOTE", Note),
+        prodid: option!(prodid, "PRODID", ProdId),
+        rev,
+        sound: multi!(sound, "SOUND", Sound),
+        uid: option!(uid, "UID", Uid),
+        clientpidmap: list!(clientpidmap),
+        url: multi!(url, "URL", Url),
+        key: multi!(key, "KEY", Key),
+        fburl: multi!(fburl, "FBURL", FbUrl),
+        caluri: multi!(caluri, "CALURI", CalUri),
+        caladuri: multi!(caladuri, "CALADURI", CalAdUri),
+        legacy: list!(legacy),
+        proprietary_properties: list!(proprietary_properties),
+    };
+
+    MergeResult { merged, conflicts }
+}
+
+fn merge_single<T: PartialEq + Clone>(
+    name: &str,
+    base: &T,
+    local: &T,
+    remote: &T,
+    to_properties: impl Fn(&T) -> Vec<Property>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> T {
+    if local == remote || local == base {
+        return remote.clone();
+    }
+    if remote == base {
+        return local.clone();
+    }
+    conflicts.push(MergeConflict {
+        name: name.into(),
+        base: to_properties(base),
+        local: to_properties(local),
+        remote: to_properties(remote),
+    });
+    remote.clone()
+}
+
+// entries are identified by their PID, or by their ALTID if they have no PID. If both sides changed
+// the entries with the same identifier differently, a conflict is reported and the `remote` entries are used.
+fn merge_container<T>(
+    name: &str,
+    base: &MultiAltIDContainer<T>,
+    local: &MultiAltIDContainer<T>,
+    remote: &MultiAltIDContainer<T>,
+    pid: impl Fn(&T) -> Option<String>,
+    to_property: impl Fn(T) -> Property,
+    conflicts: &mut Vec<MergeConflict>,
+) -> MultiAltIDContainer<T>
+where
+    T: Alternative + PartialEq + Clone + std::fmt::Debug,
+{
+    let key = |item: &T| {
+        pid(item).or_else(|| Some(item.get_alt_id().to_string()).filter(|a| !a.is_empty()))
+    };
+    let values = |c: &MultiAltIDContainer<T>| c.iter().cloned().collect::<Vec<T>>();
+    let entries = |values: &[T], k: &str| {
+        values
+            .iter()
+            .filter(|v| key(v).as_deref() == Some(k))
+            .cloned()
+            .collect::<Vec<T>>()
+    };
+    let (base, mut local, remote) = (values(base), values(local), values(remote));
+
+    let mut keys: Vec<String> = Vec::new();
+    for k in base.iter().filter_map(&key) {
+        if !keys.contains(&k) {
+            keys.push(k);
+        }
+    }
+    for k in keys {
+        let (b, l, r) = (
+            entries(&base, &k),
+            entries(&local, &k),
+            entries(&remote, &k),
+        );
+        if l.is_empty() || r.is_empty() || l == b || r == b || l == r {
+            continue;
+        }
+        local.retain(|v| key(v).as_deref() != Some(k.as_str()));
+        local.extend(r.iter().cloned());
+        conflicts.push(MergeConflict {
+            name: name.into(),
+            base: b.into_iter().map(&to_property).collect(),
+            local: l.into_iter().map(&to_property).collect(),
+            remote: r.into_iter().map(&to_property).collect(),
+        });
+    }
+
+    let mut result = MultiAltIDContainer::new();
+    for item in merge_vec(&base, &local, &remote) {
+        result.add_value(item);
+    }
+    result
+}
+
+// keeps the entries of `local` that were not removed in `remote`, followed by the entries added in `remote`
+fn merge_vec<T: PartialEq + Clone>(base: &[T], local: &[T], remote: &[T]) -> Vec<T> {
+    let mut result: Vec<T> = local
+        .iter()
+        .filter(|item| !base.contains(item) || remote.contains(item))
+        .cloned()
+        .collect();
+    for item in remote {
+        if !base.contains(item) && !result.contains(item) {
+            result.push(item.clone());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_merge() -> Result<(), Box<dyn std::error::Error>> {
+        let base = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .email(Email {
+                value: "heinrich@example.com".into(),
+                ..Default::default()
+            })
+            .n(N {
+                surenames: vec!["Tosafjord".into()],
+                ..Default::default()
+            })?
+            .rev(Rev {
+                value: "20220101T120000Z".into(),
+                ..Default::default()
+            })
+            .build()
+            .map_err(VCardError::from)?;
+
+        let other = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .email(Email {
+                value: "heinrich@tosafjord.example".into(),
+                ..Default::default()
+            })
+            .tel(Tel {
+                value: "tel:+49123456789".into(),
+                ..Default::default()
+            })
+            .n(N {
+                surenames: vec!["vom Tosafjord".into()],
+                ..Default::default()
+            })?
+            .rev(Rev {
+                value: "20230101T120000Z".into(),
+                ..Default::default()
+            })
+            .build()
+            .map_err(VCardError::from)?;
+
+        let merged = merge(base, other);
+        assert!(merged.is_valid());
+
+        let fns = merged.fn_property.total_count();
+        assert_eq!(1, fns);
+        let emails = merged.email.total_count();
+        assert_eq!(2, emails);
+        assert!(merged.tel.get_prefered_value().is_some());
+        assert_eq!(
+            vec!["vom Tosafjord".to_string()],
+            merged.n.values()[0].surenames
+        );
+        assert_eq!("20230101T120000Z", merged.rev.unwrap().value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_strategy() -> Result<(), Box<dyn std::error::Error>> {
+        let card = |surename: &str| -> Result<VCard, VCardError> {
+            Ok(VCard::new(VersionValue::V4)
+                .n(N {
+                    surenames: vec![surename.into()],
+                    ..Default::default()
+                })?
+                .build_unchecked())
+        };
+
+        let merged = merge_with_strategy(card("base")?, card("other")?, MergeStrategy::PreferOther);
+        assert_eq!(vec!["other".to_string()], merged.n.values()[0].surenames);
+
+        let merged = merge_with_strategy(card("base")?, card("other")?, MergeStrategy::PreferSelf);
+        assert_eq!(vec!["base".to_string()], merged.n.values()[0].surenames);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_variants() -> Result<(), Box<dyn std::error::Error>> {
+        let card = |bday: &str, rev: &str, tels: &[&str]| -> Result<VCard, VCardError> {
+            let mut card = VCard::new(VersionValue::V4)
+                .fn_property(FN {
+                    value: "Heinrich vom Tosafjord".into(),
+                    ..Default::default()
+                })
+                .email(Email {
+                    value: "Heinrich@Example.com".into(),
+                    ..Default::default()
+                })
+                .bday(BDay {
+                    value: bday.into(),
+                    ..Default::default()
+                })?
+                .rev(Rev {
+                    value: rev.into(),
+                    ..Default::default()
+                })
+                .build_unchecked();
+            for tel in tels {
+                card.add_tel(Tel {
+                    value: (*tel).into(),
+                    ..Default::default()
+                });
+            }
+            Ok(card)
+        };
+        // `mine` has an additional phone number, `theirs` a corrected birthday
+        let mine = card(
+            "19900101",
+            "20220101T120000Z",
+            &["tel:+49-176-1010-1520", "tel:+49-123-456789"],
+        )?;
+        let mut theirs = card("19900110", "20230101T120000Z", &["+49 176 10101520"])?;
+        theirs.set_email(Email {
+            value: "heinrich@example.com".into(),
+            ..Default::default()
+        });
+
+        let merged = mine.merge(&theirs, MergeStrategy::PreferNewerRev);
+        assert_eq!("19900110", merged.bday.values()[0].value);
+        assert_eq!("20230101T120000Z", merged.rev.as_ref().unwrap().value);
+        let tels: Vec<&str> = merged.tel.iter().map(|t| t.value.as_str()).collect();
+        assert_eq!(vec!["+49 176 10101520", "tel:+49-123-456789"], tels);
+        assert_eq!(1, merged.email.total_count());
+        assert_eq!(1, merged.fn_property.total_count());
+
+        let merged = mine.merge(&theirs, MergeStrategy::PreferSelf);
+        assert_eq!("19900101", merged.bday.values()[0].value);
+        assert_eq!(2, merged.tel.total_count());
+
+        let merged = theirs.merge(&mine, MergeStrategy::PreferOther);
+        assert_eq!("19900101", merged.bday.values()[0].value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_three_way() -> Result<(), Box<dyn std::error::Error>> {
+        let email = |value: &str| Email {
+            value: value.into(),
+            ..Default::default()
+        };
+        let gender = |sex: Sex| Gender {
+            sex: Some(sex),
+            ..Default::default()
+        };
+        let base = VCard::new(VersionValue::V4)
+            .email(email("kept@example.com"))
+            .email(email("removed-local@example.com"))
+            .email(email("removed-remote@example.com"))
+            .gender(gender(Sex::Unknown))
+            .uid(Uid {
+                value: "1".into(),
+                ..Default::default()
+            })
+            .build_unchecked();
+
+        let mut local = base.clone();
+        local
+            .email
+            .remove_value(|e| e.value == "removed-local@example.com");
+        local.add_email(email("added-local@example.com"));
+        local.set_gender(gender(Sex::Female));
+        local.set_uid(Uid {
+            value: "2".into(),
+            ..Default::default()
+        });
+
+        let mut remote = base.clone();
+        remote
+            .email
+            .remove_value(|e| e.value == "removed-remote@example.com");
+        remote.add_email(email("added-remote@example.com"));
+        remote.add_email(email("added-local@example.com"));
+        remote.set_gender(gender(Sex::Male));
+
+        let result = merge_three_way(&base, &local, &remote);
+        let emails: Vec<&str> = result
+            .merged
+            .email
+            .iter()
+            .map(|e| e.value.as_str())
+            .collect();
+        assert_eq!(
+            vec![
+                "kept@example.com",
+                "added-local@example.com",
+                "added-remote@example.com"
+            ],
+            emails
+        );
+        // only changed locally
+        assert_eq!("2", result.merged.uid.as_ref().unwrap().value);
+        // changed on both sides
+        assert_eq!(Some(gender(Sex::Male)), result.merged.gender);
+        assert_eq!(
+            vec![MergeConflict {
+                name: "GENDER".into(),
+                base: vec![Property::Gender(gender(Sex::Unknown))],
+                local: vec![Property::Gender(gender(Sex::Female))],
+                remote: vec![Property::Gender(gender(Sex::Male))],
+            }],
+            result.conflicts
+        );
+
+        // the same change on both sides is no conflict
+        let result = merge_three_way(&base, &local, &local);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(local, result.merged);
+
+        // the same entry, identified by its PID, was changed on both sides
+        let tel = |value: &str| Tel {
+            value: value.into(),
+            pid: Some(vec![PidValue {
+                first_digit: 1,
+                second_digit: None,
+            }]),
+            ..Default::default()
+        };
+        let base = VCard::new(VersionValue::V4)
+            .tel(tel("+49 176 1010-1520"))
+            .build_unchecked();
+        let mut local = base.clone();
+        local.tel = MultiAltIDContainer::new();
+        local.add_tel(tel("+49 176 1010-1521"));
+        let mut remote = base.clone();
+        remote.tel = MultiAltIDContainer::new();
+        remote.add_tel(tel("+49 176 1010-1522"));
+        let result = merge_three_way(&base, &local, &remote);
+        assert_eq!(remote.tel, result.merged.tel);
+        assert_eq!(
+            vec![MergeConflict {
+                name: "TEL".into(),
+                base: vec![Property::Tel(tel("+49 176 1010-1520"))],
+                local: vec![Property::Tel(tel("+49 176 1010-1521"))],
+                remote: vec![Property::Tel(tel("+49 176 1010-1522"))],
+            }],
+            result.conflicts
+        );
+
+        // a change on one side is no conflict
+        let result = merge_three_way(&base, &local, &base);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(local.tel, result.merged.tel);
+
+        Ok(())
+    }
+}
@@ -1,10 +1,17 @@
-use std::{collections::HashMap, fmt::Display};
+use std::fmt::Display;
+
+use indexmap::IndexMap;
 
 use crate::{errors::VCardError, Alternative, Preferable};
 
-#[derive(PartialEq, Debug)]
+/// Groups values by their altid.
+///
+/// The altids are kept in the order in which they were added, so serializing a parsed vcard
+/// writes the values in the order in which they were read.
+/// Equality ignores this order, two containers are equal if they contain the same values for every altid.
+#[derive(Clone, PartialEq, Debug)]
 pub struct MultiAltIDContainer<T: Alternative + PartialEq + std::fmt::Debug>(
-    HashMap<String, AltIDContainer<T>>,
+    IndexMap<String, AltIDContainer<T>>,
 );
 
 impl<T: Alternative + PartialEq + std::fmt::Debug> Default for MultiAltIDContainer<T> {
@@ -24,7 +31,7 @@ impl<T: Alternative + Display + PartialEq + std::fmt::Debug> Display for MultiAl
 
 impl<T: Alternative + PartialEq + std::fmt::Debug> MultiAltIDContainer<T> {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self(IndexMap::new())
     }
 
     pub fn add_value(&mut self, value: T) {
@@ -40,43 +47,200 @@ impl<T: Alternative + PartialEq + std::fmt::Debug> MultiAltIDContainer<T> {
         }
     }
 
-    pub fn values(&self) -> &HashMap<String, AltIDContainer<T>> {
-        &self.0
+    /// returns the altids and their values in the order in which the altids were added.
+    pub fn values(&self) -> impl Iterator<Item = (&str, &AltIDContainer<T>)> {
+        self.0
+            .iter()
+            .map(|(altid, container)| (altid.as_str(), container))
     }
 
-    pub fn take_values(self) -> HashMap<String, AltIDContainer<T>> {
-        self.0
+    /// returns the altids and their values in the order in which the altids were added.
+    pub fn take_values(self) -> impl Iterator<Item = (String, AltIDContainer<T>)> {
+        self.0.into_iter()
+    }
+
+    /// removes all values that match `predicate` and returns how many were removed.
+    ///
+    /// Altids without any values left are removed as well.
+    pub fn remove_value(&mut self, predicate: impl Fn(&T) -> bool) -> usize {
+        let mut removed = 0;
+        self.0.retain(|_altid, container| {
+            removed += container.remove_value(&predicate);
+            !container.is_empty()
+        });
+        removed
+    }
+
+    /// returns the number of distinct altids, see `total_count` for the number of values.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// returns the number of values over all altids.
+    pub fn total_count(&self) -> usize {
+        self.0.values().map(AltIDContainer::len).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    /// returns all values that share `altid`. Values without an altid are stored under the empty altid.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let card = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\n\
+    ///     FN;ALTID=1;LANGUAGE=de:Heinrich vom Tosafjord\r\n\
+    ///     FN;ALTID=1;LANGUAGE=en:Henry of Tosafjord\r\n\
+    ///     END:VCARD\r\n")?;
+    /// assert_eq!(2, card.fn_property.get("1").unwrap().len());
+    /// assert!(card.fn_property.get("").is_none());
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    pub fn get(&self, altid: &str) -> Option<&AltIDContainer<T>> {
+        self.0.get(altid)
+    }
+
+    pub fn get_mut(&mut self, altid: &str) -> Option<&mut AltIDContainer<T>> {
+        self.0.get_mut(altid)
+    }
+
+    /// removes all values that share `altid` and returns them.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let mut card = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+    ///     TITLE;ALTID=1;LANGUAGE=de:Richter\r\n\
+    ///     TITLE;ALTID=1;LANGUAGE=en:Judge\r\n\
+    ///     END:VCARD\r\n")?;
+    /// let removed = card.title.remove("1").unwrap();
+    /// assert_eq!("Richter", removed.values()[0].value);
+    /// assert!(card.title.is_empty());
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    pub fn remove(&mut self, altid: &str) -> Option<AltIDContainer<T>> {
+        self.0.shift_remove(altid)
+    }
+
+    /// returns an iterator over the values of all altids.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let card = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+    ///     EMAIL;ALTID=1:first@example.com\r\n\
+    ///     EMAIL:second@example.com\r\n\
+    ///     END:VCARD\r\n")?;
+    /// let emails: Vec<&str> = card.email.iter().map(|e| e.value.as_str()).collect();
+    /// assert_eq!(vec!["first@example.com", "second@example.com"], emails);
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.0.values().flat_map(|c| c.0.iter())
+    }
+
+    // applies `update` to all values. The values are added again, because `update` may change their altid.
+    pub(crate) fn update_values(&mut self, mut update: impl FnMut(&mut T)) {
+        for mut value in std::mem::take(&mut self.0).into_values().flatten() {
+            update(&mut value);
+            self.add_value(value);
+        }
+    }
+}
+
+/// An iterator over the values of all altids of a `MultiAltIDContainer`.
+pub struct MultiAltIDIntoIter<T: Alternative + std::fmt::Debug>(
+    std::iter::Flatten<indexmap::map::IntoValues<String, AltIDContainer<T>>>,
+);
+
+impl<T: Alternative + std::fmt::Debug> Iterator for MultiAltIDIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// An iterator over references to the values of all altids of a `MultiAltIDContainer`.
+pub struct MultiAltIDIter<'a, T: Alternative + std::fmt::Debug>(
+    std::iter::Flatten<indexmap::map::Values<'a, String, AltIDContainer<T>>>,
+);
+
+impl<'a, T: Alternative + std::fmt::Debug> Iterator for MultiAltIDIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<T: Alternative + PartialEq + std::fmt::Debug> IntoIterator for MultiAltIDContainer<T> {
+    type Item = T;
+    type IntoIter = MultiAltIDIntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        MultiAltIDIntoIter(self.0.into_values().flatten())
+    }
+}
+
+impl<'a, T: Alternative + PartialEq + std::fmt::Debug> IntoIterator for &'a MultiAltIDContainer<T> {
+    type Item = &'a T;
+    type IntoIter = MultiAltIDIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        MultiAltIDIter(self.0.values().flatten())
     }
 }
 
 impl<T: Alternative + Preferable + PartialEq + std::fmt::Debug> MultiAltIDContainer<T> {
     /// returns the prefered value.
     ///
-    /// Preference values are ascending. No guarantees are made when multiple values have the same `pref`
+    /// Preference values are ascending, 1 is the most preferred value and values without `pref` count as 101,
+    /// so they come after all values with a `pref`.
+    /// If multiple values have the same `pref`, the first one of `iter` is returned, so the altid does not matter.
     pub fn get_prefered_value(&self) -> Option<&T> {
-        let mut prefered_item = None;
-        for (_key, container) in self.0.iter() {
-            let container_prefered_item = if let Some(cpi) = container.get_prefered_value() {
-                cpi
-            } else {
-                continue;
-            };
-            if prefered_item.is_none() {
-                prefered_item = Some(container_prefered_item);
-            } else if prefered_item.unwrap().get_pref() > container_prefered_item.get_pref() {
-                prefered_item = Some(container_prefered_item);
-            }
-        }
-
-        prefered_item
+        self.iter().min_by_key(|item| item.get_pref())
     }
 }
 
 /// In vcard, if multiple entries share the same type and altid, they are considered
 /// to be one record. This means, all entries in an `AltIDContainer` are considered one record as well.
-#[derive(Default, PartialEq,Debug)]
+///
+/// The values keep the order in which they were added, but equality ignores it:
+/// two containers are equal if they contain the same values, each value as often as in the other container.
+#[derive(Clone, Default, Debug)]
 pub struct AltIDContainer<T: Alternative + std::fmt::Debug>(Vec<T>);
 
+impl<T: Alternative + PartialEq + std::fmt::Debug> PartialEq for AltIDContainer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.0.len() != other.0.len() {
+            return false;
+        }
+        // the values neither implement `Ord` nor `Hash`, so every value is matched with an unused equal value
+        let mut matched = vec![false; other.0.len()];
+        self.0.iter().all(|item| {
+            let found = other
+                .0
+                .iter()
+                .zip(matched.iter_mut())
+                .find(|(candidate, used)| !**used && *candidate == item);
+            match found {
+                Some((_, used)) => {
+                    *used = true;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+}
+
 impl<T> Display for AltIDContainer<T>
 where
     T: Alternative + Display + std::fmt::Debug,
@@ -103,11 +267,11 @@ impl<T: Alternative + std::fmt::Debug> AltIDContainer<T> {
     /// This will fail if `item` has a different `altid` than previous elements of this container.
     /// In case the container does not have any elemts, it will simply be added to the collection.
     pub fn add_value(&mut self, item: T) -> Result<(), VCardError> {
-        if self.0.len() == 0 {
+        if self.0.is_empty() {
             self.0.push(item);
             return Ok(());
         }
-        let prev_altid = self.0.get(0).unwrap().get_alt_id();
+        let prev_altid = self.0.first().unwrap().get_alt_id();
         if prev_altid != item.get_alt_id() {
             return Err(VCardError::InvalidAltID {
                 expected_altid: prev_altid.to_string(),
@@ -127,6 +291,61 @@ impl<T: Alternative + std::fmt::Debug> AltIDContainer<T> {
     pub fn take_values(self) -> Vec<T> {
         self.0
     }
+
+    /// removes all values that match `predicate` and returns how many were removed.
+    pub fn remove_value(&mut self, predicate: impl Fn(&T) -> bool) -> usize {
+        let len = self.0.len();
+        self.0.retain(|item| !predicate(item));
+        len - self.0.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    // applies `update` to all values. All values have to keep the same altid, so `update` must not change
+    // the result of `get_alt_id`. Removing an empty ALTID parameter is fine, because it is returned as "" either way.
+    pub(crate) fn update_values(&mut self, mut update: impl FnMut(&mut T)) {
+        for value in self.0.iter_mut() {
+            let altid = value.get_alt_id().to_string();
+            update(value);
+            debug_assert_eq!(
+                altid,
+                value.get_alt_id(),
+                "update must not change the altid"
+            );
+        }
+    }
+}
+
+impl<T: Alternative + std::fmt::Debug> IntoIterator for AltIDContainer<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T: Alternative + std::fmt::Debug> IntoIterator for &'a AltIDContainer<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
 }
 
 impl<T> AltIDContainer<T>
@@ -135,17 +354,63 @@ where
 {
     /// returns the prefered value.
     ///
-    /// Preference values are ascending. No guarantees are made when multiple values have the same `pref`
+    /// Preference values are ascending, 1 is the most preferred value and values without `pref` count as 101,
+    /// so they come after all values with a `pref`.
+    /// If multiple values have the same `pref`, the first one that was added is returned.
     pub fn get_prefered_value(&self) -> Option<&T> {
-        let mut prefered_item = None;
-        for item in self.0.iter() {
-            if prefered_item.is_none() {
-                prefered_item = Some(item);
-            } else if prefered_item.unwrap().get_pref() > item.get_pref() {
-                prefered_item = Some(item);
-            }
+        self.0.iter().min_by_key(|item| item.get_pref())
+    }
+}
+
+// Both containers are (de)serialized as a flat sequence of their values.
+// Deserialization adds the values one by one, so the altid of each value decides where it ends up.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for MultiAltIDContainer<T>
+where
+    T: Alternative + PartialEq + std::fmt::Debug + serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // the length of the sequence has to be known upfront for some formats like bincode
+        let values: Vec<&T> = self.0.values().flat_map(|c| c.0.iter()).collect();
+        serializer.collect_seq(values)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for MultiAltIDContainer<T>
+where
+    T: Alternative + PartialEq + std::fmt::Debug + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut result = Self::new();
+        for value in Vec::<T>::deserialize(deserializer)? {
+            result.add_value(value);
         }
-        prefered_item
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for AltIDContainer<T>
+where
+    T: Alternative + std::fmt::Debug + serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for AltIDContainer<T>
+where
+    T: Alternative + std::fmt::Debug + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut result = Self::new();
+        for value in Vec::<T>::deserialize(deserializer)? {
+            result.add_value(value).map_err(serde::de::Error::custom)?;
+        }
+        Ok(result)
     }
 }
 
@@ -183,10 +448,73 @@ mod tests {
 
         let prefered_val = container.get_prefered_value().expect("expect a value here");
         assert_eq!(prefered_val.value, "bar".to_string());
+        assert_eq!(2, container.len());
+
+        container.clear();
+        assert!(container.is_empty());
+        assert!(container.get_prefered_value().is_none());
+
+        // an empty ALTID parameter can be removed, the altid stays the same
+        container.add_value(FN {
+            altid: Some("".into()),
+            ..Default::default()
+        })?;
+        container.update_values(|f| f.altid = None);
+        assert_eq!(None, container.iter().next().unwrap().altid);
 
         Ok(())
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "update must not change the altid")]
+    fn test_altid_container_update_keeps_altid() {
+        let mut container = AltIDContainer::from_vec(vec![FN::default()]);
+        container.update_values(|f| f.altid = Some("1".into()));
+    }
+
+    #[test]
+    fn test_equality_ignores_order() {
+        let fn_property = |value: &str, language: &str| FN {
+            altid: Some("1".into()),
+            value: value.into(),
+            language: Some(language.into()),
+            ..Default::default()
+        };
+        let german = fn_property("Heinrich vom Tosafjord", "de");
+        let english = fn_property("Henry of Tosafjord", "en");
+
+        let container = AltIDContainer::from_vec(vec![german.clone(), english.clone()]);
+        assert_eq!(
+            container,
+            AltIDContainer::from_vec(vec![english.clone(), german.clone()])
+        );
+        assert_ne!(
+            container,
+            AltIDContainer::from_vec(vec![german.clone(), german.clone()])
+        );
+        assert_ne!(container, AltIDContainer::from_vec(vec![german.clone()]));
+
+        // the altids are added in a different order as well
+        let nickname = FN {
+            value: "Heiner".into(),
+            ..Default::default()
+        };
+        let mut first = MultiAltIDContainer::new();
+        first.add_value(german.clone());
+        first.add_value(english.clone());
+        first.add_value(nickname.clone());
+        let mut second = MultiAltIDContainer::new();
+        second.add_value(nickname);
+        second.add_value(english);
+        second.add_value(german.clone());
+        assert_eq!(first, second);
+
+        second.remove_value(|f| f.language.is_none());
+        second.add_value(german);
+        assert_ne!(first, second);
+    }
+
     #[test]
     fn test_multi_altid_container() -> Result<(), Box<dyn Error>> {
         let mut testant = MultiAltIDContainer::default();
@@ -211,6 +539,122 @@ mod tests {
             .get_prefered_value()
             .expect("expect a prefered value here");
         assert_eq!(pref.value, "foobar".to_string());
+        assert_eq!(2, testant.len());
+        assert_eq!(3, testant.total_count());
+
+        assert_eq!(1, testant.remove_value(|f| f.value == "foobar"));
+        assert_eq!(1, testant.len());
+        assert_eq!(0, testant.remove_value(|f| f.value == "foobar"));
+
+        testant.clear();
+        assert!(testant.is_empty());
+        assert_eq!(0, testant.total_count());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_altid_container_accessors() {
+        let mut testant = MultiAltIDContainer::default();
+        for (altid, value) in [("1", "foo"), ("2", "bar"), ("1", "baz")] {
+            testant.add_value(FN {
+                altid: Some(altid.into()),
+                value: value.into(),
+                ..Default::default()
+            });
+        }
+
+        let values: Vec<&str> = testant.iter().map(|f| f.value.as_str()).collect();
+        assert_eq!(vec!["foo", "baz", "bar"], values);
+        assert_eq!(3, (&testant).into_iter().count());
+        assert_eq!(2, testant.get("1").unwrap().iter().count());
+        assert!(testant.get("3").is_none());
+
+        testant
+            .get_mut("2")
+            .unwrap()
+            .add_value(FN {
+                altid: Some("2".into()),
+                value: "qux".into(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(2, testant.get("2").unwrap().len());
+
+        // removing the last value of an altid removes the altid as well
+        assert_eq!(
+            2,
+            testant.remove_value(|f| f.value == "bar" || f.value == "qux")
+        );
+        assert!(testant.get("2").is_none());
+        assert_eq!(1, testant.len());
+
+        let removed = testant.remove("1").unwrap();
+        assert_eq!(2, removed.len());
+        assert!(testant.is_empty());
+        assert!(testant.remove("1").is_none());
+
+        let values: Vec<String> = removed.into_iter().map(|f| f.value).collect();
+        assert_eq!(vec!["foo", "baz"], values);
+    }
+
+    #[test]
+    fn test_prefered_value_ignores_altid() {
+        let fn_property = |altid: &str, pref: Option<u8>, value: &str| FN {
+            altid: Some(altid.into()),
+            pref,
+            value: value.into(),
+            ..Default::default()
+        };
+
+        let mut testant = MultiAltIDContainer::default();
+        testant.add_value(fn_property("b", Some(2), "second"));
+        testant.add_value(fn_property("a", Some(3), "third"));
+        testant.add_value(fn_property("c", Some(1), "first"));
+        assert_eq!("first", testant.get_prefered_value().unwrap().value);
+
+        // a missing pref counts as 101
+        let mut testant = MultiAltIDContainer::default();
+        testant.add_value(fn_property("a", None, "none"));
+        testant.add_value(fn_property("b", Some(100), "100"));
+        assert_eq!("100", testant.get_prefered_value().unwrap().value);
+
+        // ties are resolved by insertion order, not by altid
+        let mut testant = MultiAltIDContainer::default();
+        testant.add_value(fn_property("z", Some(5), "first added"));
+        testant.add_value(fn_property("a", Some(5), "second added"));
+        assert_eq!("first added", testant.get_prefered_value().unwrap().value);
+
+        let mut container = AltIDContainer::new();
+        for value in ["first added", "second added"] {
+            container.add_value(fn_property("1", None, value)).unwrap();
+        }
+        assert_eq!("first added", container.get_prefered_value().unwrap().value);
+    }
+
+    #[test]
+    fn test_serialization_order() -> Result<(), Box<dyn Error>> {
+        let raw = "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            TITLE;ALTID=3;LANGUAGE=de:Richter\r\n\
+            TITLE;ALTID=1;LANGUAGE=de:Katze\r\n\
+            TITLE;ALTID=3;LANGUAGE=en:Judge\r\n\
+            TITLE:Kater\r\n\
+            TITLE;ALTID=2;LANGUAGE=en:Tomcat\r\n\
+            TITLE;ALTID=1;LANGUAGE=en:Cat\r\n\
+            END:VCARD\r\n";
+        let card = VCard::parse(raw)?;
+        let first = card.to_string();
+        let second = VCard::parse(&first)?.to_string();
+        assert_eq!(first, second);
+
+        let altids: Vec<&str> = card.title.values().map(|(altid, _)| altid).collect();
+        assert_eq!(vec!["3", "1", "", "2"], altids);
+        assert!(first.contains(
+            "TITLE;ALTID=3;LANGUAGE=de:Richter\r\n\
+            TITLE;ALTID=3;LANGUAGE=en:Judge\r\n\
+            TITLE;ALTID=1;LANGUAGE=de:Katze\r\n"
+        ));
         Ok(())
     }
 }
@@ -1,22 +1,41 @@
-use std::{fmt::Display, str::FromStr};
+use std::{convert::TryFrom, fmt::Display, str::FromStr};
 
-use vcard_macro::{vcard, AltID, Pref};
+use vcard_macro::{vcard, AltID, Group, GroupedProperties, Pref};
 
 use crate::{
-    errors::VCardError, AltIDContainer, MultiAltIDContainer, Parameter, Pid, ValueDataType,
+    collection::normalize_tel, conversion::is_utc_offset, errors::VCardError, escape::unescape,
+    validation::is_language_tag, AdrType, AltIDContainer, EmailType, MediaType,
+    MultiAltIDContainer, Parameter, PidValue, RelatedType, Severity, TelType, VCardValidationError,
+    ValueDataType,
 };
 
 pub trait Alternative {
     fn get_alt_id(&self) -> &str;
 }
 
+/// A property that can be part of a group, e.g. `item1` in `item1.EMAIL`, see `VCard::properties_by_group`.
+pub trait GroupedProperty: Display + std::fmt::Debug {
+    fn get_group(&self) -> Option<&str>;
+
+    /// returns the `X-ABLABEL` of the group of this property in `card`, see `VCard::label_for_group`.
+    fn custom_label(&self, card: &VCard) -> Option<String> {
+        self.get_group().and_then(|g| card.label_for_group(g))
+    }
+}
+
 pub trait Preferable {
+    /// returns the PREF parameter, which is between 1 and 100. A missing PREF is returned as 101.
     fn get_pref(&self) -> u8;
 }
 
 /// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.7.9
-#[derive(Debug, PartialEq, strum_macros::AsRefStr)]
+#[derive(Debug, Clone, PartialEq, strum_macros::AsRefStr)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum VersionValue {
+    /// vCard 2.1 can only be read, see `VCardReader`.
+    #[strum(serialize = "2.1")]
+    V21,
     #[strum(serialize = "3.0")]
     V3,
     #[strum(serialize = "4.0")]
@@ -24,7 +43,8 @@ pub enum VersionValue {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Kind {
     pub group: Option<String>,
     pub value: KindValue,
@@ -39,19 +59,39 @@ impl Default for Kind {
     }
 }
 
-#[derive(strum_macros::AsRefStr, Debug, PartialEq)]
+/// value of KIND, see https://datatracker.ietf.org/doc/html/rfc6350#section-6.1.4
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KindValue {
-    #[strum(serialize = "individual")]
     Individual, //  default
-    #[strum(serialize = "group")]
     Group,
-    #[strum(serialize = "org")]
     Org,
-    #[strum(serialize = "location")]
     Location,
+    /// a software application like a server or an online service, see https://datatracker.ietf.org/doc/html/rfc6473
+    Application,
+    /// a value like `x-robot`, it is written as it was read.
     Proprietary(String),
 }
 
+impl AsRef<str> for KindValue {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Individual => "individual",
+            Self::Group => "group",
+            Self::Org => "org",
+            Self::Location => "location",
+            Self::Application => "application",
+            Self::Proprietary(p) => p,
+        }
+    }
+}
+
+impl Display for KindValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
 impl FromStr for KindValue {
     type Err = VCardError;
 
@@ -61,28 +101,35 @@ impl FromStr for KindValue {
             "group" => Self::Group,
             "org" => Self::Org,
             "location" => Self::Location,
+            "application" => Self::Application,
             _ => Self::Proprietary(s.into()),
         };
         Ok(result)
     }
 }
 
-#[derive(strum_macros::AsRefStr, Debug, PartialEq)]
+#[derive(strum_macros::AsRefStr, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Sex {
-    #[strum(serialize = "m")]
+    #[strum(serialize = "M")]
     Male,
-    #[strum(serialize = "f")]
+    #[strum(serialize = "F")]
     Female,
-    #[strum(serialize = "o")]
+    #[strum(serialize = "O")]
     Other,
-    #[strum(serialize = "n")]
+    #[strum(serialize = "N")]
     None,
-    #[strum(serialize = "u")]
+    #[strum(serialize = "U")]
     Unknown,
 }
 
+/// GENDER property, see https://datatracker.ietf.org/doc/html/rfc6350#section-6.2.7
+///
+/// Both components are optional. The value is written as it was read: `M` has no identity component,
+/// `M;` has an empty one and `;it's complicated` has only an identity component.
 #[vcard]
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gender {
     pub sex: Option<Sex>,
     pub identity_component: Option<String>,
@@ -105,7 +152,8 @@ impl FromStr for Sex {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Version {
     pub value: VersionValue,
 }
@@ -119,17 +167,19 @@ impl Default for Version {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Source {
     pub group: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub altid: Option<String>,
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
     pub value: String,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, Default, AltID, Pref)]
+#[derive(Debug, Clone, PartialEq, Default, AltID, Pref, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FN {
     pub group: Option<String>,
     pub altid: Option<String>,
@@ -141,7 +191,8 @@ pub struct FN {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, Default, AltID)]
+#[derive(Debug, Clone, PartialEq, Default, AltID, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct N {
     pub altid: Option<String>,
     pub language: Option<String>,
@@ -155,8 +206,45 @@ pub struct N {
     pub honorific_suffixes: Vec<String>,
 }
 
+impl N {
+    /// returns the full name in western order, e.g. `Dr. Heinrich Kasper vom Tosafjord jun.`.
+    ///
+    /// The components are joined in the order honorific prefixes, given names, additional names, surnames
+    /// and honorific suffixes. Empty components are skipped.
+    /// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.2.2
+    pub fn to_display_name(&self) -> String {
+        join_words(
+            self.honorific_prefixes
+                .iter()
+                .chain(self.given_names.iter())
+                .chain(self.additional_names.iter())
+                .chain(self.surenames.iter())
+                .chain(self.honorific_suffixes.iter()),
+        )
+    }
+
+    /// returns a key for sorting contacts by name.
+    ///
+    /// The SORT-AS parameter is used if present, otherwise the surnames followed by the given names.
+    pub fn to_sort_key(&self) -> String {
+        match self.sort_as.as_ref().filter(|s| !s.is_empty()) {
+            Some(sort_as) => join_words(sort_as.iter()),
+            None => join_words(self.surenames.iter().chain(self.given_names.iter())),
+        }
+    }
+}
+
+// joins all words with a single space, whitespace within the words is collapsed as well
+fn join_words<'a>(words: impl Iterator<Item = &'a String>) -> String {
+    words
+        .flat_map(|w| w.split_whitespace())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Nickname {
     pub group: Option<String>,
     pub altid: Option<String>,
@@ -165,26 +253,132 @@ pub struct Nickname {
 
     pub language: Option<String>,
     pub pref: Option<u8>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub value: Vec<String>,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Pref,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Photo {
     pub group: Option<String>,
     pub altid: Option<String>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
     pub pref: Option<u8>,
-    pub pid: Option<Pid>,
-    pub value: String,
+    pub pid: Option<Vec<PidValue>>,
+    pub value: PhotoValue,
+}
+
+impl Photo {
+    /// returns the MEDIATYPE parameter or the media type of an inline photo, e.g. `image/jpeg`.
+    pub fn mediatype(&self) -> Option<MediaType> {
+        self.mediatype
+            .clone()
+            .or_else(|| self.value.media_type().map(MediaType::from))
+    }
+}
+
+/// value of PHOTO, LOGO and SOUND, either a reference to the media or the media itself.
+///
+/// Inline media is given as `data:` URI in vCard 4.0 and with `ENCODING=b` in vCard 2.1 and 3.0.
+/// Both are parsed into `PhotoValue::Inline` and written as `data:` URI.
+///
+/// ```
+/// use vcard::*;
+///
+/// let photo: PhotoValue = "data:image/png;base64,iVBORw0KGgo=".parse()?;
+/// assert_eq!(Some("image/png"), photo.media_type());
+/// assert_eq!(Some(&b"\x89PNG\r\n\x1a\n"[..]), photo.data());
+/// assert_eq!("data:image/png;base64,iVBORw0KGgo=", photo.to_string());
+/// # Ok::<(), VCardError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PhotoValue {
+    Uri(url::Url),
+    Inline { data: Vec<u8>, media_type: String },
+}
+
+// base64 of data: URIs, padding is optional because some clients omit it
+const BASE64: base64::engine::GeneralPurpose = base64::engine::GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    base64::engine::GeneralPurposeConfig::new()
+        .with_decode_padding_mode(base64::engine::DecodePaddingMode::Indifferent),
+);
+
+// decodes a base64 encoded data: URI like `data:image/png;base64,iVBORw0KGgo=` into its media type and data
+fn decode_data_uri(uri: &str) -> Option<(&str, Vec<u8>)> {
+    use base64::Engine;
+    let (media_type, data) = uri
+        .get(..5)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        .and_then(|_| uri[5..].split_once(','))?;
+    Some((
+        media_type.strip_suffix(";base64")?,
+        BASE64.decode(data).ok()?,
+    ))
+}
+
+impl PhotoValue {
+    pub fn data(&self) -> Option<&[u8]> {
+        match self {
+            Self::Inline { data, .. } => Some(data),
+            Self::Uri(_) => None,
+        }
+    }
+
+    /// returns the media type of inline media, e.g. `image/jpeg`.
+    pub fn media_type(&self) -> Option<&str> {
+        match self {
+            Self::Inline { media_type, .. } => Some(media_type),
+            Self::Uri(_) => None,
+        }
+    }
+}
+
+impl Default for PhotoValue {
+    fn default() -> Self {
+        Self::Inline {
+            data: Vec::new(),
+            media_type: String::new(),
+        }
+    }
+}
+
+impl FromStr for PhotoValue {
+    type Err = VCardError;
+
+    // base64 encoded data: URIs are decoded, all other values must be URIs
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match decode_data_uri(s) {
+            Some((media_type, data)) => Ok(Self::Inline {
+                data,
+                media_type: media_type.to_string(),
+            }),
+            None => Ok(Self::Uri(url::Url::parse(s)?)),
+        }
+    }
+}
+
+impl Display for PhotoValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uri(uri) => write!(f, "{}", uri),
+            Self::Inline { data, media_type } => {
+                use base64::Engine;
+                write!(f, "data:{};base64,{}", media_type, BASE64.encode(data))
+            }
+        }
+    }
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, Default, AltID)]
+#[derive(Debug, Clone, PartialEq, Default, AltID, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BDay {
+    pub group: Option<String>,
     pub altid: Option<String>,
     pub calscale: Option<String>,
     pub value_data_type: Option<ValueDataType>,
@@ -193,7 +387,8 @@ pub struct BDay {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, Default, AltID)]
+#[derive(Debug, Clone, PartialEq, Default, AltID)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Anniversary {
     pub altid: Option<String>,
     pub calscale: Option<String>,
@@ -202,7 +397,8 @@ pub struct Anniversary {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Pref, Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Adr {
     pub group: Option<String>,
     pub altid: Option<String>,
@@ -210,10 +406,10 @@ pub struct Adr {
     pub language: Option<String>,
     pub geo: Option<String>,
     pub tz: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
-    pub type_param: Option<Vec<String>>,
+    pub type_param: Option<Vec<AdrType>>,
 
     pub po_box: Vec<String>,
     pub extended_address: Vec<String>,
@@ -225,96 +421,634 @@ pub struct Adr {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, Default, AltID, Pref)]
+#[derive(Debug, Clone, PartialEq, Default, AltID, Pref, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tel {
+    pub group: Option<String>,
     pub value_data_type: Option<ValueDataType>,
-    pub type_param: Option<Vec<String>>,
+    pub type_param: Option<Vec<TelType>>,
 
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub altid: Option<String>,
-    pub value: String,
+    pub value: TelValue,
+}
+
+impl Tel {
+    /// returns true if TYPE contains `t`. Proprietary values are compared case-insensitive,
+    /// so `TYPE=pref` of vCard 3.0 matches `TelType::Proprietary("PREF".into())`.
+    pub fn has_type(&self, t: &TelType) -> bool {
+        self.type_param
+            .iter()
+            .flatten()
+            .any(|value| match (value, t) {
+                (TelType::Proprietary(value), TelType::Proprietary(t)) => {
+                    value.eq_ignore_ascii_case(t)
+                }
+                _ => value == t,
+            })
+    }
+
+    /// returns true for mobile numbers, i.e. TYPE contains `cell` or `iphone`, which is written by Apple.
+    pub fn is_mobile(&self) -> bool {
+        self.has_type(&TelType::Cell) || self.has_type(&TelType::Proprietary("iphone".into()))
+    }
+
+    /// returns the digits of the number with a leading `+` for global numbers, e.g. `+15555555555`
+    /// for `tel:+1-555-555-5555;ext=5555`. Use it to compare numbers that are formatted differently.
+    pub fn normalized_number(&self) -> String {
+        normalize_tel(self.value.as_str())
+    }
+}
+
+/// value of a TEL property.
+///
+/// Values with `VALUE=uri` or a `tel:` prefix are parsed as `TelUri`, e.g. `tel:+1-555-555-5555;ext=5555`.
+/// All other values and invalid `tel:` URIs are kept as text.
+/// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.4.1
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TelValue {
+    Uri(TelUri),
+    Text(String),
+}
+
+impl TelValue {
+    pub fn new(text: String, value_data_type: Option<&ValueDataType>) -> Self {
+        let is_uri = value_data_type == Some(&ValueDataType::Uri)
+            || text
+                .get(..4)
+                .is_some_and(|scheme| scheme.eq_ignore_ascii_case("tel:"));
+        match text.parse() {
+            Ok(uri) if is_uri => Self::Uri(uri),
+            _ => Self::Text(text),
+        }
+    }
+
+    /// returns the value as it is written, e.g. `tel:+1-555-555-5555` or `+1 555 555 5555`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Uri(uri) => uri.as_str(),
+            Self::Text(text) => text,
+        }
+    }
+}
+
+impl Default for TelValue {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl Display for TelValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<TelUri> for TelValue {
+    fn from(uri: TelUri) -> Self {
+        Self::Uri(uri)
+    }
+}
+
+impl From<String> for TelValue {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for TelValue {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+/// A `tel:` URI like `tel:+1-555-555-5555;ext=5555`, see https://datatracker.ietf.org/doc/html/rfc3966
+///
+/// The URI is kept as it was read, the number and the parameters are taken from it on access.
+///
+/// ```
+/// use vcard::*;
+///
+/// let uri: TelUri = "tel:+1-555-555-5555;ext=5555".parse()?;
+/// assert_eq!("+1-555-555-5555", uri.number());
+/// assert!(uri.is_global());
+/// assert_eq!(Some("5555"), uri.extension());
+/// assert_eq!("tel:+1-555-555-5555;ext=5555", uri.to_string());
+/// # Ok::<(), VCardError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct TelUri(String);
+
+impl TelUri {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// returns the number without parameters, including visual separators like `-`.
+    pub fn number(&self) -> &str {
+        self.0[4..].split(';').next().unwrap_or_default()
+    }
+
+    /// returns true for global numbers like `+1-555-555-5555`, local numbers have a phone-context instead.
+    pub fn is_global(&self) -> bool {
+        self.number().starts_with('+')
+    }
+
+    pub fn extension(&self) -> Option<&str> {
+        self.parameter("ext")
+    }
+
+    pub fn phone_context(&self) -> Option<&str> {
+        self.parameter("phone-context")
+    }
+
+    /// returns the value of the parameter `name`, which is compared case-insensitive.
+    /// Parameters without a value like `;enum-dip` return an empty string.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.unwrap_or_default())
+    }
+
+    /// returns all parameters in the order they are written, e.g. `("ext", Some("5555"))`.
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.0[4..]
+            .split(';')
+            .skip(1)
+            .map(|p| match p.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (p, None),
+            })
+    }
+}
+
+impl FromStr for TelUri {
+    type Err = VCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: &str| VCardError::InvalidSyntax {
+            message: format!("{}: {}", message, s),
+            property: "TEL".into(),
+        };
+        let rest = s
+            .get(..4)
+            .filter(|scheme| scheme.eq_ignore_ascii_case("tel:"))
+            .map(|_| &s[4..])
+            .ok_or_else(|| invalid("tel: URIs start with tel:"))?;
+        let mut parts = rest.split(';');
+        let number = parts.next().unwrap_or_default();
+        let is_separator = |c: char| "-.()".contains(c);
+        let is_number = match number.strip_prefix('+') {
+            Some(global) => {
+                global.chars().any(|c| c.is_ascii_digit())
+                    && global
+                        .chars()
+                        .all(|c| c.is_ascii_digit() || is_separator(c))
+            }
+            None => {
+                number
+                    .chars()
+                    .any(|c| c.is_ascii_hexdigit() || "*#".contains(c))
+                    && number
+                        .chars()
+                        .all(|c| c.is_ascii_hexdigit() || "*#".contains(c) || is_separator(c))
+            }
+        };
+        if !is_number {
+            return Err(invalid("invalid phone number"));
+        }
+        let is_parameter = |p: &str| {
+            let name = p.split('=').next().unwrap_or_default();
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        };
+        if !parts.all(is_parameter) {
+            return Err(invalid("invalid parameter"));
+        }
+        // the scheme is written in lowercase
+        Ok(Self(format!("tel:{}", rest)))
+    }
+}
+
+impl TryFrom<String> for TelUri {
+    type Error = VCardError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TelUri> for String {
+    fn from(uri: TelUri) -> Self {
+        uri.0
+    }
+}
+
+impl Display for TelUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, Default, AltID, Pref)]
+#[derive(Debug, Clone, PartialEq, Default, AltID, Pref, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Email {
     pub group: Option<String>,
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
-    pub type_param: Option<Vec<String>>,
+    pub type_param: Option<Vec<EmailType>>,
 
     pub value: String,
 }
 
+/// an IMPP value is always a URI, e.g. `xmpp:alice@example.com`.
+/// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.4.3
 #[vcard]
-#[derive(Debug, PartialEq, Default, AltID, Pref)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Impp {
     pub group: Option<String>,
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
-    pub value: String,
+    pub value: url::Url,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, Default, AltID, Pref)]
+#[derive(Debug, Clone, PartialEq, Default, AltID, Pref, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Lang {
     pub group: Option<String>,
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
-    pub value: String,
+    pub value: LanguageTag,
+}
+
+impl Lang {
+    /// returns the primary language subtag, e.g. `zh` for `zh-Hant-TW`.
+    pub fn primary_language(&self) -> &str {
+        self.value.primary_language()
+    }
+}
+
+/// A language tag like `de-DE` or `zh-Hant`, see https://datatracker.ietf.org/doc/html/rfc5646
+///
+/// The tag is kept as it was read, even if it is not well-formed. Use `VCard::validate` to find invalid tags.
+///
+/// ```
+/// use vcard::*;
+///
+/// let mut tag = LanguageTag::from("ZH-hant-tw");
+/// assert!(tag.is_well_formed());
+/// tag.normalize();
+/// assert_eq!("zh-Hant-TW", tag.as_str());
+/// assert!(!LanguageTag::from("Deutsch").is_well_formed());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct LanguageTag(String);
+
+impl LanguageTag {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// returns true if the tag follows the syntax of RFC 5646.
+    ///
+    /// Primary language subtags must have two or three letters, the reserved and registered subtags
+    /// of four to eight letters are not accepted to catch language names like `Deutsch`.
+    pub fn is_well_formed(&self) -> bool {
+        is_language_tag(&self.0)
+    }
+
+    pub fn primary_language(&self) -> &str {
+        self.0.split('-').next().unwrap_or_default()
+    }
+
+    /// applies the case conventions of RFC 5646: the language is lowercase, the script titlecase and
+    /// the region uppercase, e.g. `zh-Hant-TW`.
+    /// See https://datatracker.ietf.org/doc/html/rfc5646#section-2.1.1
+    pub fn normalize(&mut self) {
+        let mut result = Vec::new();
+        let mut after_singleton = false;
+        for (i, subtag) in self.0.split('-').enumerate() {
+            let subtag = subtag.to_ascii_lowercase();
+            let is_alphabetic = subtag.bytes().all(|b| b.is_ascii_alphabetic());
+            result.push(if i == 0 || after_singleton || !is_alphabetic {
+                subtag
+            } else if subtag.len() == 2 {
+                subtag.to_ascii_uppercase()
+            } else if subtag.len() == 4 {
+                subtag[..1].to_ascii_uppercase() + &subtag[1..]
+            } else {
+                subtag
+            });
+            after_singleton |= result.last().is_some_and(|s| s.len() == 1);
+        }
+        self.0 = result.join("-");
+    }
+}
+
+impl From<&str> for LanguageTag {
+    fn from(tag: &str) -> Self {
+        Self(tag.to_string())
+    }
+}
+
+impl From<String> for LanguageTag {
+    fn from(tag: String) -> Self {
+        Self(tag)
+    }
+}
+
+impl Display for LanguageTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, Default, AltID, Pref)]
+#[derive(Debug, Clone, PartialEq, Default, AltID, Pref, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tz {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
 
-    pub value: String,
+    pub value: TzValue,
+}
+
+/// value of a TZ property.
+///
+/// The VALUE parameter decides how the value is parsed. Without it, values like `-0500` or `+01:00`
+/// are a utc-offset and all other values are text.
+/// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.5.1
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TzValue {
+    /// an offset from UTC like `-0530`, which has `hours: 5`, `minutes: 30` and `negative: true`.
+    UtcOffset {
+        hours: i8,
+        minutes: u8,
+        negative: bool,
+    },
+    /// the UTC designator `Z`, which is an offset of zero. It is kept apart from `+0000` so that it is written as read.
+    Utc,
+    /// a time zone name like `America/New_York`.
+    Text(String),
+    Uri(url::Url),
+}
+
+impl TzValue {
+    pub fn new(value: String, value_data_type: Option<&ValueDataType>) -> Result<Self, VCardError> {
+        match value_data_type {
+            Some(ValueDataType::UtcOffset) => {
+                Self::parse_utc_offset(&value).ok_or_else(|| VCardError::InvalidSyntax {
+                    message: format!("{} is not a utc-offset like -0500", value),
+                    property: "TZ".into(),
+                })
+            }
+            Some(ValueDataType::Uri) => Ok(Self::Uri(url::Url::parse(&value)?)),
+            Some(ValueDataType::Text) => Ok(Self::Text(value)),
+            _ => Ok(Self::parse_utc_offset(&value).unwrap_or(Self::Text(value))),
+        }
+    }
+
+    // parses offsets like `-0500`, `+01:00`, `-05` and `Z`
+    fn parse_utc_offset(value: &str) -> Option<Self> {
+        if value == "Z" {
+            return Some(Self::Utc);
+        }
+        if !is_utc_offset(value) {
+            return None;
+        }
+        let digits = value.get(1..).unwrap_or_default().replace(':', "");
+        let minutes = digits.get(2..).filter(|m| !m.is_empty()).unwrap_or("0");
+        Some(Self::UtcOffset {
+            hours: digits.get(..2).unwrap_or("0").parse().ok()?,
+            minutes: minutes.parse().ok().filter(|m| *m < 60)?,
+            negative: value.starts_with('-'),
+        })
+    }
+
+    /// returns the offset as `chrono::FixedOffset`, `None` for names and URIs.
+    #[cfg(feature = "chrono")]
+    pub fn to_fixed_offset(&self) -> Option<chrono::FixedOffset> {
+        match self {
+            Self::UtcOffset {
+                hours,
+                minutes,
+                negative,
+            } => {
+                let seconds = i32::from(*hours) * 3600 + i32::from(*minutes) * 60;
+                chrono::FixedOffset::east_opt(if *negative { -seconds } else { seconds })
+            }
+            Self::Utc => chrono::FixedOffset::east_opt(0),
+            _ => None,
+        }
+    }
+}
+
+impl Default for TzValue {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl Display for TzValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UtcOffset {
+                hours,
+                minutes,
+                negative,
+            } => {
+                let sign = if *negative { '-' } else { '+' };
+                write!(f, "{}{:02}{:02}", sign, hours, minutes)
+            }
+            Self::Utc => write!(f, "Z"),
+            Self::Text(text) => write!(f, "{}", text),
+            Self::Uri(uri) => write!(f, "{}", uri),
+        }
+    }
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Pref,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Geo {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
 
-    pub value: String,
+    pub value: GeoValue,
+}
+
+/// value of a GEO property.
+///
+/// vCard 4.0 uses a URI, usually a `geo:` URI as defined by RFC 5870 like `geo:37.386013,-122.082932`.
+/// vCard 3.0 uses the latitude and longitude separated by a semicolon like `37.386013;-122.082932`.
+/// Both forms are accepted when parsing, `VCard::to_string_as` writes the form of the target version.
+/// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.5.2
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GeoValue {
+    Uri(url::Url),
+    Coords { latitude: f64, longitude: f64 },
+}
+
+impl GeoValue {
+    /// returns the latitude of the coordinates or of a `geo:` URI.
+    pub fn latitude(&self) -> Option<f64> {
+        self.coordinates().map(|(latitude, _)| latitude)
+    }
+
+    /// returns the longitude of the coordinates or of a `geo:` URI.
+    pub fn longitude(&self) -> Option<f64> {
+        self.coordinates().map(|(_, longitude)| longitude)
+    }
+
+    /// returns the uncertainty in meters, i.e. the `u` parameter of a `geo:` URI like `geo:37.386,-122.082;u=10`.
+    pub fn uncertainty(&self) -> Option<f64> {
+        match self {
+            Self::Uri(uri) if uri.scheme() == "geo" => uri
+                .path()
+                .split(';')
+                .skip(1)
+                .filter_map(|param| param.split_once('='))
+                .find(|(key, _)| key.eq_ignore_ascii_case("u"))
+                .and_then(|(_, value)| value.parse().ok()),
+            _ => None,
+        }
+    }
+
+    fn coordinates(&self) -> Option<(f64, f64)> {
+        match self {
+            Self::Coords {
+                latitude,
+                longitude,
+            } => Some((*latitude, *longitude)),
+            Self::Uri(uri) if uri.scheme() == "geo" => parse_geo_uri_path(uri.path()).ok(),
+            Self::Uri(_) => None,
+        }
+    }
+}
+
+impl Default for GeoValue {
+    fn default() -> Self {
+        Self::Coords {
+            latitude: 0.0,
+            longitude: 0.0,
+        }
+    }
+}
+
+impl FromStr for GeoValue {
+    type Err = VCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if !s.contains(':') {
+            if let Some((latitude, longitude)) = s.split_once(';') {
+                let (latitude, longitude) = parse_coordinates(latitude, longitude)?;
+                return Ok(Self::Coords {
+                    latitude,
+                    longitude,
+                });
+            }
+        }
+        let uri = url::Url::parse(s)?;
+        if uri.scheme() == "geo" {
+            parse_geo_uri_path(uri.path())?;
+        }
+        Ok(Self::Uri(uri))
+    }
+}
+
+impl Display for GeoValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uri(uri) => write!(f, "{}", uri),
+            Self::Coords {
+                latitude,
+                longitude,
+            } => write!(f, "{};{}", latitude, longitude),
+        }
+    }
+}
+
+// parses the path of a `geo:` URI like `37.386,-122.082,15;u=10`, the altitude and parameters are ignored.
+// See https://datatracker.ietf.org/doc/html/rfc5870#section-3.3
+fn parse_geo_uri_path(path: &str) -> Result<(f64, f64), VCardError> {
+    let mut coordinates = path.split(';').next().unwrap_or_default().split(',');
+    match (coordinates.next(), coordinates.next()) {
+        (Some(latitude), Some(longitude)) => parse_coordinates(latitude, longitude),
+        _ => Err(invalid_geo(format!(
+            "{} has no latitude and longitude",
+            path
+        ))),
+    }
+}
+
+fn parse_coordinates(latitude: &str, longitude: &str) -> Result<(f64, f64), VCardError> {
+    let parse = |value: &str, limit: f64| match value.trim().parse::<f64>() {
+        Ok(v) if (-limit..=limit).contains(&v) => Ok(v),
+        _ => Err(invalid_geo(format!(
+            "{} is not a number between -{} and {}",
+            value, limit, limit
+        ))),
+    };
+    Ok((parse(latitude, 90.0)?, parse(longitude, 180.0)?))
+}
+
+fn invalid_geo(message: String) -> VCardError {
+    VCardError::InvalidSyntax {
+        message,
+        property: "GEO".into(),
+    }
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Pref, Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Title {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
@@ -325,12 +1059,13 @@ pub struct Title {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Pref,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Role {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
@@ -341,29 +1076,31 @@ pub struct Role {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Pref,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Logo {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
     pub language: Option<String>,
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
 
-    pub value: String,
+    pub value: PhotoValue,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Pref, Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Org {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
@@ -375,42 +1112,73 @@ pub struct Org {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Pref,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Member {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
 
     pub value: String,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Pref,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Related {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
-    pub type_param: Option<Vec<String>>,
+    pub type_param: Option<Vec<RelatedType>>,
 
     pub language: Option<String>,
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
 
     pub value: String,
 }
 
+impl Related {
+    /// creates a RELATED property that refers to `uri`, e.g. the `urn:uuid:` of another vcard.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let uri = url::Url::parse("urn:uuid:03a0e51f-d1aa-4385-8a53-e29025acd8af").unwrap();
+    /// let related = Related::new_uri(uri, RelatedType::Spouse);
+    /// assert_eq!(
+    ///     "RELATED;VALUE=uri;TYPE=spouse:urn:uuid:03a0e51f-d1aa-4385-8a53-e29025acd8af\r\n",
+    ///     related.to_string()
+    /// );
+    /// ```
+    pub fn new_uri(uri: url::Url, related_type: RelatedType) -> Self {
+        Self {
+            value_data_type: Some(ValueDataType::Uri),
+            type_param: Some(vec![related_type]),
+            value: uri.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// returns the TYPE values, which are empty if there is no TYPE parameter.
+    pub fn types(&self) -> &[RelatedType] {
+        self.type_param.as_deref().unwrap_or_default()
+    }
+}
+
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Categories {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
@@ -419,12 +1187,13 @@ pub struct Categories {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID, Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Note {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
@@ -435,132 +1204,241 @@ pub struct Note {
 }
 
 #[vcard]
-#[derive(Debug, PartialEq,Default)]
+#[derive(Debug, Clone, PartialEq, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProdId {
     pub group: Option<String>,
     pub value: String,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq,Default)]
+#[derive(Debug, Clone, PartialEq, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rev {
     pub group: Option<String>,
     pub value: String,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sound {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
     pub language: Option<String>,
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
 
-    pub value: String,
+    pub value: PhotoValue,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq,Default)]
+#[derive(Debug, Clone, PartialEq, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Uid {
     pub group: Option<String>,
     pub value_data_type: Option<ValueDataType>,
     pub value: String,
 }
 
-#[vcard]
-#[derive(Debug, PartialEq,Default)]
-pub struct ClientPidMap {
-    pub group: Option<String>,
-    pub pid_digit: u8,
-    pub value: String,
-}
+#[cfg(feature = "uuid")]
+impl Uid {
+    const UUID_URN_PREFIX: &'static str = "urn:uuid:";
 
-#[vcard]
-#[derive(Debug, PartialEq, AltID,Default)]
-pub struct Url {
-    pub group: Option<String>,
+    /// Creates a new `Uid` containing a randomly generated (v4) UUID URN.
+    pub fn new_uuid() -> Self {
+        Self::from_uuid(uuid::Uuid::new_v4())
+    }
+
+    /// Creates a new `Uid` with a value of the form `urn:uuid:{uuid}`.
+    ///
+    /// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.7.6
+    pub fn from_uuid(uuid: uuid::Uuid) -> Self {
+        Self {
+            value: format!("{}{}", Self::UUID_URN_PREFIX, uuid.hyphenated()),
+            ..Default::default()
+        }
+    }
+
+    /// returns the UUID contained in this `Uid`.
+    ///
+    /// `None` is returned if the value is not a UUID URN.
+    pub fn as_uuid(&self) -> Option<uuid::Uuid> {
+        let prefix = self.value.get(..Self::UUID_URN_PREFIX.len())?;
+        if !prefix.eq_ignore_ascii_case(Self::UUID_URN_PREFIX) {
+            return None;
+        }
+        uuid::Uuid::parse_str(&self.value[Self::UUID_URN_PREFIX.len()..]).ok()
+    }
+}
+
+#[vcard]
+#[derive(Debug, Clone, PartialEq, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientPidMap {
+    pub group: Option<String>,
+    pub pid_digit: u8,
+    /// the global identifier of the source, e.g. `urn:uuid:3df403f4-5924-4bb7-b077-3c711d9eb34b`.
+    pub value: url::Url,
+}
+
+#[vcard]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Url {
+    pub group: Option<String>,
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
     pub value: String,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FbURL {
     pub group: Option<String>,
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
     pub value: String,
 }
 
-#[vcard]
-#[derive(Debug, PartialEq, AltID,Default)]
+/// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.9.2
+#[vcard(name = "CALADRURI")]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CalAdURI {
     pub group: Option<String>,
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
     pub value: String,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CalURI {
     pub group: Option<String>,
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
     pub value: String,
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Pref, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Key {
     pub group: Option<String>,
 
     pub altid: Option<String>,
-    pub pid: Option<Pid>,
+    pub pid: Option<Vec<PidValue>>,
     pub pref: Option<u8>,
     pub value_data_type: Option<ValueDataType>,
     pub type_param: Option<Vec<String>>,
 
-    pub mediatype: Option<String>,
+    pub mediatype: Option<MediaType>,
 
-    pub value: String,
+    pub value: KeyValue,
+}
+
+impl Key {
+    /// returns the key of a `data:` URI, e.g. a PGP public key. Keys given by reference or as text return `None`.
+    pub fn key_bytes(&self) -> Option<&[u8]> {
+        match &self.value {
+            KeyValue::Data { bytes, .. } => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+/// value of a KEY property, see https://datatracker.ietf.org/doc/html/rfc6350#section-6.8.1
+///
+/// `VALUE=text` gives `KeyValue::Text`. Otherwise base64 encoded `data:` URIs are decoded into `KeyValue::Data`,
+/// other URIs like `https://example.com/key.asc` become `KeyValue::Uri` and all remaining values are kept as text.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyValue {
+    Uri(url::Url),
+    /// the content of a `data:` URI. `mediatype` is `None` if the URI has none, e.g. `data:;base64,`.
+    Data {
+        mediatype: Option<String>,
+        bytes: Vec<u8>,
+    },
+    Text(String),
+}
+
+impl KeyValue {
+    pub fn new(value: String, value_data_type: Option<&ValueDataType>) -> Self {
+        if value_data_type == Some(&ValueDataType::Text) {
+            return Self::Text(value);
+        }
+        if let Some((mediatype, bytes)) = decode_data_uri(&value) {
+            return Self::Data {
+                mediatype: Some(mediatype.to_string()).filter(|m| !m.is_empty()),
+                bytes,
+            };
+        }
+        match url::Url::parse(&value) {
+            Ok(uri) => Self::Uri(uri),
+            Err(_) => Self::Text(value),
+        }
+    }
+}
+
+impl Default for KeyValue {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl Display for KeyValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uri(uri) => write!(f, "{}", uri),
+            Self::Data { mediatype, bytes } => {
+                use base64::Engine;
+                let mediatype = mediatype.as_deref().unwrap_or_default();
+                write!(f, "data:{};base64,{}", mediatype, BASE64.encode(bytes))
+            }
+            Self::Text(text) => write!(f, "{}", text),
+        }
+    }
 }
 
 #[vcard]
-#[derive(Debug, PartialEq, AltID,Default)]
+#[derive(Debug, Clone, PartialEq, AltID, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Xml {
     pub altid: Option<String>,
     pub group: Option<String>,
     pub value: String,
 }
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProprietaryProperty {
     pub name: String,
     pub group: Option<String>,
@@ -576,7 +1454,7 @@ impl Display for ProprietaryProperty {
         write!(f, "{}", self.name)?;
 
         for param in self.parameters.iter() {
-            write!(f, ";{}", param.to_string())?;
+            write!(f, ";{}", param)?;
         }
 
         write!(f, ":{}\r\n", self.value)?;
@@ -585,11 +1463,123 @@ impl Display for ProprietaryProperty {
     }
 }
 
+/// names of the vCard 3.0 properties that were removed in vCard 4.0.
+/// See https://datatracker.ietf.org/doc/html/rfc6350#appendix-A.2
+#[derive(strum_macros::AsRefStr, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LegacyPropertyName {
+    #[strum(serialize = "AGENT")]
+    Agent,
+    #[strum(serialize = "CLASS")]
+    Class,
+    #[strum(serialize = "LABEL")]
+    Label,
+    #[strum(serialize = "MAILER")]
+    Mailer,
+    #[strum(serialize = "NAME")]
+    Name,
+    #[strum(serialize = "PROFILE")]
+    Profile,
+    #[strum(serialize = "SORT-STRING")]
+    SortString,
+}
+
+impl LegacyPropertyName {
+    /// returns the legacy property with the given name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let result = match &name.to_uppercase()[..] {
+            "AGENT" => Self::Agent,
+            "CLASS" => Self::Class,
+            "LABEL" => Self::Label,
+            "MAILER" => Self::Mailer,
+            "NAME" => Self::Name,
+            "PROFILE" => Self::Profile,
+            "SORT-STRING" => Self::SortString,
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+/// names of the properties of a vcard, see `VCard::has_property`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyName {
+    Version,
+    Source,
+    Kind,
+    Xml,
+    FN,
+    N,
+    NickName,
+    Photo,
+    BDay,
+    Anniversary,
+    Gender,
+    Adr,
+    Tel,
+    Email,
+    Impp,
+    Lang,
+    Tz,
+    Geo,
+    Title,
+    Role,
+    Logo,
+    Org,
+    Member,
+    Related,
+    Categories,
+    Note,
+    ProdId,
+    Rev,
+    Sound,
+    Uid,
+    ClientPidMap,
+    Url,
+    Key,
+    FbUrl,
+    CalUri,
+    CalAdUri,
+    Legacy(LegacyPropertyName),
+    /// a proprietary property like `X-ABLABEL`, the name is compared ignoring case.
+    Proprietary(String),
+}
+
+/// A property of vCard 3.0 that does not exist in vCard 4.0, e.g. `LABEL;TYPE=HOME:Main St`.
+///
+/// The value is kept as it was read, so an AGENT with an embedded vcard stays escaped text.
+/// Vcards of version 4.0 must not contain legacy properties, see `VCard::upgrade_to_v4`.
+#[derive(Debug, Clone, PartialEq, Group)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LegacyProperty {
+    pub name: LegacyPropertyName,
+    pub group: Option<String>,
+    pub value: String,
+    pub parameters: Vec<Parameter>,
+}
+
+impl Display for LegacyProperty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(grp) = &self.group {
+            write!(f, "{}.", grp)?;
+        }
+        write!(f, "{}", self.name.as_ref())?;
+
+        for param in self.parameters.iter() {
+            write!(f, ";{}", param)?;
+        }
+
+        write!(f, ":{}\r\n", self.value)
+    }
+}
+
 /// Represents a single VCard.
 ///
 /// For more informatin about the fields, see https://datatracker.ietf.org/doc/html/rfc6350#section-6
-#[derive(Default, PartialEq, Debug)]
+#[derive(Clone, Default, PartialEq, Debug, GroupedProperties)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VCard {
+    #[group(skip)]
     pub version: Version,
     pub source: MultiAltIDContainer<Source>,
     pub kind: Option<Kind>,
@@ -603,8 +1593,10 @@ pub struct VCard {
     pub photo: MultiAltIDContainer<Photo>,
 
     pub bday: AltIDContainer<BDay>,
+    #[group(skip)]
     pub anniversary: AltIDContainer<Anniversary>,
 
+    #[group(skip)]
     pub gender: Option<Gender>,
     pub adr: MultiAltIDContainer<Adr>,
     pub tel: MultiAltIDContainer<Tel>,
@@ -627,7 +1619,12 @@ pub struct VCard {
     pub rev: Option<Rev>,
     pub sound: MultiAltIDContainer<Sound>,
     pub uid: Option<Uid>,
-    pub clientpidmap: Option<ClientPidMap>,
+    /// CLIENTPIDMAP has a cardinality of `*`, see https://datatracker.ietf.org/doc/html/rfc6350#section-6.7.7
+    ///
+    /// Note: this field used to be an `Option<ClientPidMap>`, so vcards with more than one CLIENTPIDMAP
+    /// failed to parse with `VCardError::InvalidCardinality`.
+    /// Use `clientpidmap.first()` to migrate code that relied on the old shape.
+    pub clientpidmap: Vec<ClientPidMap>,
 
     pub url: MultiAltIDContainer<Url>,
     pub key: MultiAltIDContainer<Key>,
@@ -635,18 +1632,146 @@ pub struct VCard {
     pub caluri: MultiAltIDContainer<CalURI>,
     pub caladuri: MultiAltIDContainer<CalAdURI>,
 
+    /// properties of vCard 2.1 and 3.0 that were removed in vCard 4.0, like LABEL or SORT-STRING.
+    pub legacy: Vec<LegacyProperty>,
     pub proprietary_properties: Vec<ProprietaryProperty>,
 }
 
 impl VCard {
+    /// Returns a `VCardBuilder` for a vcard of the given version.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let card = VCard::new(VersionValue::V4)
+    ///     .fn_property(FN {
+    ///         value: "Heinrich vom Tosafjord".into(),
+    ///         ..Default::default()
+    ///     })
+    ///     .email(Email {
+    ///         value: "heinrich@tosafjord.example".into(),
+    ///         ..Default::default()
+    ///     })
+    ///     .n(N {
+    ///         surenames: vec!["vom Tosafjord".into()],
+    ///         given_names: vec!["Heinrich".into()],
+    ///         ..Default::default()
+    ///     })?
+    ///     .build()?;
+    /// assert!(card.is_valid());
+    ///
+    /// let errors = VCard::new(VersionValue::V4).build().unwrap_err();
+    /// assert_eq!("FN", errors[0].field);
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(version: VersionValue) -> VCardBuilder {
         VCardBuilder {
             vc: VCard {
                 version: Version { value: version },
                 ..Default::default()
             },
+            duplicates: Vec::new(),
         }
     }
+
+    /// returns the email address with the lowest `PREF` value.
+    /// If no entry has a `PREF` value, the first entry is returned.
+    pub fn preferred_email(&self) -> Option<&Email> {
+        self.email.get_prefered_value()
+    }
+
+    /// returns the telephone number with the lowest `PREF` value.
+    /// If no entry has a `PREF` value, the first entry is returned.
+    pub fn preferred_tel(&self) -> Option<&Tel> {
+        self.tel.get_prefered_value()
+    }
+
+    /// returns the address with the lowest `PREF` value.
+    /// If no entry has a `PREF` value, the first entry is returned.
+    pub fn preferred_adr(&self) -> Option<&Adr> {
+        self.adr.get_prefered_value()
+    }
+
+    /// returns the URL with the lowest `PREF` value.
+    /// If no entry has a `PREF` value, the first entry is returned.
+    pub fn preferred_url(&self) -> Option<&Url> {
+        self.url.get_prefered_value()
+    }
+
+    /// returns the name to display for this contact.
+    ///
+    /// The first non-empty value of the preferred FN, the name built from N, the preferred NICKNAME,
+    /// the preferred ORG and the local part of the preferred email address is returned.
+    /// Escaped characters are unescaped, e.g. `Smith\, John` becomes `Smith, John`.
+    /// Returns `None` if the vcard has none of them.
+    pub fn display_name(&self) -> Option<String> {
+        let candidates = vec![
+            self.fn_property
+                .get_prefered_value()
+                .map(|f| unescape(&f.value)),
+            self.n.values().first().map(N::to_display_name),
+            self.nickname
+                .get_prefered_value()
+                .map(|n| n.value.join(", ")),
+            self.org.get_prefered_value().map(|o| o.value.join(", ")),
+            self.preferred_email()
+                .map(|e| unescape(e.value.split('@').next().unwrap_or_default())),
+        ];
+        candidates
+            .into_iter()
+            .flatten()
+            .map(|name| name.trim().to_string())
+            .find(|name| !name.is_empty())
+    }
+
+    /// returns all proprietary properties whose name starts with `prefix`, ignoring the case,
+    /// e.g. all `X-AB` properties of Apple Address Book.
+    pub fn proprietary_by_prefix<'a>(
+        &'a self,
+        prefix: &str,
+    ) -> impl Iterator<Item = &'a ProprietaryProperty> {
+        let prefix = prefix.to_string();
+        self.proprietary_properties.iter().filter(move |p| {
+            p.name
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(&prefix))
+        })
+    }
+
+    /// returns the first proprietary property named `name`, ignoring the case.
+    pub fn proprietary_by_name(&self, name: &str) -> Option<&ProprietaryProperty> {
+        self.proprietary_properties
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// returns the global identifier of the source of `pid`, i.e. the CLIENTPIDMAP with its source identifier.
+    /// Returns `None` if `pid` has no source identifier or there is no matching CLIENTPIDMAP.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let card = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\n\
+    ///     EMAIL;PID=1.1,2.2:heinrich@example.com\r\n\
+    ///     CLIENTPIDMAP:1;urn:uuid:3df403f4-5924-4bb7-b077-3c711d9eb34b\r\n\
+    ///     END:VCARD\r\n")?;
+    ///
+    /// let pids = card.email.iter().next().unwrap().pid.as_ref().unwrap();
+    /// assert_eq!(
+    ///     "urn:uuid:3df403f4-5924-4bb7-b077-3c711d9eb34b",
+    ///     card.resolve_pid_source(&pids[0]).unwrap().as_str()
+    /// );
+    /// assert_eq!(None, card.resolve_pid_source(&pids[1]));
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    pub fn resolve_pid_source(&self, pid: &PidValue) -> Option<&url::Url> {
+        let source = pid.second_digit?;
+        self.clientpidmap
+            .iter()
+            .find(|c| c.pid_digit == source)
+            .map(|c| &c.value)
+    }
 }
 
 macro_rules! multi_container_methods {
@@ -675,6 +1800,9 @@ macro_rules! option_methods {
     ($(($field:ident,$type:ident)),*) => {
         $(
             pub fn $field(mut self, $field: $type) -> Self {
+                if self.vc.$field.is_some() {
+                    self.duplicates.push(stringify!($field));
+                }
                 self.vc.$field = Some($field);
                 self
             }
@@ -683,15 +1811,382 @@ macro_rules! option_methods {
     };
 }
 
+macro_rules! remove_methods {
+    ($(($name:ident,$field:ident,$type:ident)),*) => {
+        $(
+        /// removes all values that match `predicate` and returns how many were removed.
+        pub fn $name(&mut self, predicate: impl Fn(&$type) -> bool) -> usize {
+            self.$field.remove_value(predicate)
+        }
+        )*
+    };
+}
+
+macro_rules! mutation_methods {
+    ($(($add:ident,$set:ident,$clear:ident,$field:ident,$type:ident)),*) => {
+        $(
+        /// adds a value, values with the same altid are considered alternatives of each other.
+        pub fn $add(&mut self, value: $type) {
+            self.$field.add_value(value)
+        }
+
+        /// replaces all values that have the same altid as `value`.
+        pub fn $set(&mut self, value: $type) {
+            self.$field.remove(value.get_alt_id());
+            self.$field.add_value(value)
+        }
+
+        pub fn $clear(&mut self) {
+            self.$field.clear()
+        }
+        )*
+    };
+}
+
+macro_rules! alternatives_mutation_methods {
+    ($(($add:ident,$set:ident,$clear:ident,$field:ident,$type:ident)),*) => {
+        $(
+        /// adds an alternative value. This fails if `value` has a different altid than the existing values.
+        pub fn $add(&mut self, value: $type) -> Result<(), VCardError> {
+            self.$field.add_value(value)
+        }
+
+        /// replaces all values.
+        pub fn $set(&mut self, value: $type) {
+            self.$field = AltIDContainer::from_vec(vec![value]);
+        }
+
+        pub fn $clear(&mut self) {
+            self.$field.clear()
+        }
+        )*
+    };
+}
+
+macro_rules! option_mutation_methods {
+    ($(($set:ident,$field:ident,$type:ident)),*) => {
+        $(
+        /// replaces the previous value.
+        pub fn $set(&mut self, value: $type) {
+            self.$field = Some(value);
+        }
+        )*
+    };
+}
+
+/// Methods to modify the properties of a vcard.
+///
+/// For every property with a cardinality of `*` there is an `add_`, `set_`, `clear_` and `remove_` method.
+/// `set_` replaces the values with the same ALTID.
+///
+/// ```
+/// use vcard::*;
+///
+/// let mut card = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\n\
+///     EMAIL;PREF=1:heinrich@old.example\r\n\
+///     EMAIL;PREF=2:heinrich@work.example\r\n\
+///     END:VCARD\r\n")?;
+///
+/// card.remove_email(|e| e.value == "heinrich@old.example");
+/// card.add_email(Email {
+///     pref: Some(1),
+///     value: "heinrich@new.example".into(),
+///     ..Default::default()
+/// });
+/// let saved = card.to_string();
+///
+/// let card = VCard::parse(&saved)?;
+/// assert_eq!("heinrich@new.example", card.preferred_email().unwrap().value);
+/// assert_eq!(2, card.email.total_count());
+/// # Ok::<(), VCardError>(())
+/// ```
+impl VCard {
+    option_mutation_methods!(
+        (set_kind, kind, Kind),
+        (set_gender, gender, Gender),
+        (set_prodid, prodid, ProdId),
+        (set_rev, rev, Rev),
+        (set_uid, uid, Uid)
+    );
+
+    alternatives_mutation_methods!(
+        (add_n, set_n, clear_n, n, N),
+        (add_bday, set_bday, clear_bday, bday, BDay),
+        (
+            add_anniversary,
+            set_anniversary,
+            clear_anniversary,
+            anniversary,
+            Anniversary
+        )
+    );
+
+    mutation_methods!(
+        (add_source, set_source, clear_source, source, Source),
+        (add_xml, set_xml, clear_xml, xml, Xml),
+        (
+            add_fn_property,
+            set_fn_property,
+            clear_fn_property,
+            fn_property,
+            FN
+        ),
+        (
+            add_nickname,
+            set_nickname,
+            clear_nickname,
+            nickname,
+            Nickname
+        ),
+        (add_photo, set_photo, clear_photo, photo, Photo),
+        (add_adr, set_adr, clear_adr, adr, Adr),
+        (add_tel, set_tel, clear_tel, tel, Tel),
+        (add_email, set_email, clear_email, email, Email),
+        (add_impp, set_impp, clear_impp, impp, Impp),
+        (add_lang, set_lang, clear_lang, lang, Lang),
+        (add_tz, set_tz, clear_tz, tz, Tz),
+        (add_geo, set_geo, clear_geo, geo, Geo),
+        (add_title, set_title, clear_title, title, Title),
+        (add_role, set_role, clear_role, role, Role),
+        (add_logo, set_logo, clear_logo, logo, Logo),
+        (add_org, set_org, clear_org, org, Org),
+        (add_member, set_member, clear_member, member, Member),
+        (add_related, set_related, clear_related, related, Related),
+        (
+            add_categories,
+            set_categories,
+            clear_categories,
+            categories,
+            Categories
+        ),
+        (add_note, set_note, clear_note, note, Note),
+        (add_sound, set_sound, clear_sound, sound, Sound),
+        (add_url, set_url, clear_url, url, Url),
+        (add_key, set_key, clear_key, key, Key),
+        (add_fburl, set_fburl, clear_fburl, fburl, FbURL),
+        (add_caluri, set_caluri, clear_caluri, caluri, CalURI),
+        (
+            add_caladuri,
+            set_caladuri,
+            clear_caladuri,
+            caladuri,
+            CalAdURI
+        )
+    );
+
+    remove_methods!(
+        (remove_source, source, Source),
+        (remove_xml, xml, Xml),
+        (remove_fn_property, fn_property, FN),
+        (remove_nickname, nickname, Nickname),
+        (remove_photo, photo, Photo),
+        (remove_adr, adr, Adr),
+        (remove_tel, tel, Tel),
+        (remove_email, email, Email),
+        (remove_impp, impp, Impp),
+        (remove_lang, lang, Lang),
+        (remove_tz, tz, Tz),
+        (remove_geo, geo, Geo),
+        (remove_title, title, Title),
+        (remove_role, role, Role),
+        (remove_logo, logo, Logo),
+        (remove_org, org, Org),
+        (remove_member, member, Member),
+        (remove_related, related, Related),
+        (remove_categories, categories, Categories),
+        (remove_note, note, Note),
+        (remove_sound, sound, Sound),
+        (remove_url, url, Url),
+        (remove_key, key, Key),
+        (remove_fburl, fburl, FbURL),
+        (remove_caluri, caluri, CalURI),
+        (remove_caladuri, caladuri, CalAdURI)
+    );
+}
+
+macro_rules! count_methods {
+    ($(($has:ident,$count:ident,$field:ident)),*) => {
+        $(
+        pub fn $has(&self) -> bool {
+            !self.$field.is_empty()
+        }
+
+        /// returns the number of values over all altids.
+        pub fn $count(&self) -> usize {
+            self.$field.total_count()
+        }
+        )*
+    };
+}
+
+macro_rules! alternatives_count_methods {
+    ($(($has:ident,$count:ident,$field:ident)),*) => {
+        $(
+        pub fn $has(&self) -> bool {
+            !self.$field.is_empty()
+        }
+
+        /// returns 1 if the property is present, alternatives with the same altid count as one value.
+        pub fn $count(&self) -> usize {
+            usize::from(self.$has())
+        }
+        )*
+    };
+}
+
+macro_rules! option_count_methods {
+    ($(($has:ident,$count:ident,$field:ident)),*) => {
+        $(
+        pub fn $has(&self) -> bool {
+            self.$field.is_some()
+        }
+
+        /// returns 1 if the property is present, 0 otherwise.
+        pub fn $count(&self) -> usize {
+            usize::from(self.$has())
+        }
+        )*
+    };
+}
+
+/// Methods to check which properties a vcard has.
+///
+/// For every property there is a `has_` and a `count_` method, `has_property` takes the name of the property.
+///
+/// ```
+/// use vcard::*;
+///
+/// let card = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\n\
+///     EMAIL;ALTID=1:heinrich@tosafjord.example\r\n\
+///     EMAIL;ALTID=1:heinrich@example.com\r\n\
+///     EMAIL:heiner@example.com\r\n\
+///     X-ABLABEL:fjord\r\n\
+///     END:VCARD\r\n")?;
+///
+/// assert!(card.has_email());
+/// assert_eq!(3, card.count_emails());
+/// assert!(!card.has_tel());
+/// assert_eq!(0, card.count_uid());
+/// assert!(card.has_property(PropertyName::Proprietary("x-ablabel".into())));
+/// # Ok::<(), VCardError>(())
+/// ```
+impl VCard {
+    option_count_methods!(
+        (has_kind, count_kind, kind),
+        (has_gender, count_gender, gender),
+        (has_prodid, count_prodid, prodid),
+        (has_rev, count_rev, rev),
+        (has_uid, count_uid, uid)
+    );
+
+    alternatives_count_methods!(
+        (has_n, count_n, n),
+        (has_bday, count_bday, bday),
+        (has_anniversary, count_anniversary, anniversary)
+    );
+
+    count_methods!(
+        (has_source, count_sources, source),
+        (has_xml, count_xmls, xml),
+        (has_fn_property, count_fn_properties, fn_property),
+        (has_nickname, count_nicknames, nickname),
+        (has_photo, count_photos, photo),
+        (has_adr, count_adrs, adr),
+        (has_tel, count_tels, tel),
+        (has_email, count_emails, email),
+        (has_impp, count_impps, impp),
+        (has_lang, count_langs, lang),
+        (has_tz, count_tzs, tz),
+        (has_geo, count_geos, geo),
+        (has_title, count_titles, title),
+        (has_role, count_roles, role),
+        (has_logo, count_logos, logo),
+        (has_org, count_orgs, org),
+        (has_member, count_members, member),
+        (has_related, count_related, related),
+        (has_categories, count_categories, categories),
+        (has_note, count_notes, note),
+        (has_sound, count_sounds, sound),
+        (has_url, count_urls, url),
+        (has_key, count_keys, key),
+        (has_fburl, count_fburls, fburl),
+        (has_caluri, count_caluris, caluri),
+        (has_caladuri, count_caladuris, caladuri)
+    );
+
+    pub fn has_clientpidmap(&self) -> bool {
+        !self.clientpidmap.is_empty()
+    }
+
+    pub fn count_clientpidmaps(&self) -> usize {
+        self.clientpidmap.len()
+    }
+
+    /// returns true if the vcard has at least one value of the property `name`.
+    pub fn has_property(&self, name: PropertyName) -> bool {
+        match name {
+            PropertyName::Version => true,
+            PropertyName::Source => self.has_source(),
+            PropertyName::Kind => self.has_kind(),
+            PropertyName::Xml => self.has_xml(),
+            PropertyName::FN => self.has_fn_property(),
+            PropertyName::N => self.has_n(),
+            PropertyName::NickName => self.has_nickname(),
+            PropertyName::Photo => self.has_photo(),
+            PropertyName::BDay => self.has_bday(),
+            PropertyName::Anniversary => self.has_anniversary(),
+            PropertyName::Gender => self.has_gender(),
+            PropertyName::Adr => self.has_adr(),
+            PropertyName::Tel => self.has_tel(),
+            PropertyName::Email => self.has_email(),
+            PropertyName::Impp => self.has_impp(),
+            PropertyName::Lang => self.has_lang(),
+            PropertyName::Tz => self.has_tz(),
+            PropertyName::Geo => self.has_geo(),
+            PropertyName::Title => self.has_title(),
+            PropertyName::Role => self.has_role(),
+            PropertyName::Logo => self.has_logo(),
+            PropertyName::Org => self.has_org(),
+            PropertyName::Member => self.has_member(),
+            PropertyName::Related => self.has_related(),
+            PropertyName::Categories => self.has_categories(),
+            PropertyName::Note => self.has_note(),
+            PropertyName::ProdId => self.has_prodid(),
+            PropertyName::Rev => self.has_rev(),
+            PropertyName::Sound => self.has_sound(),
+            PropertyName::Uid => self.has_uid(),
+            PropertyName::ClientPidMap => self.has_clientpidmap(),
+            PropertyName::Url => self.has_url(),
+            PropertyName::Key => self.has_key(),
+            PropertyName::FbUrl => self.has_fburl(),
+            PropertyName::CalUri => self.has_caluri(),
+            PropertyName::CalAdUri => self.has_caladuri(),
+            PropertyName::Legacy(name) => self.legacy.iter().any(|p| p.name == name),
+            PropertyName::Proprietary(name) => self.proprietary_by_name(&name).is_some(),
+        }
+    }
+}
+
+/// A builder for `VCard`s, created by `VCard::new`.
+///
+/// Every setter adds its property to the corresponding field of the vcard:
+/// - properties with a cardinality of `*` like `fn_property`, `email` or `tel` are added to their container.
+/// - properties with a cardinality of `*1` like `n` or `bday` return an error if the new value has
+///   a different ALTID than the existing values, see `AltIDContainer::add_value`.
+/// - properties with a cardinality of `1` or `*1` without ALTID like `kind` or `uid` replace the previous value,
+///   `build` reports the duplicate.
+///
+/// `build` validates the vcard, `build_unchecked` returns it as it is.
 pub struct VCardBuilder {
     vc: VCard,
+    // properties with a cardinality of *1 that have been set more than once
+    duplicates: Vec<&'static str>,
 }
 
 impl VCardBuilder {
     option_methods!(
         (kind, Kind),
         (gender, Gender),
-        (clientpidmap, ClientPidMap),
         (prodid, ProdId),
         (rev, Rev),
         (uid, Uid)
@@ -727,12 +2222,54 @@ impl VCardBuilder {
         (caluri, CalURI),
         (caladuri, CalAdURI)
     );
+    pub fn clientpidmap(mut self, clientpidmap: ClientPidMap) -> Self {
+        self.vc.clientpidmap.push(clientpidmap);
+        self
+    }
+
+    pub fn legacy(mut self, prop: LegacyProperty) -> Self {
+        self.vc.legacy.push(prop);
+        self
+    }
+
     pub fn proprietary(mut self, prop: ProprietaryProperty) -> Self {
         self.vc.proprietary_properties.push(prop);
         self
     }
 
-    pub fn build(self) -> VCard {
+    /// Validates and returns the vcard.
+    ///
+    /// Besides the constraints checked by `VCard::validate`, setting KIND, GENDER, PRODID, REV or UID
+    /// more than once is an error, and N, BDAY and ANNIVERSARY must not be set more than once
+    /// without a shared ALTID in any version. Fails only if there is at least one issue with `Severity::Error`,
+    /// all issues including warnings are returned at once.
+    /// `VCardError` implements `From<Vec<VCardValidationError>>`, so `?` can be used in functions returning `VCardError`.
+    pub fn build(self) -> Result<VCard, Vec<VCardValidationError>> {
+        let mut errors: Vec<VCardValidationError> = self
+            .duplicates
+            .iter()
+            .map(|field| {
+                VCardValidationError::error(
+                    &field.to_uppercase(),
+                    "property must not appear more than once",
+                    None,
+                )
+            })
+            .collect();
+        errors.extend(self.vc.validate_alternatives());
+        for e in self.vc.validate() {
+            if !errors.contains(&e) {
+                errors.push(e);
+            }
+        }
+        if errors.iter().any(|e| e.severity == Severity::Error) {
+            return Err(errors);
+        }
+        Ok(self.vc)
+    }
+
+    /// Returns the vcard without validating it.
+    pub fn build_unchecked(self) -> VCard {
         self.vc
     }
 }
@@ -747,52 +2284,73 @@ fn write_vcard_property<D: Display>(
     Ok(())
 }
 
+// Well known TYPE values are written in lowercase in vCard 4.0 and in uppercase in older versions.
+// The properties write them in uppercase if the alternate flag `{:#}` is set.
 impl Display for VCard {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.version.value == VersionValue::V4 {
+            write!(f, "{}", VCardProperties(self))
+        } else {
+            write!(f, "{:#}", VCardProperties(self))
+        }
+    }
+}
+
+// writes all properties of a vcard, the formatter flags are passed on to the properties
+pub(crate) struct VCardProperties<'a>(pub(crate) &'a VCard);
+
+impl Display for VCardProperties<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let card = self.0;
         write!(f, "BEGIN:VCARD\r\n")?;
-        write_vcard_property(f, &Some(&self.version))?;
-
-        self.source.fmt(f)?;
-        write_vcard_property(f, &self.kind)?;
-
-        self.xml.fmt(f)?;
-        self.fn_property.fmt(f)?;
-        self.n.fmt(f)?;
-        self.nickname.fmt(f)?;
-        self.photo.fmt(f)?;
-        self.bday.fmt(f)?;
-        self.anniversary.fmt(f)?;
-
-        write_vcard_property(f, &self.gender)?;
-
-        self.adr.fmt(f)?;
-        self.tel.fmt(f)?;
-        self.email.fmt(f)?;
-        self.impp.fmt(f)?;
-        self.lang.fmt(f)?;
-        self.tz.fmt(f)?;
-        self.geo.fmt(f)?;
-        self.title.fmt(f)?;
-        self.role.fmt(f)?;
-        self.logo.fmt(f)?;
-        self.org.fmt(f)?;
-        self.member.fmt(f)?;
-        self.related.fmt(f)?;
-        self.categories.fmt(f)?;
-        self.note.fmt(f)?;
-
-        write_vcard_property(f, &self.prodid)?;
-        write_vcard_property(f, &self.rev)?;
-        write_vcard_property(f, &self.uid)?;
-        write_vcard_property(f, &self.clientpidmap)?;
-
-        self.sound.fmt(f)?;
-        self.url.fmt(f)?;
-        self.key.fmt(f)?;
-        self.fburl.fmt(f)?;
-        self.caluri.fmt(f)?;
-        self.caladuri.fmt(f)?;
-        for prop in self.proprietary_properties.iter() {
+        write_vcard_property(f, &Some(&card.version))?;
+
+        card.source.fmt(f)?;
+        write_vcard_property(f, &card.kind)?;
+
+        card.xml.fmt(f)?;
+        card.fn_property.fmt(f)?;
+        card.n.fmt(f)?;
+        card.nickname.fmt(f)?;
+        card.photo.fmt(f)?;
+        card.bday.fmt(f)?;
+        card.anniversary.fmt(f)?;
+
+        write_vcard_property(f, &card.gender)?;
+
+        card.adr.fmt(f)?;
+        card.tel.fmt(f)?;
+        card.email.fmt(f)?;
+        card.impp.fmt(f)?;
+        card.lang.fmt(f)?;
+        card.tz.fmt(f)?;
+        card.geo.fmt(f)?;
+        card.title.fmt(f)?;
+        card.role.fmt(f)?;
+        card.logo.fmt(f)?;
+        card.org.fmt(f)?;
+        card.member.fmt(f)?;
+        card.related.fmt(f)?;
+        card.categories.fmt(f)?;
+        card.note.fmt(f)?;
+
+        write_vcard_property(f, &card.prodid)?;
+        write_vcard_property(f, &card.rev)?;
+        write_vcard_property(f, &card.uid)?;
+        for clientpidmap in card.clientpidmap.iter() {
+            clientpidmap.fmt(f)?;
+        }
+
+        card.sound.fmt(f)?;
+        card.url.fmt(f)?;
+        card.key.fmt(f)?;
+        card.fburl.fmt(f)?;
+        card.caluri.fmt(f)?;
+        card.caladuri.fmt(f)?;
+        for prop in card.legacy.iter() {
+            prop.fmt(f)?;
+        }
+        for prop in card.proprietary_properties.iter() {
             prop.fmt(f)?;
         }
         write!(f, "END:VCARD\r\n")
@@ -804,6 +2362,7 @@ mod tests {
     use std::vec;
 
     use super::*;
+    use crate::{Property, VCardWriter};
 
     #[test]
     fn test_display() -> Result<(), Box<dyn std::error::Error>> {
@@ -835,4 +2394,813 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uid_uuid() {
+        let uuid = uuid::Uuid::new_v4();
+        let uid = Uid::from_uuid(uuid);
+        assert_eq!(format!("urn:uuid:{}", uuid), uid.value);
+        assert_eq!(Some(uuid), uid.as_uuid());
+
+        let uid = Uid::new_uuid();
+        assert!(uid.as_uuid().is_some());
+
+        let uid = Uid {
+            value: "http://example.com/contacts/1".into(),
+            ..Default::default()
+        };
+        assert_eq!(None, uid.as_uuid());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN;ALTID=1;LANGUAGE=de:Heinrich vom Tosafjord\r\n\
+            FN;ALTID=1;LANGUAGE=en:Henry of Tosafjord\r\n\
+            N:vom Tosafjord;Heinrich;;;\r\n\
+            GENDER:M;\r\n\
+            TEL;VALUE=uri;TYPE=\"work,voice\";PREF=1:tel:+1-418-656-9254\r\n\
+            IMPP;PREF=1:xmpp:heinrich@example.com\r\n\
+            EMAIL;PID=1.1:heinrich@example.com\r\n\
+            X-FOO;BAR=baz:qux\r\n\
+            END:VCARD\r\n",
+        )?;
+
+        let json = serde_json::to_string(&card)?;
+        assert_eq!(card, serde_json::from_str::<VCard>(&json)?);
+
+        let uri: TelUri = serde_json::from_str("\"tel:+1-555-555-5555;ext=5555\"")?;
+        assert_eq!("+1-555-555-5555", uri.number());
+        assert_eq!(
+            "\"tel:+1-555-555-5555;ext=5555\"",
+            serde_json::to_string(&uri)?
+        );
+        // the tel: scheme is checked when deserializing, too
+        assert!(serde_json::from_str::<TelUri>("\"x\"").is_err());
+        assert!(serde_json::from_str::<TelValue>("{\"Uri\":\"x\"}").is_err());
+
+        let bytes = bincode::serialize(&card)?;
+        assert_eq!(card, bincode::deserialize::<VCard>(&bytes)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone() -> Result<(), Box<dyn std::error::Error>> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            SOURCE:http://directory.example.com/addressbooks/jdoe/Jean%20Dupont.vcf\r\n\
+            KIND:individual\r\n\
+            FN;ALTID=1;LANGUAGE=fr:Jean Dupont\r\n\
+            FN;ALTID=1;LANGUAGE=en:John Doe\r\n\
+            N;SORT-AS=Dupont:Dupont;Jean;;;\r\n\
+            NICKNAME:Jim,Jimmie\r\n\
+            PHOTO:http://www.example.com/pub/photos/jdupont.jpg\r\n\
+            BDAY:19531015T231000Z\r\n\
+            ANNIVERSARY:19960415\r\n\
+            GENDER:M;\r\n\
+            ADR;TYPE=work;LABEL=\"Mail Drop: TNE QB\\n123 Main Street\":;;123 Main Street;Any Town;CA;91921-1234;U.S.A.\r\n\
+            TEL;VALUE=uri;TYPE=\"voice,home\";PREF=1:tel:+1-555-555-5555;ext=5555\r\n\
+            EMAIL;TYPE=work:jean.dupont@example.com\r\n\
+            IMPP;PREF=1:xmpp:jean@example.com\r\n\
+            LANG;PREF=1:fr\r\n\
+            TZ:Europe/Paris\r\n\
+            GEO:geo:37.386013,-122.082932\r\n\
+            TITLE:Research Scientist\r\n\
+            ROLE:Project Leader\r\n\
+            LOGO:http://www.example.com/pub/logos/abccorp.jpg\r\n\
+            ORG:ABC\\, Inc.;North American Division;Marketing\r\n\
+            RELATED;TYPE=friend:urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6\r\n\
+            CATEGORIES:TRAVEL AGENT\r\n\
+            NOTE:This fax number is operational 0800 to 1715\r\n\
+            PRODID:-//ONLINE DIRECTORY//NONSGML Version 1//EN\r\n\
+            REV:19951031T222710Z\r\n\
+            SOUND:CID:JOHNQPUBLIC.part8.19960229T080000.xyzMail@example.com\r\n\
+            UID:urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6\r\n\
+            URL:http://example.org/restaurant.french/~chezchic.html\r\n\
+            KEY:http://www.example.com/keys/jdoe.cer\r\n\
+            FBURL;PREF=1:http://www.example.com/busy/janedoe\r\n\
+            CALURI:http://cal.example.com/calA\r\n\
+            item1.X-ABLABEL:Assistant\r\n\
+            END:VCARD\r\n",
+        )?;
+
+        let mut cloned = card.clone();
+        assert_eq!(card, cloned);
+
+        // the clone is independent of the original card
+        cloned.email.clear();
+        assert_eq!(1, card.email.len());
+        assert_ne!(card, cloned);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_adr_label() -> Result<(), Box<dyn std::error::Error>> {
+        let adr = Adr {
+            label: Some("123 Main Street\\nAny Town, CA 91921-1234\\nU.S.A.".into()),
+            street: vec!["123 Main Street".into()],
+            city: vec!["Any Town".into()],
+            ..Default::default()
+        };
+        let serialized = adr.to_string();
+        assert_eq!(
+            "ADR;LABEL=\"123 Main Street\\nAny Town, CA 91921-1234\\nU.S.A.\":;;123 Main Street;Any Town;;;\r\n",
+            serialized
+        );
+
+        match serialized.trim_end().parse()? {
+            Property::Adr(actual) => assert_eq!(adr, actual),
+            p => panic!("expected ADR but got {:?}", p),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_n_display_name() {
+        let mut n = N {
+            honorific_prefixes: vec!["Dr.".into()],
+            given_names: vec!["Heinrich".into()],
+            additional_names: vec!["Kasper".into(), "".into()],
+            surenames: vec!["Vom Tosafjord".into()],
+            honorific_suffixes: vec![" Von  und Zu".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            "Dr. Heinrich Kasper Vom Tosafjord Von und Zu",
+            n.to_display_name()
+        );
+        assert_eq!("", N::default().to_display_name());
+
+        assert_eq!("Vom Tosafjord Heinrich", n.to_sort_key());
+        n.sort_as = Some(vec!["Tosafjord".into(), "Heinrich".into()]);
+        assert_eq!("Tosafjord Heinrich", n.to_sort_key());
+    }
+
+    #[test]
+    fn test_n_sort_as_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let n = N {
+            sort_as: Some(vec!["Tosafjord".into(), "Heinrich".into()]),
+            surenames: vec!["vom Tosafjord".into()],
+            given_names: vec!["Heinrich".into()],
+            ..Default::default()
+        };
+        let raw = n.to_string();
+        assert_eq!(
+            "N;SORT-AS=\"Tosafjord,Heinrich\":vom Tosafjord;Heinrich;;;\r\n",
+            raw
+        );
+
+        match Property::from_str(raw.trim_end())? {
+            Property::N(parsed) => assert_eq!(n, parsed),
+            other => panic!("expected N but got {:?}", other),
+        }
+        Ok(())
+    }
+
+    // The `#[vcard]` macro writes the value of all properties without a special case with `self.value.as_str()`.
+    // This fails to compile if the value of one of them changes to a type without `as_str()`.
+    macro_rules! assert_value_as_str {
+        ($($type:ident),*) => {
+            $(
+            let _: fn(&$type) -> &str = |p| p.value.as_str();
+            )*
+        };
+    }
+
+    #[test]
+    fn test_value_as_str() {
+        assert_value_as_str!(
+            Source,
+            Xml,
+            FN,
+            BDay,
+            Anniversary,
+            Tel,
+            Email,
+            Impp,
+            Lang,
+            Title,
+            Role,
+            Member,
+            Related,
+            Note,
+            ProdId,
+            Rev,
+            Uid,
+            Url,
+            FbURL,
+            CalURI,
+            CalAdURI
+        );
+
+        let source = Source {
+            value: "https://example.com/heinrich.vcf".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            "SOURCE:https://example.com/heinrich.vcf\r\n",
+            source.to_string()
+        );
+        let member = Member {
+            value: "urn:uuid:03a0e51f-d1aa-4385-8a53-e29025acd8af".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            "MEMBER:urn:uuid:03a0e51f-d1aa-4385-8a53-e29025acd8af\r\n",
+            member.to_string()
+        );
+    }
+
+    #[test]
+    fn test_uri_property_names() -> Result<(), VCardError> {
+        let raw = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            URL;TYPE=home:https://tosafjord.example\r\n\
+            FBURL;PREF=1:http://www.example.com/busy/heinrich\r\n\
+            CALADRURI:mailto:heinrich@example.com\r\n\
+            CALURI:http://cal.example.com/heinrich\r\n\
+            END:VCARD\r\n";
+        let card = VCard::parse(raw)?;
+        assert_eq!(1, card.count_urls());
+        assert_eq!(1, card.count_caladuris());
+
+        let written = card.to_string();
+        for line in raw.split_terminator("\r\n") {
+            assert!(written.contains(line), "{} in {}", line, written);
+        }
+        assert_eq!(card, VCard::parse(&written)?);
+
+        // earlier versions wrote CALADRURI as CALADURI
+        let legacy = VCard::parse(&raw.replace("CALADRURI", "CALADURI"))?;
+        assert_eq!(card, legacy);
+        Ok(())
+    }
+
+    #[test]
+    fn test_has_property() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Heinrich vom Tosafjord\r\n\
+            N;ALTID=1:vom Tosafjord;Heinrich;;;\r\n\
+            N;ALTID=1:Tosafjord;Heiner;;;\r\n\
+            TEL;TYPE=cell:+49 176 10101520\r\n\
+            TEL;TYPE=home:+49 30 1234567\r\n\
+            LABEL;TYPE=home:Fjordweg 1\r\n\
+            X-ABLABEL:fjord\r\n\
+            END:VCARD\r\n",
+        )?;
+        assert_eq!(2, card.count_tels());
+        assert_eq!(1, card.count_n());
+        assert_eq!(0, card.count_emails());
+        assert_eq!(0, card.count_kind());
+        assert!(card.has_tel());
+        assert!(!card.has_email());
+
+        assert!(card.has_property(PropertyName::Version));
+        assert!(card.has_property(PropertyName::N));
+        assert!(!card.has_property(PropertyName::Uid));
+        assert!(card.has_property(PropertyName::Legacy(LegacyPropertyName::Label)));
+        assert!(!card.has_property(PropertyName::Legacy(LegacyPropertyName::Agent)));
+        assert!(card.has_property(PropertyName::Proprietary("X-ABLabel".into())));
+        assert!(!card.has_property(PropertyName::Proprietary("X-AB".into())));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_clientpidmaps() {
+        let card = VCard::new(VersionValue::V4)
+            .clientpidmap(ClientPidMap {
+                group: None,
+                pid_digit: 1,
+                value: "urn:uuid:3df403f4-5924-4bb7-b077-3c711d9eb34b"
+                    .parse()
+                    .unwrap(),
+            })
+            .clientpidmap(ClientPidMap {
+                group: None,
+                pid_digit: 2,
+                value: "urn:uuid:d89c9c7a-2e1b-4832-82de-7e992d95faa5"
+                    .parse()
+                    .unwrap(),
+            })
+            .build_unchecked();
+
+        assert_eq!(2, card.clientpidmap.len());
+        let raw = card.to_string();
+        assert!(raw.contains("CLIENTPIDMAP:1;urn:uuid:3df403f4-5924-4bb7-b077-3c711d9eb34b\r\n"));
+        assert!(raw.contains("CLIENTPIDMAP:2;urn:uuid:d89c9c7a-2e1b-4832-82de-7e992d95faa5\r\n"));
+
+        let parsed = VCard::parse(&raw).unwrap();
+        assert_eq!(card.clientpidmap, parsed.clientpidmap);
+        assert_eq!(raw, parsed.to_string());
+    }
+
+    #[test]
+    fn test_preferred_values() {
+        let card = VCard::new(VersionValue::V4)
+            .email(Email {
+                value: "first@example.com".into(),
+                ..Default::default()
+            })
+            .email(Email {
+                value: "second@example.com".into(),
+                ..Default::default()
+            })
+            .tel(Tel {
+                value: "tel:+49111".into(),
+                pref: Some(50),
+                ..Default::default()
+            })
+            .tel(Tel {
+                value: "tel:+49222".into(),
+                pref: Some(1),
+                ..Default::default()
+            })
+            .build_unchecked();
+
+        assert_eq!("first@example.com", card.preferred_email().unwrap().value);
+        assert_eq!("tel:+49222", card.preferred_tel().unwrap().value.as_str());
+        assert!(card.preferred_adr().is_none());
+    }
+
+    #[test]
+    fn test_preferred_url_and_nickname() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\n\
+            URL;PREF=2:https://example.com/heinrich\r\n\
+            URL;PREF=1:https://tosafjord.example\r\n\
+            NICKNAME:Heini\r\n\
+            NICKNAME;PREF=1:Tosa\r\n\
+            NOTE:first\r\n\
+            NOTE:second\r\n\
+            END:VCARD\r\n",
+        )?;
+
+        assert_eq!(
+            "https://tosafjord.example",
+            card.preferred_url().unwrap().value
+        );
+        assert_eq!(
+            vec!["Tosa".to_string()],
+            card.nickname.get_prefered_value().unwrap().value
+        );
+        // without PREF the first value is preferred
+        assert_eq!("first", card.note.get_prefered_value().unwrap().value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_name() -> Result<(), VCardError> {
+        let card = |lines: &str| {
+            crate::VCardReader::new(
+                format!("BEGIN:VCARD\r\nVERSION:4.0\r\n{}END:VCARD\r\n", lines).as_bytes(),
+            )
+            .parse_vcard_lenient()
+        };
+        let name = |lines: &str| -> Result<Option<String>, VCardError> {
+            Ok(card(lines)?.0.display_name())
+        };
+
+        assert_eq!(
+            Some("Heinrich".into()),
+            name("FN:Heinrich\r\nN:Tosafjord;Kasper;;;\r\n")?
+        );
+        assert_eq!(
+            Some("Kasper Tosafjord".into()),
+            name("FN:\r\nN:Tosafjord;Kasper;;;\r\n")?
+        );
+        assert_eq!(
+            Some("Knödel".into()),
+            name("NICKNAME:Knödel\r\nORG:Tosafjord GmbH\r\n")?
+        );
+        assert_eq!(
+            Some("Tosafjord GmbH".into()),
+            name("KIND:org\r\nORG:Tosafjord GmbH\r\n")?
+        );
+        assert_eq!(
+            Some("heinrich".into()),
+            name("EMAIL:heinrich@example.com\r\n")?
+        );
+        assert_eq!(Some("Smith, John".into()), name("FN:Smith\\, John\r\n")?);
+        assert_eq!(None, name("")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tel_types() -> Result<(), VCardError> {
+        let raw = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/good_vcards/apple_icloud.vcf"
+        ));
+        let card = VCard::parse(raw)?;
+        let tel = card.tel.iter().next().unwrap();
+        assert!(tel.is_mobile());
+        assert!(tel.has_type(&TelType::Voice));
+        assert!(tel.has_type(&TelType::Proprietary("PREF".into())));
+        assert!(!tel.has_type(&TelType::Fax));
+
+        let tel = Tel {
+            type_param: Some(vec!["iPhone".into()]),
+            ..Default::default()
+        };
+        assert!(tel.is_mobile());
+        assert!(!Tel::default().is_mobile());
+        Ok(())
+    }
+
+    #[test]
+    fn test_geo_value() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Heinrich\r\n\
+            GEO:37.386013;-122.082932\r\nEND:VCARD\r\n",
+        )?;
+        let geo = &card.geo.get_prefered_value().unwrap().value;
+        assert_eq!(
+            &GeoValue::Coords {
+                latitude: 37.386013,
+                longitude: -122.082932
+            },
+            geo
+        );
+        assert_eq!(Some(37.386013), geo.latitude());
+        assert!(card
+            .to_string()
+            .contains("\r\nGEO:37.386013;-122.082932\r\n"));
+        assert!(card
+            .to_string_as(VersionValue::V4)
+            .contains("\r\nGEO:geo:37.386013,-122.082932\r\n"));
+
+        let geo: GeoValue = "geo:37.386,-122.082;u=10".parse()?;
+        assert!(matches!(geo, GeoValue::Uri(_)));
+        assert_eq!(Some(37.386), geo.latitude());
+        assert_eq!(Some(-122.082), geo.longitude());
+        assert_eq!(Some(10.0), geo.uncertainty());
+        assert_eq!("geo:37.386,-122.082;u=10", geo.to_string());
+
+        let geo: GeoValue = "https://example.com/where".parse()?;
+        assert_eq!(None, geo.latitude());
+
+        for invalid in ["geo:91,10", "91;10", "10;181", "geo:", "north;south"] {
+            assert!(invalid.parse::<GeoValue>().is_err(), "{}", invalid);
+        }
+        assert!(VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nGEO:geo:91,10\r\nEND:VCARD\r\n"
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_proprietary_lookup() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            item1.X-ABLabel:PROFILE\r\n\
+            X-GOOGLE-TALK:heinrich\r\n\
+            x-abuid:1234\r\n\
+            item2.X-ABLABEL:BLOG\r\n\
+            END:VCARD\r\n",
+        )?;
+        let names: Vec<&str> = card
+            .proprietary_by_prefix("X-AB")
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(vec!["X-ABLabel", "x-abuid", "X-ABLABEL"], names);
+        assert_eq!(1, card.proprietary_by_prefix("x-google-").count());
+        assert_eq!(0, card.proprietary_by_prefix("X-ABLABEL-LONGER").count());
+
+        assert_eq!(
+            "PROFILE",
+            card.proprietary_by_name("x-ablabel").unwrap().value
+        );
+        assert!(card.proprietary_by_name("X-AB").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_value() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/pgp_key.vcf"
+        ));
+        let card = VCard::parse(testant)?;
+        let keys: Vec<&Key> = card.key.iter().collect();
+
+        match &keys[0].value {
+            KeyValue::Data { mediatype, bytes } => {
+                assert_eq!(Some("application/pgp-keys"), mediatype.as_deref());
+                // an OpenPGP public key packet
+                assert_eq!(0x98, bytes[0]);
+            }
+            v => panic!("expected an inline key, got {:?}", v),
+        }
+        assert!(keys[0].key_bytes().is_some_and(|k| k.len() > 50));
+        assert_eq!(
+            KeyValue::Text("01A1F8732C2BAE7C5538070294624AC08F970100".into()),
+            keys[1].value
+        );
+        assert!(keys[1].key_bytes().is_none());
+        assert!(matches!(keys[2].value, KeyValue::Uri(_)));
+
+        let mut writer = VCardWriter::new(Vec::new());
+        writer.write_vcard(&card)?;
+        assert_eq!(testant.as_bytes(), &writer.into_inner()[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tel_uri() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            TEL;VALUE=uri;TYPE=work:tel:+1-555-555-5555;ext=5555\r\n\
+            TEL:tel:7042;phone-context=example.com\r\n\
+            TEL:+49 176 1010-1520\r\n\
+            TEL;VALUE=uri:tel:call me\r\n\
+            END:VCARD\r\n",
+        )?;
+        let tels: Vec<&Tel> = card.tel.iter().collect();
+        match &tels[0].value {
+            TelValue::Uri(uri) => {
+                assert_eq!("+1-555-555-5555", uri.number());
+                assert!(uri.is_global());
+                assert_eq!(Some("5555"), uri.extension());
+            }
+            v => panic!("expected a tel: URI, got {:?}", v),
+        }
+        assert_eq!("+15555555555", tels[0].normalized_number());
+        match &tels[1].value {
+            TelValue::Uri(uri) => {
+                assert!(!uri.is_global());
+                assert_eq!(Some("example.com"), uri.phone_context());
+                assert_eq!(
+                    vec![("phone-context", Some("example.com"))],
+                    uri.parameters().collect::<Vec<_>>()
+                );
+            }
+            v => panic!("expected a tel: URI, got {:?}", v),
+        }
+        assert_eq!(TelValue::Text("+49 176 1010-1520".into()), tels[2].value);
+        assert_eq!("+4917610101520", tels[2].normalized_number());
+        // invalid URIs are kept as text
+        assert_eq!(TelValue::Text("tel:call me".into()), tels[3].value);
+
+        let raw = card.to_string();
+        assert!(raw.contains("TEL;VALUE=uri;TYPE=work:tel:+1-555-555-5555;ext=5555\r\n"));
+        assert!(raw.contains("TEL:tel:7042;phone-context=example.com\r\n"));
+
+        assert!("tel:".parse::<TelUri>().is_err());
+        assert!("tel:+1-555;=x".parse::<TelUri>().is_err());
+        assert!("mailto:heinrich@example.com".parse::<TelUri>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lang() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            LANG;PREF=1:zh-Hant-TW\r\n\
+            LANG;PREF=2:Deutsch\r\n\
+            END:VCARD\r\n",
+        )?;
+        let langs: Vec<&Lang> = card.lang.iter().collect();
+        assert_eq!("zh", langs[0].primary_language());
+        assert!(langs[0].value.is_well_formed());
+        // invalid tags are kept and reported by validate
+        assert_eq!("Deutsch", langs[1].value.as_str());
+        assert!(!langs[1].value.is_well_formed());
+        assert!(card
+            .validate()
+            .iter()
+            .any(|e| e.field == "LANG" && e.value.as_deref() == Some("Deutsch")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tz_value() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            TZ:-05:00\r\n\
+            TZ;VALUE=utc-offset:+0530\r\n\
+            TZ;VALUE=text:America/New_York\r\n\
+            TZ;VALUE=uri:https://example.com/tz/berlin\r\n\
+            TZ:-0030\r\n\
+            TZ:Z\r\n\
+            END:VCARD\r\n",
+        )?;
+        let values: Vec<&TzValue> = card.tz.iter().map(|t| &t.value).collect();
+        assert_eq!(
+            vec![
+                &TzValue::UtcOffset {
+                    hours: 5,
+                    minutes: 0,
+                    negative: true
+                },
+                &TzValue::UtcOffset {
+                    hours: 5,
+                    minutes: 30,
+                    negative: false
+                },
+                &TzValue::Text("America/New_York".into()),
+                &TzValue::Uri("https://example.com/tz/berlin".parse()?),
+                &TzValue::UtcOffset {
+                    hours: 0,
+                    minutes: 30,
+                    negative: true
+                },
+                &TzValue::Utc,
+            ],
+            values
+        );
+        let written: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            vec![
+                "-0500",
+                "+0530",
+                "America/New_York",
+                "https://example.com/tz/berlin",
+                "-0030",
+                "Z"
+            ],
+            written
+        );
+        assert_eq!(
+            TzValue::Utc,
+            TzValue::new("Z".into(), Some(&ValueDataType::UtcOffset))?
+        );
+        assert!(TzValue::new("5 hours".into(), Some(&ValueDataType::UtcOffset)).is_err());
+        assert!(TzValue::new("+0575".into(), Some(&ValueDataType::UtcOffset)).is_err());
+
+        #[cfg(feature = "chrono")]
+        {
+            assert_eq!(
+                chrono::FixedOffset::west_opt(30 * 60),
+                values[4].to_fixed_offset()
+            );
+            assert_eq!(None, values[2].to_fixed_offset());
+            assert_eq!(
+                chrono::FixedOffset::east_opt(0),
+                values[5].to_fixed_offset()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_preferred_email_order() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            EMAIL:none@example.com\r\n\
+            EMAIL;PREF=2:second@example.com\r\n\
+            EMAIL;PREF=1:first@example.com\r\n\
+            END:VCARD\r\n",
+        )?;
+        assert_eq!("first@example.com", card.preferred_email().unwrap().value);
+
+        let mut card = card;
+        card.remove_email(|e| e.pref == Some(1));
+        assert_eq!("second@example.com", card.preferred_email().unwrap().value);
+        card.remove_email(|e| e.pref == Some(2));
+        assert_eq!("none@example.com", card.preferred_email().unwrap().value);
+
+        assert!(VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nEMAIL;PREF=0:a@example.com\r\nEND:VCARD\r\n"
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_values() {
+        let mut card = VCard::new(VersionValue::V4)
+            .email(Email {
+                value: "first@example.com".into(),
+                ..Default::default()
+            })
+            .email(Email {
+                value: "second@example.com".into(),
+                ..Default::default()
+            })
+            .tel(Tel {
+                value: "tel:+49111".into(),
+                ..Default::default()
+            })
+            .build_unchecked();
+
+        assert_eq!(1, card.remove_email(|e| e.value == "first@example.com"));
+        assert_eq!("second@example.com", card.preferred_email().unwrap().value);
+        assert_eq!(0, card.remove_email(|e| e.value == "first@example.com"));
+
+        assert_eq!(1, card.remove_tel(|_| true));
+        assert!(card.tel.is_empty());
+        assert!(!card.to_string().contains("TEL"));
+    }
+
+    #[test]
+    fn test_mutation_methods() {
+        let mut card = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                altid: Some("1".into()),
+                language: Some("de".into()),
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .fn_property(FN {
+                altid: Some("1".into()),
+                language: Some("en".into()),
+                value: "Henry of Tosafjord".into(),
+                ..Default::default()
+            })
+            .fn_property(FN {
+                value: "Heinrich".into(),
+                ..Default::default()
+            })
+            .build_unchecked();
+
+        // only the alternatives with the same altid are replaced
+        card.set_fn_property(FN {
+            altid: Some("1".into()),
+            value: "Heinz".into(),
+            ..Default::default()
+        });
+        let values: Vec<&str> = card.fn_property.iter().map(|f| f.value.as_str()).collect();
+        assert_eq!(vec!["Heinrich", "Heinz"], values);
+
+        card.add_n(N {
+            altid: Some("1".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(card
+            .add_n(N {
+                altid: Some("2".into()),
+                ..Default::default()
+            })
+            .is_err());
+        card.set_n(N {
+            altid: Some("2".into()),
+            ..Default::default()
+        });
+        assert_eq!(1, card.n.len());
+
+        card.set_uid(Uid {
+            value: "1".into(),
+            ..Default::default()
+        });
+        card.set_uid(Uid {
+            value: "2".into(),
+            ..Default::default()
+        });
+        assert_eq!("2", card.uid.as_ref().unwrap().value);
+
+        card.add_photo(Photo {
+            value: PhotoValue::Uri("http://www.example.com/heinrich.jpg".parse().unwrap()),
+            ..Default::default()
+        });
+        assert_eq!(1, card.photo.total_count());
+        card.clear_photo();
+        assert!(card.photo.is_empty());
+    }
+
+    #[test]
+    fn test_validating_builder() {
+        let fn_property = || FN {
+            value: "Heinrich vom Tosafjord".into(),
+            ..Default::default()
+        };
+        assert!(VCard::new(VersionValue::V4)
+            .fn_property(fn_property())
+            .build()
+            .is_ok());
+
+        match VCard::new(VersionValue::V4).build() {
+            Err(errors) => {
+                assert_eq!(1, errors.len());
+                assert_eq!("FN", errors[0].field);
+            }
+            other => panic!("expected a missing FN but got {:?}", other),
+        }
+
+        let gender = || Gender {
+            sex: Some(Sex::Female),
+            ..Default::default()
+        };
+        let builder = VCard::new(VersionValue::V4)
+            .fn_property(fn_property())
+            .gender(gender())
+            .gender(gender());
+        match builder.build() {
+            Err(errors) => {
+                assert_eq!(1, errors.len());
+                assert_eq!("GENDER", errors[0].field);
+            }
+            other => panic!("expected a duplicate GENDER but got {:?}", other),
+        }
+
+        let card = VCard::new(VersionValue::V4).build_unchecked();
+        assert!(!card.is_valid());
+    }
 }
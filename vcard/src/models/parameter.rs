@@ -1,39 +1,81 @@
-use std::{fmt::Display, str::FromStr};
+use std::{borrow::Cow, fmt::Display, str::FromStr};
 
+use super::property::split_parameter;
 use crate::errors::VCardError;
 
-#[derive(Debug, PartialEq, strum_macros::AsRefStr)]
+#[derive(Debug, Clone, PartialEq, strum_macros::AsRefStr)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Parameter {
     Label(String),
     Language(String),
     Value(ValueDataType),
     Pref(u8),
     AltId(String),
-    Pid(Pid),
+    /// the values of PID, e.g. `PID=1.1,2.2`.
+    Pid(Vec<PidValue>),
     Type(Vec<String>),
     MediaType(String),
     CalScale(String),
     SortAs(Vec<String>),
     Geo(String),
     TimeZone(String),
+    /// ENCODING parameter of vCard 2.1 and 3.0, it does not exist in vCard 4.0.
+    Encoding(EncodingValue),
+    /// an unknown parameter as `NAME=VALUE`, e.g. `X-SERVICE-TYPE=Skype`.
     Proprietary(String),
 }
 
+/// Quotes a parameter value if necessary.
+///
+/// Values that contain a comma, semicolon, colon or double-quote are enclosed in double-quotes
+/// and embedded double-quotes are escaped as `\"`. All other values are returned as they are.
+/// See https://datatracker.ietf.org/doc/html/rfc6350#section-5
+pub fn quote_param_value(s: &str) -> String {
+    if s.contains([',', ';', ':', '"']) {
+        format!("\"{}\"", s.replace('"', "\\\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Encloses a parameter value in double-quotes and escapes embedded double-quotes as `\"`.
+///
+/// Used for LABEL and GEO, which RFC 6350 always shows quoted because addresses and URIs
+/// usually contain commas or colons, see https://datatracker.ietf.org/doc/html/rfc6350#section-6.3.1
+pub(crate) fn force_quote_param_value(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\\\""))
+}
+
+/// Removes the enclosing double-quotes of a quoted parameter value and unescapes embedded double-quotes.
+///
+/// This is the counterpart of `quote_param_value`. Values without double-quotes are returned as they are.
+pub fn unquote_param_value(s: &str) -> Cow<'_, str> {
+    match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) if inner.contains("\\\"") => Cow::Owned(inner.replace("\\\"", "\"")),
+        Some(inner) => Cow::Borrowed(inner),
+        None => Cow::Borrowed(s),
+    }
+}
+
 impl Display for Parameter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Label(l) => write!(f, "LABEL={}", l)?,
-            Self::Language(l) => write!(f, "LANGUAGE={}", l)?,
-            Self::Value(v) => write!(f, "VALUE={}", v.to_string())?,
+            Self::Label(l) => write!(f, "LABEL={}", force_quote_param_value(l))?,
+            Self::Language(l) => write!(f, "LANGUAGE={}", quote_param_value(l))?,
+            Self::Value(v) => write!(f, "VALUE={}", v)?,
             Self::Pref(p) => write!(f, "PREF={}", p)?,
-            Self::AltId(a) => write!(f, "ALTID={}", a)?,
-            Self::Pid(p) => write!(f, "PID={}", p)?,
-            Self::Type(t) => write!(f, "TYPE={}", t.join(","))?,
-            Self::MediaType(m) => write!(f, "MEDIATYPE={}", m)?,
-            Self::CalScale(c) => write!(f, "CALSCALE={}", c)?,
-            Self::SortAs(s) => write!(f, "SORT-AS={}", s.join(","))?,
-            Self::Geo(g) => write!(f, "GEO={}", g)?,
-            Self::TimeZone(t) => write!(f, "TZ={}", t)?,
+            Self::AltId(a) => write!(f, "ALTID={}", quote_param_value(a))?,
+            Self::Pid(p) => write!(f, "PID={}", join_pids(p))?,
+            Self::Type(t) => {
+                let types: Vec<String> = t.iter().map(|t| quote_param_value(t)).collect();
+                write!(f, "TYPE={}", types.join(","))?
+            }
+            Self::MediaType(m) => write!(f, "MEDIATYPE={}", quote_param_value(m))?,
+            Self::CalScale(c) => write!(f, "CALSCALE={}", quote_param_value(c))?,
+            Self::SortAs(s) => write!(f, "SORT-AS={}", quote_param_value(&s.join(",")))?,
+            Self::Geo(g) => write!(f, "GEO={}", force_quote_param_value(g))?,
+            Self::TimeZone(t) => write!(f, "TZ={}", quote_param_value(t))?,
+            Self::Encoding(e) => write!(f, "ENCODING={}", e)?,
             Self::Proprietary(p) => write!(f, "{}", p)?,
         }
 
@@ -41,6 +83,7 @@ impl Display for Parameter {
     }
 }
 
+const LABEL: &str = "label";
 const LANGUAGE: &str = "language";
 const VALUE: &str = "value";
 const PREF: &str = "pref";
@@ -52,57 +95,210 @@ const CALSCALE: &str = "calscale";
 const SORT_AS: &str = "sort-as";
 const GEO: &str = "geo";
 const TZ: &str = "tz";
+const ENCODING: &str = "encoding";
 
 impl FromStr for Parameter {
     type Err = VCardError;
 
     fn from_str(raw: &str) -> Result<Self, Self::Err> {
-        let (k, v) = raw.split_once("=").ok_or_else(|| VCardError::InvalidLine {
-            reason: "parameter has no = sign",
-            raw_line: raw.into(),
-        })?;
+        // vCard 2.1 allows parameters without a name like `TEL;HOME;VOICE:` or `NOTE;QUOTED-PRINTABLE:`
+        let (k, v) = split_parameter(raw)?;
+        Self::from_key_value(k, v)
+    }
+}
+
+impl Parameter {
+    /// Parses a parameter from its name and its raw value, e.g. `TYPE` and `home`.
+    pub fn from_key_value(k: &str, v: &str) -> Result<Self, VCardError> {
         let identifier = k.to_lowercase();
         let param = match &identifier[..] {
-            LANGUAGE => Parameter::Language(v.into()),
-            PREF => Parameter::Pref(v.parse()?),
-            ALTID => Parameter::AltId(v.into()),
-            PID => {
-                let mut split = v.split(".");
-                let first_digit = split
-                    .next()
-                    .map(u8::from_str)
-                    .ok_or_else(|| VCardError::InvalidPID { provided: v.into() })??;
-                let second_digit = if let Some(item) = split.next() {
-                    Some(u8::from_str(item)?)
-                } else {
-                    None
-                };
-                Parameter::Pid(Pid {
-                    first_digit,
-                    second_digit,
-                })
-            }
+            LABEL => Parameter::Label(unquote_param_value(v).into()),
+            LANGUAGE => Parameter::Language(unquote_param_value(v).into()),
+            PREF => match v.parse() {
+                Ok(pref) if (1..=100).contains(&pref) => Parameter::Pref(pref),
+                // see https://datatracker.ietf.org/doc/html/rfc6350#section-5.3
+                _ => {
+                    return Err(VCardError::InvalidValue {
+                        expected_values: "1-100".into(),
+                        actual_value: v.into(),
+                        raw_line: format!("{}={}", k, v),
+                    })
+                }
+            },
+            ALTID => Parameter::AltId(unquote_param_value(v).into()),
+            PID => Parameter::Pid(
+                unquote_param_value(v)
+                    .split(',')
+                    .map(PidValue::from_str)
+                    .collect::<Result<_, _>>()?,
+            ),
             VALUE => Self::Value(ValueDataType::from_str(v)?),
-            TYPE => Self::Type(v.split(",").map(String::from).collect()),
-            MEDIATYPE => Self::MediaType(v.into()),
-            CALSCALE => Self::CalScale(v.into()),
-            SORT_AS => Self::SortAs(v.split(",").map(String::from).collect()),
-            GEO => Self::Geo(v.into()),
-            TZ => Self::TimeZone(v.into()),
-            _ => Self::Proprietary(v.into()),
+            TYPE => Self::Type(
+                unquote_param_value(v)
+                    .split(',')
+                    .map(String::from)
+                    .collect(),
+            ),
+            MEDIATYPE => Self::MediaType(unquote_param_value(v).into()),
+            CALSCALE => Self::CalScale(unquote_param_value(v).into()),
+            SORT_AS => Self::SortAs(
+                unquote_param_value(v)
+                    .split(',')
+                    .map(String::from)
+                    .collect(),
+            ),
+            GEO => Self::Geo(unquote_param_value(v).into()),
+            TZ => Self::TimeZone(unquote_param_value(v).into()),
+            ENCODING => Self::Encoding(v.parse()?),
+            // the name is kept, so the parameter is written as it was read
+            _ => Self::Proprietary(format!("{}={}", k, v)),
         };
         Ok(param)
     }
 }
 
+// Generates an enum for the well known values of a TYPE parameter.
+// Parsing is case-insensitive, unknown values are kept as they are in the `Proprietary` variant.
+macro_rules! type_param_values {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:literal),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub enum $name {
+            $($variant,)*
+            Proprietary(String),
+        }
+
+        impl From<&str> for $name {
+            fn from(s: &str) -> Self {
+                match &s.to_lowercase()[..] {
+                    $($value => Self::$variant,)*
+                    _ => Self::Proprietary(s.into()),
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = VCardError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(Self::from(s))
+            }
+        }
+
+        // well known values are written in lowercase like in RFC 6350,
+        // the alternate flag `{:#}` writes them in uppercase like in vCard 3.0
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(Self::$variant if f.alternate() => write!(f, "{}", $value.to_uppercase()),)*
+                    $(Self::$variant => write!(f, "{}", $value),)*
+                    Self::Proprietary(p) => write!(f, "{}", p),
+                }
+            }
+        }
+    };
+}
+
+type_param_values!(
+    /// TYPE values of the TEL property.
+    ///
+    /// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.4.1
+    TelType {
+        Text => "text",
+        Voice => "voice",
+        Fax => "fax",
+        Cell => "cell",
+        Video => "video",
+        Pager => "pager",
+        TextPhone => "textphone",
+        Home => "home",
+        Work => "work",
+    }
+);
+
+type_param_values!(
+    /// TYPE values of the EMAIL property.
+    ///
+    /// `internet` is not part of RFC 6350 but is still emitted by most vCard 3.0 producers.
+    /// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.4.2
+    EmailType {
+        Home => "home",
+        Work => "work",
+        Internet => "internet",
+    }
+);
 
-#[derive(Debug, PartialEq)]
-pub struct Pid {
+type_param_values!(
+    /// TYPE values of the ADR property.
+    ///
+    /// `dom`, `intl`, `postal` and `parcel` are only defined for vCard 3.0 (RFC 2426).
+    /// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.3.1
+    AdrType {
+        Home => "home",
+        Work => "work",
+        Dom => "dom",
+        Intl => "intl",
+        Postal => "postal",
+        Parcel => "parcel",
+    }
+);
+
+type_param_values!(
+    /// TYPE values of the RELATED property.
+    ///
+    /// See https://datatracker.ietf.org/doc/html/rfc6350#section-6.6.6
+    RelatedType {
+        Contact => "contact",
+        Acquaintance => "acquaintance",
+        Friend => "friend",
+        Met => "met",
+        CoWorker => "co-worker",
+        Colleague => "colleague",
+        CoResident => "co-resident",
+        Neighbor => "neighbor",
+        Child => "child",
+        Parent => "parent",
+        Sibling => "sibling",
+        Spouse => "spouse",
+        Kin => "kin",
+        Muse => "muse",
+        Crush => "crush",
+        Date => "date",
+        Sweetheart => "sweetheart",
+        Me => "me",
+        Agent => "agent",
+        Emergency => "emergency",
+    }
+);
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PidValue {
     pub first_digit: u8,
+    /// the source identifier, it refers to the CLIENTPIDMAP with the same digit, see `VCard::resolve_pid_source`.
     pub second_digit: Option<u8>,
 }
 
-impl Display for Pid {
+impl FromStr for PidValue {
+    type Err = VCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || VCardError::InvalidPID { provided: s.into() };
+        let (first, second) = match s.split_once('.') {
+            Some((first, second)) => (first, Some(second)),
+            None => (s, None),
+        };
+        Ok(Self {
+            first_digit: first.parse().map_err(|_| invalid())?,
+            second_digit: second
+                .map(|d| d.parse().map_err(|_| invalid()))
+                .transpose()?,
+        })
+    }
+}
+
+impl Display for PidValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(d) = self.second_digit {
             write!(f, "{}.{}", self.first_digit, d)
@@ -112,8 +308,177 @@ impl Display for Pid {
     }
 }
 
+// joins the values of a PID parameter with commas, e.g. `1.1,2.2`
+pub(crate) fn join_pids(pids: &[PidValue]) -> String {
+    pids.iter()
+        .map(PidValue::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// value of the MEDIATYPE parameter like `image/png` or `text/plain;charset=utf-8`,
+/// see https://datatracker.ietf.org/doc/html/rfc6350#section-5.7
+///
+/// The value is kept as it was read, so it is written back exactly, even if it is not well-formed.
+/// Media types are equal if their type, subtype and attribute names are equal ignoring case
+/// and their attribute values are equal.
+///
+/// ```
+/// use vcard::*;
+///
+/// let media_type = MediaType::from("Image/PNG");
+/// assert_eq!("image", media_type.type_name().to_lowercase());
+/// assert_eq!(MediaType::from("image/png"), media_type);
+/// assert_eq!("Image/PNG", media_type.to_string());
+/// ```
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct MediaType(String);
+
+impl MediaType {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// returns the type, e.g. `text` for `text/plain;charset=utf-8`.
+    pub fn type_name(&self) -> &str {
+        self.essence().split('/').next().unwrap_or_default()
+    }
+
+    /// returns the subtype, e.g. `plain` for `text/plain;charset=utf-8`.
+    pub fn subtype(&self) -> &str {
+        self.essence()
+            .split_once('/')
+            .map(|(_, subtype)| subtype)
+            .unwrap_or_default()
+    }
+
+    /// returns the attributes as pairs of name and value, e.g. `("charset", "utf-8")`.
+    /// Quoted values are returned with their double-quotes.
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.split(';').skip(1).map(|attribute| {
+            let (name, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            (name.trim(), value.trim())
+        })
+    }
+
+    /// returns true if the media type follows the syntax of RFC 6838 like `type/subtype;name=value`.
+    /// See https://datatracker.ietf.org/doc/html/rfc6838#section-4.2
+    pub fn is_well_formed(&self) -> bool {
+        let is_name = |name: &str| {
+            name.len() <= 127
+                && name
+                    .bytes()
+                    .next()
+                    .is_some_and(|b| b.is_ascii_alphanumeric())
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(&b))
+        };
+        let is_value = |value: &str| {
+            (value.len() >= 2 && value.starts_with('"') && value.ends_with('"'))
+                || (!value.is_empty()
+                    && value
+                        .bytes()
+                        .all(|b| b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?=".contains(&b)))
+        };
+        let essence = self.essence();
+        essence.len() == self.0.split(';').next().unwrap_or_default().len()
+            && essence
+                .split_once('/')
+                .is_some_and(|(t, subtype)| is_name(t) && is_name(subtype))
+            && self
+                .attributes()
+                .all(|(name, value)| is_name(name) && is_value(value))
+    }
+
+    // type and subtype without attributes, e.g. `text/plain`
+    fn essence(&self) -> &str {
+        self.0.split(';').next().unwrap_or_default().trim()
+    }
+}
+
+impl PartialEq for MediaType {
+    fn eq(&self, other: &Self) -> bool {
+        let attributes = |m: &Self| -> Vec<(String, String)> {
+            m.attributes()
+                .map(|(name, value)| (name.to_lowercase(), value.to_string()))
+                .collect()
+        };
+        self.essence().eq_ignore_ascii_case(other.essence())
+            && attributes(self) == attributes(other)
+    }
+}
+
+impl AsRef<str> for MediaType {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for MediaType {
+    fn from(media_type: String) -> Self {
+        Self(media_type)
+    }
+}
+
+impl From<&str> for MediaType {
+    fn from(media_type: &str) -> Self {
+        Self(media_type.to_string())
+    }
+}
+
+/// Encoding of a property value in vCard 2.1 and 3.0.
+///
+/// Values with `QuotedPrintable` are decoded while parsing. Values of PHOTO, LOGO, SOUND and KEY
+/// with `Base64` are stored as `data:` URI like in vCard 4.0.
+/// See https://datatracker.ietf.org/doc/html/rfc2426#section-5.1
+#[derive(strum_macros::AsRefStr, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EncodingValue {
+    /// `b` in vCard 3.0, `BASE64` in vCard 2.1
+    #[strum(serialize = "b")]
+    Base64,
+    #[strum(serialize = "QUOTED-PRINTABLE")]
+    QuotedPrintable,
+    Proprietary(String),
+}
+
+impl Display for EncodingValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Proprietary(p) => write!(f, "{}", p),
+            _ => write!(f, "{}", self.as_ref()),
+        }
+    }
+}
+
+impl FromStr for EncodingValue {
+    type Err = VCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let result = match &s.to_lowercase()[..] {
+            "b" | "base64" => Self::Base64,
+            "quoted-printable" => Self::QuotedPrintable,
+            _ => Self::Proprietary(s.into()),
+        };
+        Ok(result)
+    }
+}
+
 /// See https://datatracker.ietf.org/doc/html/rfc6350#section-5.2
-#[derive(strum_macros::AsRefStr, Debug, PartialEq)]
+#[derive(strum_macros::AsRefStr, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueDataType {
     #[strum(serialize = "uri")]
     Uri,
@@ -192,4 +557,179 @@ impl FromStr for ValueDataType {
         };
         Ok(t)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Property, VCard};
+
+    #[test]
+    fn test_type_param_values() {
+        assert_eq!(TelType::Cell, "cell".parse().unwrap());
+        assert_eq!(TelType::Cell, TelType::from("CeLL"));
+        assert_eq!(TelType::TextPhone, TelType::from("textphone"));
+        assert_eq!(TelType::Proprietary("pref".into()), TelType::from("pref"));
+        assert_eq!(RelatedType::CoWorker, RelatedType::from("Co-Worker"));
+
+        assert_eq!("cell", TelType::Cell.to_string());
+        assert_eq!("co-worker", RelatedType::CoWorker.to_string());
+        assert_eq!("CO-WORKER", format!("{:#}", RelatedType::CoWorker));
+        assert_eq!(
+            "x-Custom",
+            EmailType::Proprietary("x-Custom".into()).to_string()
+        );
+    }
+
+    #[test]
+    fn test_unnamed_parameters() -> Result<(), VCardError> {
+        assert_eq!(
+            Parameter::Encoding(EncodingValue::QuotedPrintable),
+            "QUOTED-PRINTABLE".parse()?
+        );
+        assert_eq!(
+            Parameter::Encoding(EncodingValue::Base64),
+            "base64".parse()?
+        );
+        assert_eq!(Parameter::Type(vec!["HOME".into()]), "HOME".parse()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pref_range() {
+        assert_eq!(Parameter::Pref(1), "PREF=1".parse().unwrap());
+        assert_eq!(Parameter::Pref(100), "pref=100".parse().unwrap());
+        for invalid in [
+            "PREF=0", "PREF=101", "PREF=200", "PREF=300", "PREF=-1", "PREF=",
+        ] {
+            assert!(
+                matches!(
+                    invalid.parse::<Parameter>(),
+                    Err(VCardError::InvalidValue { .. })
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_proprietary_display() -> Result<(), VCardError> {
+        let param: Parameter = "X-SERVICE-TYPE=Skype".parse()?;
+        assert_eq!(Parameter::Proprietary("X-SERVICE-TYPE=Skype".into()), param);
+        assert_eq!("X-SERVICE-TYPE=Skype", param.to_string());
+        assert_eq!(
+            "x-list=\"a,b\"",
+            "x-list=\"a,b\"".parse::<Parameter>()?.to_string()
+        );
+
+        let line = "X-CUSTOM;X-PARAM=value;X-QUOTED=\"a,b\":text\r\n";
+        match line.trim_end().parse()? {
+            Property::Proprietary(p) => assert_eq!(line, p.to_string()),
+            p => panic!("expected a proprietary property, got {:?}", p),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pid() -> Result<(), VCardError> {
+        assert_eq!(
+            Parameter::Pid(vec![
+                PidValue {
+                    first_digit: 1,
+                    second_digit: Some(1),
+                },
+                PidValue {
+                    first_digit: 2,
+                    second_digit: None,
+                },
+            ]),
+            "PID=1.1,2".parse()?
+        );
+        for invalid in ["PID=", "PID=1.", "PID=a.1", "PID=1,,2", "PID=1.1.1"] {
+            assert!(
+                matches!(
+                    invalid.parse::<Parameter>(),
+                    Err(VCardError::InvalidPID { .. })
+                ),
+                "{}",
+                invalid
+            );
+        }
+
+        let line = "EMAIL;PID=1.1,2.2:heinrich@example.com\r\n";
+        match line.trim_end().parse()? {
+            Property::Email(e) => {
+                assert_eq!(2, e.pid.as_ref().map_or(0, Vec::len));
+                assert_eq!(line, e.to_string());
+            }
+            p => panic!("expected an email, got {:?}", p),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_param_value() {
+        assert_eq!("home", quote_param_value("home"));
+        assert_eq!("\"geo:50.1,8.6\"", quote_param_value("geo:50.1,8.6"));
+        assert_eq!("\"a;b\"", quote_param_value("a;b"));
+        assert_eq!("\"say \\\"hi\\\"\"", quote_param_value("say \"hi\""));
+        for value in ["home", "geo:50.1,8.6", "say \"hi\""] {
+            assert_eq!(value, unquote_param_value(&quote_param_value(value)));
+        }
+
+        assert_eq!(
+            "LABEL=\"Main Street, Any Town\"",
+            Parameter::Label("Main Street, Any Town".into()).to_string()
+        );
+        assert_eq!(
+            "TYPE=home,\"x-a;b\"",
+            Parameter::Type(vec!["home".into(), "x-a;b".into()]).to_string()
+        );
+    }
+
+    #[test]
+    fn test_quoted_parameters_round_trip() -> Result<(), VCardError> {
+        for param in [
+            "LANGUAGE=\"de-DE\"",
+            "ALTID=\"1\"",
+            "ALTID=\"a,b\"",
+            "CALSCALE=\"gregorian\"",
+            "MEDIATYPE=\"text/plain\"",
+            "TZ=\"Europe/Berlin\"",
+            "SORT-AS=\"vom Tosafjord,Heinrich\"",
+            "TYPE=\"home,work\"",
+        ] {
+            let read: Parameter = param.parse()?;
+            let written = read.to_string();
+            assert_eq!(
+                read,
+                written.parse()?,
+                "{} was written as {}",
+                param,
+                written
+            );
+            assert!(
+                !written.contains("\\\""),
+                "{} was written as {}",
+                param,
+                written
+            );
+        }
+
+        let input = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            NOTE;LANGUAGE=\"de-DE\";ALTID=\"1\":hallo\r\n\
+            BDAY;CALSCALE=\"gregorian\":20180301\r\nEND:VCARD\r\n";
+        let card = VCard::parse(input)?;
+        let note = card.note.iter().next().unwrap();
+        assert_eq!(Some("de-DE"), note.language.as_deref());
+        assert_eq!(Some("1"), note.altid.as_deref());
+        let written = card.to_string();
+        assert!(
+            written.contains("NOTE;ALTID=1;LANGUAGE=de-DE:hallo\r\n"),
+            "{}",
+            written
+        );
+        assert_eq!(card, VCard::parse(&written)?);
+        Ok(())
+    }
+}
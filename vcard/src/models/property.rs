@@ -1,11 +1,14 @@
-use regex::Regex;
-use std::str::FromStr;
+use std::{convert::TryFrom, fmt::Display, str::FromStr};
 
-use crate::errors::VCardError;
+use crate::{
+    errors::VCardError,
+    escape::{split_unescaped, unescape},
+};
 
 use super::*;
 
-#[derive(strum_macros::AsRefStr, Debug, PartialEq)]
+#[derive(strum_macros::AsRefStr, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Property {
     #[strum(serialize = "begin")]
     Begin {
@@ -73,7 +76,7 @@ pub enum Property {
     Sound(Sound),
     #[strum(serialize = "uid")]
     Uid(Uid),
-    #[strum(serialize = "clientidmap")]
+    #[strum(serialize = "clientpidmap")]
     ClientPidMap(ClientPidMap),
     #[strum(serialize = "url")]
     Url(Url),
@@ -81,15 +84,63 @@ pub enum Property {
     Key(Key),
     #[strum(serialize = "fburl")]
     FbUrl(FbURL),
-    #[strum(serialize = "caladuri")]
+    #[strum(serialize = "caladruri")]
     CalAdUri(CalAdURI),
     #[strum(serialize = "caluri")]
     CalUri(CalURI),
     #[strum(serialize = "xml")]
     Xml(Xml),
+    Legacy(LegacyProperty),
     Proprietary(ProprietaryProperty),
 }
 
+impl Display for Property {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Begin { value } => write!(f, "BEGIN:{}\r\n", value),
+            Self::End { value } => write!(f, "END:{}\r\n", value),
+            Self::Version(p) => p.fmt(f),
+            Self::Source(p) => p.fmt(f),
+            Self::Kind(p) => p.fmt(f),
+            Self::FN(p) => p.fmt(f),
+            Self::N(p) => p.fmt(f),
+            Self::NickName(p) => p.fmt(f),
+            Self::Photo(p) => p.fmt(f),
+            Self::BDay(p) => p.fmt(f),
+            Self::Anniversary(p) => p.fmt(f),
+            Self::Gender(p) => p.fmt(f),
+            Self::Adr(p) => p.fmt(f),
+            Self::Tel(p) => p.fmt(f),
+            Self::Email(p) => p.fmt(f),
+            Self::Impp(p) => p.fmt(f),
+            Self::Lang(p) => p.fmt(f),
+            Self::Tz(p) => p.fmt(f),
+            Self::Geo(p) => p.fmt(f),
+            Self::Title(p) => p.fmt(f),
+            Self::Role(p) => p.fmt(f),
+            Self::Logo(p) => p.fmt(f),
+            Self::Org(p) => p.fmt(f),
+            Self::Member(p) => p.fmt(f),
+            Self::Related(p) => p.fmt(f),
+            Self::Categories(p) => p.fmt(f),
+            Self::Note(p) => p.fmt(f),
+            Self::ProdId(p) => p.fmt(f),
+            Self::Rev(p) => p.fmt(f),
+            Self::Sound(p) => p.fmt(f),
+            Self::Uid(p) => p.fmt(f),
+            Self::ClientPidMap(p) => p.fmt(f),
+            Self::Url(p) => p.fmt(f),
+            Self::Key(p) => p.fmt(f),
+            Self::FbUrl(p) => p.fmt(f),
+            Self::CalAdUri(p) => p.fmt(f),
+            Self::CalUri(p) => p.fmt(f),
+            Self::Xml(p) => p.fmt(f),
+            Self::Legacy(p) => p.fmt(f),
+            Self::Proprietary(p) => p.fmt(f),
+        }
+    }
+}
+
 fn filter_and_transform<A: AsRef<str>>(input: A) -> Option<String> {
     if input.as_ref().is_empty() {
         None
@@ -98,98 +149,297 @@ fn filter_and_transform<A: AsRef<str>>(input: A) -> Option<String> {
     }
 }
 
-fn parse_parameters(raw: &str) -> Result<Vec<Parameter>, VCardError> {
+fn typed_type_param<T: for<'a> From<&'a str>>(types: Option<Vec<String>>) -> Option<Vec<T>> {
+    types.map(|types| types.iter().map(|t| T::from(t)).collect())
+}
+
+// states of the parameter scanner in `parse_parameters`
+#[derive(Clone, Copy)]
+enum ParameterState {
+    Unquoted,
+    // the previous character was a backslash outside of double-quotes
+    UnquotedEscape,
+    Quoted,
+    // the previous character was a backslash within double-quotes
+    QuotedEscape,
+}
+
+// splits the raw parameters of a content line into pairs of name and value.
+//
+// Semicolons only separate parameters if they are neither escaped (in the form \;)
+// nor part of a quoted value like LABEL="Main St; Suite 4".
+// Quoted values are returned with their double-quotes, see `unquote_param_value`.
+fn parse_parameters(raw: &str) -> Result<Vec<(&str, &str)>, VCardError> {
     let raw = raw.trim_start_matches(";");
     let mut result = Vec::new();
-    let mut prev = 0;
-    let mut buf = Vec::new();
-    for char in raw.as_bytes() {
-        // it is possible that a parameter contains an escaped semicolon (in the form \;).
-        // We have to ensure those semicolons are not parsed as a separate parameter.
-        if *char == b';' && prev != b'\\' {
-            let s = std::str::from_utf8(&buf)?;
-            let param = s.parse()?;
-            result.push(param);
-            buf.clear();
-        } else {
-            prev = *char;
-            buf.push(*char);
-        }
+    let mut start = 0;
+    let mut state = ParameterState::Unquoted;
+    for (i, char) in raw.bytes().enumerate() {
+        state = match (state, char) {
+            (ParameterState::Unquoted, b';') => {
+                result.push(split_parameter(&raw[start..i])?);
+                start = i + 1;
+                ParameterState::Unquoted
+            }
+            (ParameterState::Unquoted, b'"') => ParameterState::Quoted,
+            (ParameterState::Unquoted, b'\\') => ParameterState::UnquotedEscape,
+            (ParameterState::UnquotedEscape, _) => ParameterState::Unquoted,
+            (ParameterState::Quoted, b'"') => ParameterState::Unquoted,
+            (ParameterState::Quoted, b'\\') => ParameterState::QuotedEscape,
+            (ParameterState::QuotedEscape, _) => ParameterState::Quoted,
+            (state, _) => state,
+        };
+    }
+    if let ParameterState::Quoted | ParameterState::QuotedEscape = state {
+        return Err(VCardError::InvalidLine {
+            reason: "quoted parameter value has no closing double-quote",
+            raw_line: raw.into(),
+        });
     }
     // ensure that the last entry gets added as well.
-    let s = std::str::from_utf8(&buf)?;
-    let param = s.parse()?;
-    result.push(param);
+    result.push(split_parameter(&raw[start..])?);
     Ok(result)
 }
 
-fn escaped_split(item: &str, split: char) -> impl Iterator<Item = String> {
-    let escape_char = '\\';
-    let mut result = Vec::new();
-    let mut escaped_value = false;
-    let mut buf = String::new();
-    for c in item.chars() {
-        // add escaped values no matter what
-        if escaped_value {
-            buf.push(c);
-            escaped_value = false;
-            continue;
+// vCard 2.1 allows parameters without a name like `TEL;HOME;VOICE:` or `NOTE;QUOTED-PRINTABLE:`,
+// those are treated as ENCODING or TYPE parameters.
+pub(crate) fn split_parameter(raw: &str) -> Result<(&str, &str), VCardError> {
+    match raw.split_once('=') {
+        Some(param) => Ok(param),
+        None if raw.is_empty() => Err(VCardError::InvalidLine {
+            reason: "parameter has no = sign",
+            raw_line: raw.into(),
+        }),
+        None if ENCODINGS.iter().any(|e| e.eq_ignore_ascii_case(raw)) => Ok(("ENCODING", raw)),
+        None => Ok(("TYPE", raw)),
+    }
+}
+
+// returns the top-level media type of properties with binary values, e.g. `image` for PHOTO.
+fn binary_media_type(name: &str) -> Option<&'static str> {
+    crate::conversion::MEDIA_PROPERTIES
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, media_type)| *media_type)
+}
+
+// builds a data: URI from a base64 encoded value. The media type is taken from MEDIATYPE
+// or from a TYPE like `JPEG`, which is removed. Folding whitespace is removed from the value.
+fn to_data_uri(
+    value: &str,
+    media_type: &str,
+    mediatype: Option<&str>,
+    type_param: &mut Option<Vec<String>>,
+) -> String {
+    let format = type_param
+        .as_ref()
+        .and_then(|types| {
+            types
+                .iter()
+                .position(|t| crate::conversion::is_media_format(t))
+        })
+        .map(|i| type_param.as_mut().unwrap().remove(i));
+    if type_param.as_ref().is_some_and(Vec::is_empty) {
+        *type_param = None;
+    }
+    let media_type = match (mediatype, format) {
+        (Some(m), _) => m.to_string(),
+        (None, Some(format)) => format!("{}/{}", media_type, format.to_lowercase()),
+        (None, None) => "application/octet-stream".into(),
+    };
+    let data: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    format!("data:{};base64,{}", media_type, data)
+}
+
+// encodings that are defined by vCard 2.1
+const ENCODINGS: &[&str] = &["7BIT", "8BIT", "QUOTED-PRINTABLE", "BASE64", "B"];
+
+// decodes a quoted-printable value of vCard 2.1.
+// Line breaks are escaped as `\n`, invalid escape sequences are kept as they are.
+fn decode_quoted_printable(value: &str, charset: Option<&str>) -> String {
+    let raw = value.as_bytes();
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let decoded = raw
+            .get(i + 1..i + 3)
+            .filter(|_| raw[i] == b'=')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match decoded {
+            Some(b) => {
+                bytes.push(b);
+                i += 3;
+            }
+            None => {
+                bytes.push(raw[i]);
+                i += 1;
+            }
         }
+    }
 
-        if c == escape_char {
-            escaped_value = true
-        } else if c == split {
-            result.push(buf);
-            buf = String::new();
-        } else {
-            buf.push(c)
+    let is_utf8 = charset.is_none_or(|c| c.eq_ignore_ascii_case("UTF-8"));
+    let text = match String::from_utf8(bytes) {
+        Ok(text) if is_utf8 => text,
+        // everything else is treated as ISO-8859-1
+        Ok(text) => text.bytes().map(char::from).collect(),
+        Err(e) => e.into_bytes().into_iter().map(char::from).collect(),
+    };
+    text.replace("\r\n", "\\n").replace('\n', "\\n")
+}
+
+// splits a content line into group, name, parameters and value.
+// see https://datatracker.ietf.org/doc/html/rfc6350#section-3.3
+//
+// The parameters are returned including the leading semicolon.
+// A colon inside a quoted parameter value does not end the parameters.
+fn split_content_line(line: &str) -> Option<(Option<&str>, &str, Option<&str>, &str)> {
+    let name_end = line.find([';', ':'])?;
+    let group_and_name = &line[..name_end];
+    if group_and_name.is_empty() {
+        return None;
+    }
+
+    // the name has to consist of at least one character
+    let last_char = group_and_name.char_indices().last().map_or(0, |(i, _)| i);
+    let (group, name) = match group_and_name[..last_char].rfind('.') {
+        Some(pos) if pos > 0 => (Some(&group_and_name[..pos]), &group_and_name[pos + 1..]),
+        _ => (None, group_and_name),
+    };
+
+    let mut value_start = name_end;
+    if line.as_bytes()[name_end] == b';' {
+        let mut quoted = false;
+        let mut escaped = false;
+        let colon = line[name_end..].char_indices().find(|(_, c)| {
+            if escaped {
+                escaped = false;
+                return false;
+            }
+            match c {
+                // only escaped double-quotes within quoted values are relevant here
+                '\\' => {
+                    escaped = quoted;
+                    false
+                }
+                '"' => {
+                    quoted = !quoted;
+                    false
+                }
+                ':' => !quoted,
+                _ => false,
+            }
+        })?;
+        value_start = name_end + colon.0;
+        // at least one character has to follow the semicolon
+        if value_start - name_end < 2 {
+            return None;
         }
     }
-    result.push(buf);
 
-    result.into_iter()
+    let parameters = if value_start > name_end {
+        Some(&line[name_end..value_start])
+    } else {
+        None
+    };
+    Some((group, name, parameters, &line[value_start + 1..]))
 }
 
-lazy_static::lazy_static! {
-    static ref RE: Regex = Regex::new(r"(?P<group>[^;:]+\.)?(?P<name>[^;:]+)(?P<parameter>;[^:]+)*:(?P<value>.*)").unwrap();
+/// A content line that is split into its components, but not parsed any further.
+///
+/// All components borrow from the raw line, which makes it cheap to filter properties by name
+/// before parsing them into a `Property` with `Property::try_from`.
+/// See https://datatracker.ietf.org/doc/html/rfc6350#section-3.3
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentLine<'a> {
+    pub group: Option<&'a str>,
+    pub name: &'a str,
+    /// pairs of parameter name and raw parameter value
+    pub params: Vec<(&'a str, &'a str)>,
+    pub value: &'a str,
 }
-impl FromStr for Property {
-    type Err = VCardError;
 
-    fn from_str(line: &str) -> Result<Self, Self::Err> {
-        let captures = if let Some(captures) = RE.captures(&line) {
-            captures
-        } else {
-            return Err(VCardError::InvalidLine {
+impl<'a> ContentLine<'a> {
+    /// Splits a single unfolded content line into its components.
+    pub fn parse(line: &'a str) -> Result<Self, VCardError> {
+        let (group, name, parameter, value) =
+            split_content_line(line).ok_or_else(|| VCardError::InvalidLine {
                 reason: "does not match property pattern",
                 raw_line: line.into(),
-            });
-        };
-        let group = captures
-            .name("group")
-            .map(|m| m.as_str().trim_end_matches(".").to_string());
-        let name =
-            captures
-                .name("name")
-                .map(|m| m.as_str())
-                .ok_or_else(|| VCardError::InvalidLine {
-                    reason: "no name found",
-                    raw_line: line.into(),
-                })?;
-        let parameter = captures.name("parameter").map(|m| m.as_str());
-        let value = captures
-            .name("value")
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| VCardError::InvalidLine {
-                reason: "no value found",
-                raw_line: line.into(),
             })?;
-        let name = name.trim_matches(char::from(0));
-        let parameters = if let Some(raw_parameter) = parameter {
+        let params = if let Some(raw_parameter) = parameter {
             parse_parameters(raw_parameter)?
         } else {
             Vec::new()
         };
+        Ok(Self {
+            group,
+            name: name.trim_matches(char::from(0)),
+            params,
+            value,
+        })
+    }
+}
+
+impl<'a> Display for ContentLine<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(group) = self.group {
+            write!(f, "{}.", group)?;
+        }
+        write!(f, "{}", self.name)?;
+        for (k, v) in self.params.iter() {
+            write!(f, ";{}={}", k, v)?;
+        }
+        write!(f, ":{}", self.value)
+    }
+}
+
+impl FromStr for Property {
+    type Err = VCardError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        Self::try_from(&ContentLine::parse(line)?)
+    }
+}
+
+impl<'a, 'b> TryFrom<&'b ContentLine<'a>> for Property {
+    type Error = VCardError;
+
+    fn try_from(line: &'b ContentLine<'a>) -> Result<Self, Self::Error> {
+        let is_quoted_printable = line.params.iter().any(|(k, v)| {
+            k.eq_ignore_ascii_case("ENCODING") && v.eq_ignore_ascii_case("QUOTED-PRINTABLE")
+        });
+        if is_quoted_printable {
+            let charset = line
+                .params
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("CHARSET"))
+                .map(|(_, v)| *v);
+            let value = decode_quoted_printable(line.value, charset);
+            let decoded = ContentLine {
+                group: line.group,
+                name: line.name,
+                params: line
+                    .params
+                    .iter()
+                    .filter(|(k, _)| {
+                        !k.eq_ignore_ascii_case("ENCODING") && !k.eq_ignore_ascii_case("CHARSET")
+                    })
+                    .copied()
+                    .collect(),
+                value: &value,
+            };
+            return Self::try_from(&decoded);
+        }
+
+        let group = line.group.map(String::from);
+        let value = line.value.to_string();
+        let name = line.name;
+        let parameters = line
+            .params
+            .iter()
+            .map(|(k, v)| Parameter::from_key_value(k, v))
+            .collect::<Result<Vec<Parameter>, VCardError>>()?;
 
         let mut pid = None;
         let mut altid = None;
@@ -203,12 +453,13 @@ impl FromStr for Property {
         let mut pref = None;
         let mut language = None;
         let mut label = None;
+        let mut encoding = None;
         let mut proprietary_parameters = Vec::new();
         for param in parameters {
             match param {
                 Parameter::Pid(p) => pid = Some(p),
                 Parameter::AltId(a) => altid = Some(a),
-                Parameter::MediaType(m) => mediatype = Some(m),
+                Parameter::MediaType(m) => mediatype = Some(MediaType::from(m)),
                 Parameter::TimeZone(t) => tz = Some(t),
                 Parameter::Geo(g) => geo = Some(g),
                 Parameter::SortAs(s) => sort_as = Some(s),
@@ -224,10 +475,24 @@ impl FromStr for Property {
                 Parameter::Language(l) => language = Some(l),
                 Parameter::Pref(p) => pref = Some(p),
                 Parameter::Label(l) => label = Some(l),
+                Parameter::Encoding(e) => encoding = Some(e),
                 Parameter::Proprietary(p) => proprietary_parameters.push(Parameter::Proprietary(p)),
             }
         }
 
+        // inline binary values of vCard 2.1 and 3.0 are stored as data: URI like in vCard 4.0
+        let value = match (&encoding, binary_media_type(name)) {
+            (Some(EncodingValue::Base64), Some(media_type)) if url::Url::parse(&value).is_err() => {
+                to_data_uri(
+                    &value,
+                    media_type,
+                    mediatype.as_ref().map(MediaType::as_str),
+                    &mut type_param,
+                )
+            }
+            _ => value,
+        };
+
         let prop =
             match &name.to_lowercase()[..] {
                 "begin" => Self::Begin { value },
@@ -236,6 +501,7 @@ impl FromStr for Property {
                     let value = match &value[..] {
                         "4.0" => VersionValue::V4,
                         "3.0" => VersionValue::V3,
+                        "2.1" => VersionValue::V21,
                         _ => return Err(VCardError::InvalidVersion(value)),
                     };
                     Self::Version(Version { value })
@@ -245,7 +511,7 @@ impl FromStr for Property {
                     altid,
                     mediatype,
                     group,
-                    value: value,
+                    value,
                 }),
                 "kind" => Self::Kind(Kind {
                     group,
@@ -261,8 +527,10 @@ impl FromStr for Property {
                     pref,
                 }),
                 "n" => {
-                    let mut split = escaped_split(&value, ';').map(|item| {
-                        escaped_split(&item, ',')
+                    let mut split = split_unescaped(&value, ';').into_iter().map(|item| {
+                        split_unescaped(item, ',')
+                            .into_iter()
+                            .map(unescape)
                             .filter_map(filter_and_transform)
                             .collect::<Vec<String>>()
                     });
@@ -291,7 +559,10 @@ impl FromStr for Property {
                     language,
                     pid,
                     group,
-                    value: escaped_split(&value, ',').map(String::from).collect(),
+                    value: split_unescaped(&value, ',')
+                        .into_iter()
+                        .map(unescape)
+                        .collect(),
                 }),
                 "photo" => Self::Photo(Photo {
                     group,
@@ -301,9 +572,10 @@ impl FromStr for Property {
                     type_param,
                     value_data_type,
                     pref,
-                    value: value,
+                    value: value.parse()?,
                 }),
                 "bday" => Self::BDay(BDay {
+                    group,
                     altid,
                     calscale,
                     language,
@@ -317,37 +589,36 @@ impl FromStr for Property {
                     value,
                 }),
                 "gender" => {
-                    let (sex, identity) =
-                        value
-                            .split_once(";")
-                            .ok_or_else(|| VCardError::InvalidSyntax {
-                                property: "Gender".into(),
-                                message: "gender property must include a semicolon (;)".into(),
-                            })?;
-                    let value = if sex.is_empty() {
+                    // both components are optional, e.g. `M`, `M;text`, `;text` or an empty value
+                    let (sex, identity_component) = match value.split_once(';') {
+                        Some((sex, identity)) => (sex, Some(identity.to_string())),
+                        None => (&value[..], None),
+                    };
+                    let sex = if sex.is_empty() {
                         None
                     } else {
                         Some(Sex::from_str(sex)?)
                     };
-                    let identity_component = Some(identity.to_string());
                     Self::Gender(Gender {
-                        sex: value,
+                        sex,
                         identity_component,
                     })
                 }
                 "adr" => {
-                    let mut split = escaped_split(&value, ';').map(|item| {
-                        escaped_split(&item, ',')
+                    let mut split = split_unescaped(&value, ';').into_iter().map(|item| {
+                        split_unescaped(item, ',')
+                            .into_iter()
+                            .map(unescape)
                             .filter_map(filter_and_transform)
                             .collect::<Vec<String>>()
                     });
-                    let po_box = split.next().unwrap_or_else(|| Vec::new());
-                    let extended_address = split.next().unwrap_or_else(|| Vec::new());
-                    let street = split.next().unwrap_or_else(|| Vec::new());
-                    let city = split.next().unwrap_or_else(|| Vec::new());
-                    let region = split.next().unwrap_or_else(|| Vec::new());
-                    let postal_code = split.next().unwrap_or_else(|| Vec::new());
-                    let country = split.next().unwrap_or_else(|| Vec::new());
+                    let po_box = split.next().unwrap_or_else(Vec::new);
+                    let extended_address = split.next().unwrap_or_else(Vec::new);
+                    let street = split.next().unwrap_or_else(Vec::new);
+                    let city = split.next().unwrap_or_else(Vec::new);
+                    let region = split.next().unwrap_or_else(Vec::new);
+                    let postal_code = split.next().unwrap_or_else(Vec::new);
+                    let country = split.next().unwrap_or_else(Vec::new);
                     Self::Adr(Adr {
                         altid,
                         pid,
@@ -356,7 +627,7 @@ impl FromStr for Property {
                         geo,
                         tz,
                         value_data_type,
-                        type_param,
+                        type_param: typed_type_param(type_param),
                         pref,
                         region,
                         po_box,
@@ -369,12 +640,13 @@ impl FromStr for Property {
                     })
                 }
                 "tel" => Self::Tel(Tel {
+                    group,
+                    value: TelValue::new(value, value_data_type.as_ref()),
                     value_data_type,
-                    type_param,
+                    type_param: typed_type_param(type_param),
                     pid,
                     pref,
                     altid,
-                    value,
                 }),
                 "email" => Self::Email(Email {
                     altid,
@@ -382,7 +654,7 @@ impl FromStr for Property {
                     pid,
                     pref,
                     value_data_type,
-                    type_param,
+                    type_param: typed_type_param(type_param),
                     value,
                 }),
                 "impp" => Self::Impp(Impp {
@@ -393,7 +665,7 @@ impl FromStr for Property {
                     value_data_type,
                     type_param,
                     mediatype,
-                    value,
+                    value: url::Url::parse(&value)?,
                 }),
 
                 "lang" => Self::Lang(Lang {
@@ -403,9 +675,10 @@ impl FromStr for Property {
                     value_data_type,
                     type_param,
                     group,
-                    value,
+                    value: value.into(),
                 }),
                 "tz" => Self::Tz(Tz {
+                    value: TzValue::new(value, value_data_type.as_ref())?,
                     altid,
                     pid,
                     pref,
@@ -413,7 +686,6 @@ impl FromStr for Property {
                     type_param,
                     mediatype,
                     group,
-                    value,
                 }),
                 "geo" => Self::Geo(Geo {
                     altid,
@@ -423,7 +695,7 @@ impl FromStr for Property {
                     type_param,
                     mediatype,
                     group,
-                    value,
+                    value: value.parse()?,
                 }),
                 "title" => Self::Title(Title {
                     altid,
@@ -452,7 +724,9 @@ impl FromStr for Property {
                     value_data_type,
                     type_param,
                     group,
-                    value: escaped_split(&value, ',')
+                    value: split_unescaped(&value, ',')
+                        .into_iter()
+                        .map(unescape)
                         .filter_map(filter_and_transform)
                         .collect(),
                 }),
@@ -465,7 +739,9 @@ impl FromStr for Property {
                     language,
                     sort_as,
                     group,
-                    value: escaped_split(&value, ';')
+                    value: split_unescaped(&value, ';')
+                        .into_iter()
+                        .map(unescape)
                         .filter_map(filter_and_transform)
                         .collect(),
                 }),
@@ -482,7 +758,7 @@ impl FromStr for Property {
                     pid,
                     pref,
                     value_data_type,
-                    type_param,
+                    type_param: typed_type_param(type_param),
                     language,
                     mediatype,
                     group,
@@ -497,7 +773,7 @@ impl FromStr for Property {
                     language,
                     mediatype,
                     group,
-                    value,
+                    value: value.parse()?,
                 }),
                 "note" => Self::Note(Note {
                     altid,
@@ -520,14 +796,14 @@ impl FromStr for Property {
                     language,
                     mediatype,
                     group,
-                    value,
+                    value: value.parse()?,
                 }),
                 "uid" => Self::Uid(Uid {
                     value_data_type,
                     group,
                     value,
                 }),
-                "clientidmap" => {
+                "clientpidmap" => {
                     let mut split = value.split(";");
                     let pid = split.next().map(u8::from_str).ok_or_else(|| {
                         VCardError::InvalidLine {
@@ -544,7 +820,7 @@ impl FromStr for Property {
                         }
                     })?;
                     Self::ClientPidMap(ClientPidMap {
-                        value: global_identifier,
+                        value: url::Url::parse(&global_identifier)?,
                         pid_digit: pid,
                         group,
                     })
@@ -560,6 +836,7 @@ impl FromStr for Property {
                     value,
                 }),
                 "key" => Self::Key(Key {
+                    value: KeyValue::new(value, value_data_type.as_ref()),
                     group,
                     altid,
                     pid,
@@ -567,7 +844,6 @@ impl FromStr for Property {
                     value_data_type,
                     type_param,
                     mediatype,
-                    value,
                 }),
                 "fburl" => Self::FbUrl(FbURL {
                     group,
@@ -579,7 +855,8 @@ impl FromStr for Property {
                     mediatype,
                     value,
                 }),
-                "caladuri" => Self::CalAdUri(CalAdURI {
+                // earlier versions of this crate wrote CALADRURI as CALADURI
+                "caladruri" | "caladuri" => Self::CalAdUri(CalAdURI {
                     group,
                     altid,
                     pid,
@@ -605,10 +882,11 @@ impl FromStr for Property {
                     group,
                 }),
                 _ => {
-                    if !name.starts_with("X-") && !name.starts_with("x-") {
+                    let legacy_name = LegacyPropertyName::from_name(name);
+                    if legacy_name.is_none() && !name.starts_with("X-") && !name.starts_with("x-") {
                         return Err(VCardError::InvalidName {
                             actual_name: name.into(),
-                            raw_line: line.into(),
+                            raw_line: line.to_string(),
                         });
                     }
 
@@ -621,7 +899,7 @@ impl FromStr for Property {
                         proprietary_parameters.push(Parameter::Pid(pid));
                     }
                     if let Some(mediatype) = mediatype {
-                        proprietary_parameters.push(Parameter::MediaType(mediatype));
+                        proprietary_parameters.push(Parameter::MediaType(mediatype.to_string()));
                     }
                     if let Some(tz) = tz {
                         proprietary_parameters.push(Parameter::TimeZone(tz));
@@ -655,14 +933,354 @@ impl FromStr for Property {
                         proprietary_parameters.push(Parameter::Language(l));
                     }
 
-                    Property::Proprietary(ProprietaryProperty {
-                        name: name.into(),
-                        value: value.into(),
-                        group,
-                        parameters: proprietary_parameters,
-                    })
+                    if let Some(v) = value_data_type {
+                        proprietary_parameters.push(Parameter::Value(v));
+                    }
+
+                    if let Some(e) = encoding {
+                        proprietary_parameters.push(Parameter::Encoding(e));
+                    }
+
+                    match legacy_name {
+                        Some(name) => Property::Legacy(LegacyProperty {
+                            name,
+                            value,
+                            group,
+                            parameters: proprietary_parameters,
+                        }),
+                        None => Property::Proprietary(ProprietaryProperty {
+                            name: name.into(),
+                            value,
+                            group,
+                            parameters: proprietary_parameters,
+                        }),
+                    }
                 }
             };
         Ok(prop)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_content_line() {
+        assert_eq!(
+            Some((Some("item1"), "EMAIL", None, "mail@example.com")),
+            split_content_line("item1.EMAIL:mail@example.com")
+        );
+        assert_eq!(
+            Some((None, "TEL", Some(";TYPE=cell;PREF=1"), "tel:+49123")),
+            split_content_line("TEL;TYPE=cell;PREF=1:tel:+49123")
+        );
+        assert_eq!(
+            Some((None, "ADR", Some(";GEO=\"geo:12.3,4.5\""), ";;Street;;;;")),
+            split_content_line("ADR;GEO=\"geo:12.3,4.5\":;;Street;;;;")
+        );
+        assert_eq!(None, split_content_line("no colon"));
+        assert_eq!(None, split_content_line(":value"));
+        assert_eq!(None, split_content_line("NOTE;:value"));
+    }
+
+    #[test]
+    fn test_non_ascii_property_name() -> Result<(), VCardError> {
+        assert_eq!(
+            Some((None, "Ä", None, "value")),
+            split_content_line("Ä:value")
+        );
+        assert_eq!(
+            Some((Some("gruppe"), "NÄME", None, "value")),
+            split_content_line("gruppe.NÄME:value")
+        );
+        assert!("Ä:value".parse::<Property>().is_err());
+        assert!(VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nÄ:value\r\nEND:VCARD\r\n"
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_line() -> Result<(), VCardError> {
+        let raw = "item1.TEL;TYPE=cell;PREF=1:tel:+49123";
+        let line = ContentLine::parse(raw)?;
+        assert_eq!(Some("item1"), line.group);
+        assert_eq!("TEL", line.name);
+        assert_eq!(vec![("TYPE", "cell"), ("PREF", "1")], line.params);
+        assert_eq!("tel:+49123", line.value);
+        assert_eq!(raw, line.to_string());
+
+        match Property::try_from(&line)? {
+            Property::Tel(t) => assert_eq!(Some(1), t.pref),
+            p => panic!("expected TEL but got {:?}", p),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_quoted_parameters() -> Result<(), VCardError> {
+        let raw = "ADR;LABEL=\"123 Main St; Suite 4\";TYPE=home:;;123 Main St;Any Town;;;";
+        let line = ContentLine::parse(raw)?;
+        assert_eq!(
+            vec![("LABEL", "\"123 Main St; Suite 4\""), ("TYPE", "home")],
+            line.params
+        );
+
+        match Property::try_from(&line)? {
+            Property::Adr(adr) => {
+                assert_eq!(Some("123 Main St; Suite 4"), adr.label.as_deref());
+                assert_eq!(format!("{}\r\n", raw), adr.to_string());
+            }
+            p => panic!("expected ADR but got {:?}", p),
+        }
+
+        let line = ContentLine::parse("ADR;LABEL=\"Main St\\\";4\";TYPE=HOME:;;Main St;;;;")?;
+        assert_eq!(
+            vec![("LABEL", "\"Main St\\\";4\""), ("TYPE", "HOME")],
+            line.params
+        );
+        match Property::try_from(&line)? {
+            Property::Adr(adr) => assert_eq!(Some("Main St\";4"), adr.label.as_deref()),
+            p => panic!("expected ADR but got {:?}", p),
+        }
+
+        assert!(ContentLine::parse("ADR;LABEL=\"Main St;TYPE=HOME:;;Main St;;;;").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() -> Result<(), VCardError> {
+        for line in [
+            "BEGIN:VCARD",
+            "VERSION:4.0",
+            "item1.EMAIL;TYPE=home:heinrich@tosafjord.com",
+            "item1.X-ABLABEL:_$!<HomePage>!$_",
+            "END:VCARD",
+        ] {
+            let prop: Property = line.parse()?;
+            assert_eq!(format!("{}\r\n", line), prop.to_string());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_gender() -> Result<(), VCardError> {
+        for (line, sex, identity) in [
+            ("GENDER:M", Some(Sex::Male), None),
+            ("GENDER:F;Female", Some(Sex::Female), Some("Female")),
+            ("GENDER:;it's complicated", None, Some("it's complicated")),
+            ("GENDER:", None, None),
+            ("GENDER:O;", Some(Sex::Other), Some("")),
+        ] {
+            match line.parse()? {
+                Property::Gender(gender) => {
+                    assert_eq!(sex, gender.sex, "{}", line);
+                    assert_eq!(identity, gender.identity_component.as_deref(), "{}", line);
+                    assert_eq!(format!("{}\r\n", line), gender.to_string());
+                }
+                p => panic!("expected GENDER but got {:?}", p),
+            }
+        }
+        assert!("GENDER:X".parse::<Property>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_kind() -> Result<(), VCardError> {
+        for (line, value) in [
+            ("KIND:individual", KindValue::Individual),
+            ("KIND:application", KindValue::Application),
+            ("KIND:X-robot", KindValue::Proprietary("X-robot".into())),
+        ] {
+            match line.parse()? {
+                Property::Kind(kind) => {
+                    assert_eq!(value, kind.value);
+                    assert_eq!(format!("{}\r\n", line), kind.to_string());
+                }
+                p => panic!("expected KIND but got {:?}", p),
+            }
+        }
+        assert_eq!(KindValue::Application, "Application".parse::<KindValue>()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_adr_geo() -> Result<(), VCardError> {
+        let line = "ADR;GEO=\"geo:48.2,16.3\":;;Main St;Vienna;;;AT";
+        match line.parse()? {
+            Property::Adr(adr) => {
+                assert_eq!(Some("geo:48.2,16.3"), adr.geo.as_deref());
+                assert_eq!(vec!["Vienna".to_string()], adr.city);
+                assert_eq!(format!("{}\r\n", line), adr.to_string());
+            }
+            p => panic!("expected ADR but got {:?}", p),
+        }
+
+        // the GEO parameter does not mix with the GEO property
+        let card = VCard::parse(&format!(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n{}\r\n\
+            GEO:geo:37.386013,-122.082932\r\nEND:VCARD\r\n",
+            line
+        ))?;
+        assert_eq!(1, card.count_geos());
+        assert!(card.to_string().contains(line));
+        Ok(())
+    }
+
+    #[test]
+    fn test_uri_values() -> Result<(), VCardError> {
+        match "TEL;VALUE=uri:tel:+1-555-555-5555".parse()? {
+            Property::Tel(tel) => {
+                assert_eq!(TelValue::Uri("tel:+1-555-555-5555".parse()?), tel.value)
+            }
+            p => panic!("expected TEL but got {:?}", p),
+        }
+        match "TEL:+1-555-555-5555".parse()? {
+            Property::Tel(tel) => {
+                assert_eq!(TelValue::Text("+1-555-555-5555".into()), tel.value);
+            }
+            p => panic!("expected TEL but got {:?}", p),
+        }
+        match "IMPP;PREF=1:xmpp:alice@example.com".parse()? {
+            Property::Impp(impp) => assert_eq!("xmpp", impp.value.scheme()),
+            p => panic!("expected IMPP but got {:?}", p),
+        }
+        assert!("IMPP:alice@example.com".parse::<Property>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoding() -> Result<(), VCardError> {
+        let photo = |line: &str| -> Result<Photo, VCardError> {
+            match line.parse()? {
+                Property::Photo(photo) => Ok(photo),
+                p => panic!("expected PHOTO, got {:?}", p),
+            }
+        };
+        let p = photo("PHOTO;ENCODING=BASE64;TYPE=JPEG:MIICajCCAdOg AwIBAgICBEUw")?;
+        assert_eq!(
+            "data:image/jpeg;base64,MIICajCCAdOgAwIBAgICBEUw",
+            p.value.to_string()
+        );
+        assert_eq!(Some("image/jpeg"), p.value.media_type());
+        assert_eq!(None, p.type_param);
+
+        let p = photo("PHOTO;ENCODING=b;TYPE=HOME;MEDIATYPE=image/png:iVBORw0KGgo")?;
+        assert_eq!(Some(&b"\x89PNG\r\n\x1a\n"[..]), p.value.data());
+        // the padding is added when the value is written
+        assert_eq!("data:image/png;base64,iVBORw0KGgo=", p.value.to_string());
+        assert_eq!(Some(vec!["HOME".to_string()]), p.type_param);
+
+        // only known formats are media types
+        let p = photo("PHOTO;ENCODING=b;TYPE=PREF:iVBORw0KGgo")?;
+        assert_eq!(
+            "data:application/octet-stream;base64,iVBORw0KGgo=",
+            p.value.to_string()
+        );
+        assert_eq!(Some(vec!["PREF".to_string()]), p.type_param);
+
+        // URIs are kept as they are
+        let p = photo("PHOTO;ENCODING=b:http://example.com/photo.jpg")?;
+        assert_eq!(
+            PhotoValue::Uri("http://example.com/photo.jpg".parse()?),
+            p.value
+        );
+
+        assert_eq!(
+            Parameter::Encoding(EncodingValue::QuotedPrintable),
+            "ENCODING=quoted-printable".parse()?
+        );
+        assert_eq!(
+            "ENCODING=b",
+            Parameter::Encoding(EncodingValue::Base64).to_string()
+        );
+        match "NOTE;ENCODING=QUOTED-PRINTABLE:Katzenh=C3=A4usen".parse()? {
+            Property::Note(note) => assert_eq!("Katzenhäusen", note.value),
+            p => panic!("expected NOTE, got {:?}", p),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_categories_are_comma_separated() -> Result<(), VCardError> {
+        match "CATEGORIES:Freunde,myContacts,starred".parse()? {
+            Property::Categories(categories) => {
+                assert_eq!(vec!["Freunde", "myContacts", "starred"], categories.value);
+                assert_eq!(
+                    "CATEGORIES:Freunde,myContacts,starred\r\n",
+                    categories.to_string()
+                );
+            }
+            p => panic!("expected CATEGORIES, got {:?}", p),
+        }
+        // a semicolon separates the components of structured values like N, not the values of a list
+        match "CATEGORIES:TRAVEL AGENT;VIP".parse()? {
+            Property::Categories(categories) => {
+                assert_eq!(vec!["TRAVEL AGENT;VIP"], categories.value)
+            }
+            p => panic!("expected CATEGORIES, got {:?}", p),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_escaped_separators() -> Result<(), VCardError> {
+        let lines = [
+            "ORG:Smith\\, Jones;IT\\;Support\r\n",
+            "CATEGORIES:rock\\, pop,jazz\r\n",
+            "NICKNAME:Jim\\, the Great,Jimmy\r\n",
+            "N:Doe\\,Smith;John;;Dr.\\;;\r\n",
+            "ADR:;;Main Street 1\\, Back;Any\\\\Town;;;\r\n",
+        ];
+        for line in lines.iter() {
+            let property: Property = line.trim_end().parse()?;
+            assert_eq!(*line, property.to_string());
+        }
+
+        match lines[0].trim_end().parse()? {
+            Property::Org(org) => assert_eq!(vec!["Smith, Jones", "IT;Support"], org.value),
+            p => panic!("expected ORG, got {:?}", p),
+        }
+        match lines[3].trim_end().parse()? {
+            Property::N(n) => {
+                assert_eq!(vec!["Doe,Smith"], n.surenames);
+                assert_eq!(vec!["Dr.;"], n.honorific_prefixes);
+            }
+            p => panic!("expected N, got {:?}", p),
+        }
+        match lines[4].trim_end().parse()? {
+            Property::Adr(adr) => assert_eq!(vec!["Any\\Town"], adr.city),
+            p => panic!("expected ADR, got {:?}", p),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_extension_name_case() -> Result<(), VCardError> {
+        for line in ["X-ABLABEL:work", "x-ablabel:work", "x-AbLabel:work"] {
+            match line.parse()? {
+                Property::Proprietary(p) => assert_eq!(&line[..9], p.name),
+                p => panic!("expected a proprietary property but got {:?}", p),
+            }
+        }
+        assert!("ABLABEL:work".parse::<Property>().is_err());
+
+        // the original name is written back, for single properties as well as for whole vcards
+        for line in ["item1.X-ABLabel:PROFILE\r\n", "x-custom-prop:foo\r\n"] {
+            assert_eq!(line, line.trim_end().parse::<Property>()?.to_string());
+        }
+        let raw = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            item1.X-ABLabel:PROFILE\r\nx-custom-prop:foo\r\nEND:VCARD\r\n";
+        let card = VCard::parse(raw)?;
+        let names: Vec<&str> = card
+            .proprietary_properties
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(vec!["X-ABLabel", "x-custom-prop"], names);
+        assert_eq!(raw, card.to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+use std::fmt::Display;
+
+use crate::{collection::normalize_tel, LanguageTag, TelValue, VCard, ValueDataType, VersionValue};
+
+macro_rules! normalize_properties {
+    ($vcard:expr, $normalize:expr, $($field:ident),*) => {
+        $(
+        $vcard.$field.update_values($normalize);
+        )*
+    };
+}
+
+impl VCard {
+    /// Rewrites this vcard into a canonical form, so that vcards that differ only in their formatting
+    /// are equal and serialize to the same bytes afterwards.
+    ///
+    /// - TYPE values are uppercased, sorted and deduplicated. In vCard 4.0, `TYPE=pref` becomes `PREF=1`.
+    /// - empty TYPE, LANGUAGE, MEDIATYPE and ALTID parameters are removed.
+    /// - language tags of LANGUAGE and LANG follow the case conventions of RFC 5646, e.g. `de-DE`.
+    /// - the components of N, ADR, ORG and NICKNAME are trimmed.
+    /// - the values of CATEGORIES are trimmed and sorted, because their order is not significant.
+    /// - visual separators like spaces, dashes and parentheses are removed from phone numbers,
+    ///   so `+49 (176) 1010-1520` becomes `+4917610101520`. Only `tel:` URIs and text values that
+    ///   consist of a number are changed, other text values like `ask for Heinrich` are kept as they are.
+    ///
+    /// ```
+    /// use vcard::*;
+    ///
+    /// let mut google = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+    ///     TEL;TYPE=cell,voice:+49 176 1010-1520\r\nEND:VCARD\r\n")?;
+    /// let mut apple = VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+    ///     TEL;TYPE=VOICE;TYPE=CELL:+49-176-10101520\r\nEND:VCARD\r\n")?;
+    /// google.normalize();
+    /// apple.normalize();
+    /// assert_eq!(google.to_string(), apple.to_string());
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    pub fn normalize(&mut self) {
+        let is_v4 = self.version.value == VersionValue::V4;
+
+        normalize_properties!(
+            self,
+            |p| normalize_types(&mut p.type_param, &mut p.pref, is_v4),
+            fn_property,
+            nickname,
+            photo,
+            adr,
+            tel,
+            email,
+            impp,
+            lang,
+            tz,
+            geo,
+            title,
+            role,
+            logo,
+            org,
+            related,
+            categories,
+            note,
+            sound,
+            url,
+            key,
+            fburl,
+            caluri,
+            caladuri
+        );
+        normalize_properties!(
+            self,
+            |p| normalize_language(&mut p.language),
+            fn_property,
+            nickname,
+            adr,
+            title,
+            role,
+            logo,
+            org,
+            related,
+            note,
+            sound
+        );
+        normalize_properties!(
+            self,
+            |p| strip_empty(&mut p.mediatype),
+            source,
+            photo,
+            impp,
+            tz,
+            geo,
+            logo,
+            member,
+            related,
+            sound,
+            url,
+            key,
+            fburl,
+            caluri,
+            caladuri
+        );
+        normalize_properties!(
+            self,
+            |p| strip_empty(&mut p.altid),
+            source,
+            xml,
+            fn_property,
+            nickname,
+            photo,
+            adr,
+            tel,
+            email,
+            impp,
+            lang,
+            tz,
+            geo,
+            title,
+            role,
+            logo,
+            org,
+            member,
+            related,
+            categories,
+            note,
+            sound,
+            url,
+            key,
+            fburl,
+            caluri,
+            caladuri
+        );
+
+        self.n.update_values(|n| {
+            strip_empty(&mut n.altid);
+            normalize_language(&mut n.language);
+            for component in [
+                &mut n.surenames,
+                &mut n.given_names,
+                &mut n.additional_names,
+                &mut n.honorific_prefixes,
+                &mut n.honorific_suffixes,
+            ] {
+                trim_all(component);
+            }
+        });
+        self.bday.update_values(|b| {
+            strip_empty(&mut b.altid);
+            normalize_language(&mut b.language);
+        });
+        self.anniversary
+            .update_values(|a| strip_empty(&mut a.altid));
+
+        self.adr.update_values(|a| {
+            for component in [
+                &mut a.po_box,
+                &mut a.extended_address,
+                &mut a.street,
+                &mut a.city,
+                &mut a.region,
+                &mut a.postal_code,
+                &mut a.country,
+            ] {
+                trim_all(component);
+            }
+        });
+        self.lang.update_values(|l| l.value.normalize());
+        self.org.update_values(|o| trim_all(&mut o.value));
+        self.nickname.update_values(|n| trim_all(&mut n.value));
+        self.categories.update_values(|c| {
+            trim_all(&mut c.value);
+            c.value.sort();
+        });
+        self.tel.update_values(|t| {
+            let value = match &t.value {
+                TelValue::Uri(uri) => {
+                    let uri = uri.as_str();
+                    uri.get(4..)
+                        .filter(|_| uri[..4].eq_ignore_ascii_case("tel:"))
+                        .and_then(canonical_tel)
+                        .map(|number| {
+                            TelValue::new(format!("tel:{}", number), Some(&ValueDataType::Uri))
+                        })
+                }
+                TelValue::Text(text) => canonical_tel(text).map(TelValue::Text),
+            };
+            if let Some(value) = value {
+                t.value = value;
+            }
+        });
+    }
+}
+
+fn normalize_types<T>(types: &mut Option<Vec<T>>, pref: &mut Option<u8>, is_v4: bool)
+where
+    T: Display + for<'a> From<&'a str>,
+{
+    let mut values: Vec<String> = types
+        .iter()
+        .flatten()
+        .map(|t| t.to_string().trim().to_uppercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    // vCard 3.0 marks the preferred value with TYPE=pref, see https://datatracker.ietf.org/doc/html/rfc2426#section-3.6.1
+    if is_v4 && values.iter().any(|t| t == "PREF") {
+        values.retain(|t| t != "PREF");
+        pref.get_or_insert(1);
+    }
+    values.sort();
+    values.dedup();
+    *types = if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().map(|t| T::from(t.as_str())).collect())
+    };
+}
+
+fn strip_empty<T: AsRef<str>>(param: &mut Option<T>) {
+    if param.as_ref().is_some_and(|p| p.as_ref().trim().is_empty()) {
+        *param = None;
+    }
+}
+
+// removes empty LANGUAGE parameters and applies the case conventions of language tags, e.g. `de-DE`
+fn normalize_language(language: &mut Option<String>) {
+    strip_empty(language);
+    if let Some(language) = language {
+        let mut tag = LanguageTag::from(language.as_str());
+        tag.normalize();
+        *language = tag.to_string();
+    }
+}
+
+fn trim_all(values: &mut [String]) {
+    for value in values.iter_mut() {
+        let trimmed = value.trim();
+        if trimmed.len() != value.len() {
+            *value = trimmed.to_string();
+        }
+    }
+}
+
+// removes the visual separators of a phone number like `+49 (176) 1010-1520`, parameters like `;ext=1` are kept.
+// Returns `None` if the number contains anything but digits, visual separators and a leading `+`.
+// See https://datatracker.ietf.org/doc/html/rfc3966#section-5.1.1
+fn canonical_tel(tel: &str) -> Option<String> {
+    let (number, params) = tel.split_at(tel.find(';').unwrap_or(tel.len()));
+    let digits = number.trim_start().strip_prefix('+').unwrap_or(number);
+    let is_number = digits.chars().any(|c| c.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_whitespace() || "-.()".contains(c));
+    is_number.then(|| format!("{}{}", normalize_tel(number), params))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_normalize() -> Result<(), VCardError> {
+        let mut google = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            N:vom Tosafjord ;Heinrich;;;\r\n\
+            TEL;TYPE=home,voice;VALUE=uri:tel:+49-176-1010-1520\r\n\
+            EMAIL;PREF=1;TYPE=work;TYPE=WORK:heinrich@example.com\r\n\
+            ADR;TYPE=home:;; Fjordweg 1 ;Tosafjord;;12345;Norway\r\n\
+            ORG:Tosafjord Inc.; Sales\r\n\
+            CATEGORIES:work,family\r\n\
+            NOTE;LANGUAGE=:hello\r\n\
+            LANG:DE-de\r\n\
+            END:VCARD\r\n",
+        )?;
+        let mut apple = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            N:vom Tosafjord;Heinrich;;;\r\n\
+            TEL;TYPE=VOICE;TYPE=HOME;VALUE=uri:tel:+49176-10101520\r\n\
+            EMAIL;TYPE=\"WORK,pref\":heinrich@example.com\r\n\
+            ADR;TYPE=HOME:;;Fjordweg 1;Tosafjord;;12345;Norway\r\n\
+            ORG:Tosafjord Inc.;Sales\r\n\
+            CATEGORIES:family,work\r\n\
+            NOTE:hello\r\n\
+            LANG:de-DE\r\n\
+            END:VCARD\r\n",
+        )?;
+        assert_ne!(google.to_string(), apple.to_string());
+
+        google.normalize();
+        apple.normalize();
+        assert_eq!(google.to_string(), apple.to_string());
+        assert_eq!(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            N:vom Tosafjord;Heinrich;;;\r\n\
+            ADR;TYPE=home:;;Fjordweg 1;Tosafjord;;12345;Norway\r\n\
+            TEL;VALUE=uri;TYPE=home;TYPE=voice:tel:+4917610101520\r\n\
+            EMAIL;PREF=1;TYPE=work:heinrich@example.com\r\n\
+            LANG:de-DE\r\n\
+            ORG:Tosafjord Inc.;Sales\r\n\
+            CATEGORIES:family,work\r\n\
+            NOTE:hello\r\n\
+            END:VCARD\r\n",
+            google.to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_v3_pref() -> Result<(), VCardError> {
+        let mut card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Heinrich\r\n\
+            TEL;TYPE=pref,cell:+49 (176) 1010-1520;ext=1\r\nEND:VCARD\r\n",
+        )?;
+        card.normalize();
+        let tel = card.tel.iter().next().unwrap();
+        // vCard 3.0 has no PREF parameter
+        assert_eq!(None, tel.pref);
+        assert_eq!(
+            Some(vec![TelType::Cell, TelType::Proprietary("PREF".into())]),
+            tel.type_param
+        );
+        assert_eq!("+4917610101520;ext=1", tel.value.as_str());
+
+        // only numbers are normalized
+        for value in ["ask for Heinrich", "+49 176 CALL-ME", "tel:*31#1234"] {
+            let mut card = VCard::parse(&format!(
+                "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nTEL:{}\r\nEND:VCARD\r\n",
+                value
+            ))?;
+            card.normalize();
+            assert_eq!(value, card.tel.iter().next().unwrap().value.as_str());
+        }
+        Ok(())
+    }
+}
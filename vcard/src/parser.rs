@@ -0,0 +1,311 @@
+use std::{collections::VecDeque, convert::TryFrom};
+
+use crate::{errors::VCardError, has_soft_line_break, ContentLine, Property};
+
+const DEFAULT_MAX_LINE_LENGTH: u64 = 5000;
+
+/// A push parser that does not own an `std::io::Read`.
+///
+/// Bytes are fed in chunks of arbitrary size with `feed`, completed properties are pulled with `next_property`.
+/// A property is only returned once the first byte of the following line is known, because the
+/// following line could be a continuation of the current logical line.
+/// Call `finish` after the last chunk to flush the trailing line, e.g. if the input does not end with CRLF.
+///
+/// Logical lines are unfolded by the same rules as in `VCardReader`, including the soft line breaks of
+/// vCard 2.1 quoted-printable values,
+/// see https://datatracker.ietf.org/doc/html/rfc6350#section-3.2 for more information about logical lines.
+pub struct VCardParser {
+    scan_state: ScanState,
+    // the logical line that is scanned, without line breaks and folding whitespace
+    line: Vec<u8>,
+    // true if the scanned logical line exceeds `max_logical_line_length`. Its bytes are dropped.
+    skipping: bool,
+    // scanned logical lines that were not returned by `next_property` yet
+    lines: VecDeque<ScannedLine>,
+    finished: bool,
+    pub max_logical_line_length: u64,
+}
+
+#[derive(Clone, Copy)]
+enum ScanState {
+    // in front of a logical line, blank lines are skipped
+    Start,
+    Content,
+    CarriageReturn,
+    // after the line break of a physical line
+    LineBreak,
+    // after the whitespace that starts a continuation line
+    Folding,
+    // in a continuation line that starts with two whitespace characters, those lines are discarded
+    Discard,
+}
+
+enum ScannedLine {
+    Complete(Vec<u8>),
+    TooLong,
+}
+
+impl Default for VCardParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VCardParser {
+    /// Creates a new `VCardParser` with the default logical line limit of 5000
+    pub fn new() -> Self {
+        Self::new_with_logical_line_limit(DEFAULT_MAX_LINE_LENGTH)
+    }
+
+    /// Creates a new `VCardParser` with a configurable line limit
+    pub fn new_with_logical_line_limit(max_logical_line_length: u64) -> Self {
+        Self {
+            scan_state: ScanState::Start,
+            line: Vec::new(),
+            skipping: false,
+            lines: VecDeque::new(),
+            finished: false,
+            max_logical_line_length,
+        }
+    }
+
+    /// Appends `bytes` to the input of this parser.
+    ///
+    /// The incomplete logical line at the end of the input is checked against `max_logical_line_length`
+    /// right away and dropped if it is too long, so the buffer does not grow if a line never ends.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.scan(b);
+        }
+    }
+
+    /// Marks the end of the input, so that the trailing line is returned by `next_property`
+    /// even if it is not terminated by CRLF.
+    pub fn finish(&mut self) {
+        if !matches!(
+            self.scan_state,
+            ScanState::Start | ScanState::CarriageReturn | ScanState::LineBreak
+        ) {
+            self.feed(b"\r\n");
+        }
+        if matches!(
+            self.scan_state,
+            ScanState::LineBreak | ScanState::CarriageReturn
+        ) {
+            self.end_line();
+        }
+        self.finished = true;
+    }
+
+    /// Returns the next complete property, or `None` if more input is needed.
+    ///
+    /// Blank lines are skipped. If a logical line exceeds `max_logical_line_length`,
+    /// `VCardError::MaxLineLengthExceeded` is returned instead and the line is skipped.
+    pub fn next_property(&mut self) -> Option<Result<Property, VCardError>> {
+        // a quoted-printable value that ends with a soft line break continues on the next logical line
+        let mut count = 1;
+        loop {
+            match self.lines.get(count - 1)? {
+                ScannedLine::Complete(line) if has_soft_line_break(line) => {}
+                _ => break,
+            }
+            if count == self.lines.len() {
+                if !self.finished {
+                    return None;
+                }
+                break;
+            }
+            count += 1;
+        }
+
+        let mut line = Vec::new();
+        let mut too_long = false;
+        for (i, scanned) in self.lines.drain(..count).enumerate() {
+            if i > 0 {
+                // the soft line break
+                line.pop();
+            }
+            match scanned {
+                ScannedLine::Complete(l) => line.extend(l),
+                ScannedLine::TooLong => too_long = true,
+            }
+        }
+        if too_long || line.len() as u64 > self.max_logical_line_length {
+            return Some(Err(VCardError::MaxLineLengthExceeded(
+                self.max_logical_line_length,
+            )));
+        }
+        let line = match String::from_utf8(line) {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e.into())),
+        };
+        Some(ContentLine::parse(&line).and_then(|line| Property::try_from(&line)))
+    }
+
+    // scans the next byte of the input for the end of the logical line.
+    fn scan(&mut self, b: u8) {
+        loop {
+            match (self.scan_state, b) {
+                (ScanState::Start, b'\r' | b'\n') => {}
+                (ScanState::LineBreak, b' ' | b'\t') => self.scan_state = ScanState::Folding,
+                (ScanState::LineBreak, _) => {
+                    // the byte starts the next logical line, so it is scanned again
+                    self.end_line();
+                    continue;
+                }
+                (ScanState::CarriageReturn, b'\n') => self.scan_state = ScanState::LineBreak,
+                (ScanState::CarriageReturn, _) => {
+                    // a bare \r ends the line as well
+                    self.scan_state = ScanState::LineBreak;
+                    continue;
+                }
+                (ScanState::Folding, b' ' | b'\t' | b'\r' | b'\n') => {
+                    self.scan_state = ScanState::Discard;
+                    continue;
+                }
+                (_, b'\r') => self.scan_state = ScanState::CarriageReturn,
+                (_, b'\n') => self.scan_state = ScanState::LineBreak,
+                (ScanState::Discard, _) => {}
+                (_, b) => {
+                    self.scan_state = ScanState::Content;
+                    if self.skipping {
+                        // the bytes of a line that is too long are dropped
+                    } else if self.line.len() as u64 >= self.max_logical_line_length {
+                        self.skipping = true;
+                        self.line.clear();
+                        self.lines.push_back(ScannedLine::TooLong);
+                    } else {
+                        self.line.push(b);
+                    }
+                }
+            }
+            return;
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.skipping {
+            self.skipping = false;
+        } else {
+            let line = std::mem::take(&mut self.line);
+            self.lines.push_back(ScannedLine::Complete(line));
+        }
+        self.scan_state = ScanState::Start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn parse_all(input: &[u8], chunk_sizes: &mut dyn FnMut() -> usize) -> Vec<Property> {
+        let mut parser = VCardParser::new();
+        let mut result = Vec::new();
+        let mut rest = input;
+        while !rest.is_empty() {
+            let (chunk, r) = rest.split_at(chunk_sizes().min(rest.len()));
+            rest = r;
+            parser.feed(chunk);
+            while let Some(prop) = parser.next_property() {
+                result.push(prop.unwrap());
+            }
+        }
+        parser.finish();
+        while let Some(prop) = parser.next_property() {
+            result.push(prop.unwrap());
+        }
+        result
+    }
+
+    #[test]
+    fn test_chunked_input() -> Result<(), Box<dyn std::error::Error>> {
+        let testants = [
+            &include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test_assets/apple_icloud.vcf",
+            ))[..],
+            &include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test_assets/new_line.vcf",
+            ))[..],
+            &include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test_assets/multiple_no_trailing_newline.vcf",
+            ))[..],
+            &include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test_assets/nokia_21.vcf",
+            ))[..],
+        ];
+
+        for testant in testants {
+            let mut expected = Vec::new();
+            let mut reader = VCardReader::new(testant);
+            loop {
+                let (prop, more) = reader.read_property()?;
+                expected.push(prop);
+                if !more {
+                    break;
+                }
+            }
+
+            assert_eq!(expected, parse_all(testant, &mut || testant.len()));
+            assert_eq!(expected, parse_all(testant, &mut || 1));
+
+            // pseudo random chunk sizes between 1 and 16
+            let mut state: u32 = 42;
+            let mut random = || {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as usize % 16 + 1
+            };
+            assert_eq!(expected, parse_all(testant, &mut random));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_quoted_printable_soft_line_break() {
+        let mut parser = VCardParser::new();
+        parser.feed(b"NOTE;ENCODING=QUOTED-PRINTABLE:Hallo=\r\n");
+        assert!(parser.next_property().is_none());
+        // the soft line break is only resolved once the next line is complete
+        parser.feed(b"Welt\r\n");
+        assert!(parser.next_property().is_none());
+        parser.feed(b"FN:Heinrich\r\n");
+        match parser.next_property() {
+            Some(Ok(Property::Note(note))) => assert_eq!("HalloWelt", note.value),
+            p => panic!("unexpected {:?}", p),
+        }
+        parser.finish();
+        assert!(matches!(parser.next_property(), Some(Ok(Property::FN(_)))));
+        assert!(parser.next_property().is_none());
+    }
+
+    #[test]
+    fn test_max_logical_line_length() {
+        let mut parser = VCardParser::new_with_logical_line_limit(100);
+        parser.feed(b"FN:Heinrich\r\nNOTE:");
+        // the incomplete line is dropped as soon as it exceeds the limit
+        for _ in 0..1000 {
+            parser.feed(b"0123456789");
+            assert!(parser.line.len() < 200);
+        }
+        assert!(matches!(parser.next_property(), Some(Ok(Property::FN(_)))));
+        assert!(matches!(
+            parser.next_property(),
+            Some(Err(VCardError::MaxLineLengthExceeded(100)))
+        ));
+        assert!(parser.next_property().is_none());
+
+        // folded continuation lines belong to the skipped line
+        parser.feed(b"\r\n 0123456789\r\nEMAIL:heinrich@example.com\r\n");
+        parser.finish();
+        assert!(matches!(
+            parser.next_property(),
+            Some(Ok(Property::Email(_)))
+        ));
+        assert!(parser.next_property().is_none());
+    }
+}
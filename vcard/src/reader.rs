@@ -1,24 +1,98 @@
 use std::{
-    cell::RefCell,
+    collections::VecDeque,
+    convert::TryFrom,
     io::{self, BufReader, Read},
-    rc::Rc,
     str::FromStr,
 };
 
-use crate::{errors::VCardError, Property, VCard};
+use crate::{
+    errors::VCardError, quote_param_value, unquote_param_value, validation::is_language_tag,
+    warnings::inspect_content_line, ContentLine, Parameter, ParseWarning, Property,
+    ProprietaryProperty, VCard, Version, VersionValue,
+};
 
 /// A reader that reads vcard properties one by one.
 ///
 /// Vcard properties can span accross multiple lines called "logical lines".
-/// The `max_logical_line_length` field acts as a safety net to prevent memory overflows.
+/// The `max_logical_line_length` and `max_properties_per_vcard` fields act as a safety net to prevent memory overflows.
 /// An `std::io::BufReader` is used internally.
+///
+/// Errors returned by the reader are wrapped in `VCardError::At`, which carries the line number and
+/// byte offset of the property that caused the error.
+///
+/// If the input can be cloned, e.g. a `&[u8]` or an `io::Cursor`, the reader can be cloned as well.
+/// The clone continues at the same position, independent of the original:
+///
+/// ```
+/// use vcard::*;
+///
+/// let input = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN;LANGUAGE=de_DE:Heinrich\r\nEND:VCARD\r\n";
+/// let mut reader = VCardReader::new(input.as_bytes());
+/// let mut checkpoint = reader.clone();
+/// assert!(reader.parse_vcard().is_err());
+///
+/// checkpoint.strict_language_tags = false;
+/// let card = checkpoint.parse_vcard()?;
+/// assert_eq!("Heinrich", card.fn_property.iter().next().unwrap().value);
+/// # Ok::<(), VCardError>(())
+/// ```
+#[derive(Clone)]
 pub struct VCardReader<R: io::Read> {
     inner: PushbackReader<R>,
-    discard_buf: Rc<RefCell<Vec<u8>>>,
+    // scratch buffer for lines that are discarded
+    discard_buf: Vec<u8>,
+    // the last logical line that was read
+    line_buf: String,
     pub max_logical_line_length: u64,
+    /// The maximum number of properties of a vcard, BEGIN, VERSION and END excluded.
+    /// Lines that cannot be parsed count as well. `parse_vcard`, `read_property` and the `Iterator`
+    /// implementation fail with `VCardError::MaxPropertiesExceeded` if a vcard has more properties.
+    /// Defaults to `None`, which does not limit the number of properties.
+    pub max_properties_per_vcard: Option<u64>,
+    pub line_ending_mode: LineEndingMode,
+    /// If true, LANGUAGE parameters that are no language tags as defined by BCP 47 fail with `VCardError::InvalidValue`,
+    /// see https://datatracker.ietf.org/doc/html/rfc5646#section-2.1
+    /// If false, they are kept and reported as `ParseWarning::InvalidLanguageTag` by `parse_vcard_with_warnings`.
+    /// Defaults to true.
+    pub strict_language_tags: bool,
+    /// RFC 6350 requires VERSION right after BEGIN:VCARD, but some applications write it later.
+    /// `parse_vcard` looks for VERSION within this many properties and parses the properties in front of it
+    /// afterwards. Defaults to 10, 1 only accepts VERSION as second line.
+    /// If VERSION is not found within this depth, parsing fails with `VCardError::InvalidVersionProperty`.
+    pub version_search_depth: usize,
+
+    // line number and byte offset of the last property that was read.
+    property_line: u64,
+    property_byte_offset: u64,
+
+    // true if the last logical line that was read is `BEGIN:VCARD`
+    last_line_was_begin: bool,
+    // true if `skip_to_next_card` already consumed the `BEGIN:VCARD` line of the next vcard
+    begin_consumed: bool,
+    // number of properties of the current vcard, see `max_properties_per_vcard`
+    properties_in_card: u64,
+    // logical lines in front of VERSION that are read again, with their `more` flag and position
+    replay: VecDeque<(String, bool, u64, u64)>,
+
+    // state of the `Iterator` implementation
+    in_card: bool,
+    card_ended: bool,
+    input_exhausted: bool,
 }
 
 const DEFAULT_MAX_LINE_LENGTH: u64 = 5000;
+const DEFAULT_VERSION_SEARCH_DEPTH: usize = 10;
+
+/// Decides which line breaks end a line, see `VCardReader::line_ending_mode`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum LineEndingMode {
+    /// only CRLF ends a line, as required by RFC 6350. Bare LF and CR characters are part of the line.
+    /// See https://datatracker.ietf.org/doc/html/rfc6350#section-3.2
+    Strict,
+    /// CRLF, LF and CR end a line. Many applications write vcards with LF line breaks.
+    #[default]
+    Lenient,
+}
 
 enum LineInspection {
     NoMoreContent,
@@ -47,104 +121,331 @@ impl<R: io::Read> VCardReader<R> {
 
     /// Creates a new `VCardReader` with a configurable line limit
     pub fn new_with_logical_line_limit(input: R, max_logical_line_length: u64) -> Self {
+        Self::new_with_limits(input, max_logical_line_length, None)
+    }
+
+    /// Creates a new `VCardReader` with a configurable line limit and maximum number of properties per vcard
+    pub fn new_with_limits(
+        input: R,
+        max_logical_line_length: u64,
+        max_properties_per_vcard: Option<u64>,
+    ) -> Self {
         Self {
             inner: PushbackReader {
                 inner: io::BufReader::new(input),
+                pending: io::Cursor::new(Vec::new()),
                 num_returned_bytes: 0,
                 buf: [0, 0],
+                consumed_bytes: 0,
+                consumed_lines: 0,
             },
-            discard_buf: Rc::new(RefCell::new(Vec::with_capacity(1024))),
+            discard_buf: Vec::with_capacity(1024),
+            line_buf: String::new(),
             max_logical_line_length,
+            max_properties_per_vcard,
+            line_ending_mode: LineEndingMode::default(),
+            strict_language_tags: true,
+            version_search_depth: DEFAULT_VERSION_SEARCH_DEPTH,
+            property_line: 1,
+            property_byte_offset: 0,
+            last_line_was_begin: false,
+            begin_consumed: false,
+            properties_in_card: 0,
+            replay: VecDeque::new(),
+            in_card: false,
+            card_ended: false,
+            input_exhausted: false,
         }
     }
 
-    pub fn parse_vcard(&mut self) -> Result<VCard, VCardError> {
-        let (prop, more) = self.read_property()?;
-        match prop {
-            Property::Begin { value } => {
-                if &value[..] != "VCARD" {
-                    return Err(VCardError::InvalidBeginProperty);
+    /// Returns an iterator over all vcards of the underlying reader.
+    ///
+    /// Blank lines between two vcards are skipped.
+    /// If a vcard cannot be parsed, the error is returned and the iterator continues with the next `BEGIN:VCARD` line.
+    /// This includes `VCardError::MaxLineLengthExceeded` and `VCardError::MaxPropertiesExceeded`, because the
+    /// limits apply to a single vcard and the following vcards can still be read.
+    /// Only after an I/O error, or if the input ends inside a vcard, the iterator returns `None`.
+    ///
+    /// The vcards are read one by one, so stopping early does not read the remaining input:
+    ///
+    /// ```
+    /// use std::io::{self, Read};
+    /// use vcard::*;
+    ///
+    /// // returns the first vcard and the first line of the second one, reading any further fails
+    /// struct FirstCardOnly<'a>(&'a [u8]);
+    ///
+    /// impl Read for FirstCardOnly<'_> {
+    ///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    ///         if self.0.is_empty() {
+    ///             return Err(io::Error::new(io::ErrorKind::Other, "read after the first vcard"));
+    ///         }
+    ///         self.0.read(buf)
+    ///     }
+    /// }
+    ///
+    /// let input = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nEND:VCARD\r\nBEGIN:VCARD\r\n";
+    /// let mut heinrich = None;
+    /// for card in VCardReader::new(FirstCardOnly(input.as_bytes())).vcards() {
+    ///     let card = card?;
+    ///     if card.fn_property.iter().any(|f| f.value == "Heinrich") {
+    ///         heinrich = Some(card);
+    ///         break;
+    ///     }
+    /// }
+    /// assert!(heinrich.is_some());
+    /// # Ok::<(), VCardError>(())
+    /// ```
+    pub fn vcards(self) -> VCardIterator<R> {
+        VCardIterator {
+            reader: self,
+            done: false,
+            resync: false,
+        }
+    }
+
+    /// Skips all lines until the next `BEGIN:VCARD` line, so that the next call to `parse_vcard` parses the following vcard.
+    /// This can be used to continue reading after `parse_vcard` returned an error.
+    ///
+    /// Returns `false` if the end of the input was reached without finding another vcard.
+    pub fn skip_to_next_card(&mut self) -> Result<bool, VCardError> {
+        // a vcard without END:VCARD fails when the BEGIN:VCARD line of the following vcard is read.
+        if self.last_line_was_begin {
+            self.last_line_was_begin = false;
+            self.begin_consumed = true;
+            return Ok(true);
+        }
+
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match self.read_physical_line(&mut line) {
+                Ok(()) => {}
+                // the remainder of the line is read by the next iteration
+                Err(VCardError::MaxLineLengthExceeded(_)) => continue,
+                Err(VCardError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(false);
                 }
+                Err(e) => return Err(e),
+            }
+            if is_begin_line(&line) {
+                self.begin_consumed = true;
+                return Ok(true);
             }
-            _ => return Err(VCardError::InvalidBeginProperty),
         }
+    }
 
-        if !more {
-            return Err(VCardError::InvalidVersionProperty);
+    /// Parses the next vcard. Blank lines in front of the vcard are skipped.
+    /// VERSION does not have to be the first property, see `version_search_depth`.
+    pub fn parse_vcard(&mut self) -> Result<VCard, VCardError> {
+        self.parse_vcard_at_position(None, None)
+            .map_err(|e| self.at_position(e))
+    }
+
+    /// Parses the next vcard like `parse_vcard`, but continues after recoverable errors.
+    ///
+    /// Recoverable errors are returned next to the vcard:
+    /// - properties that cannot be parsed, e.g. because of an invalid value, are stored as `ProprietaryProperty`
+    /// - lines that are no valid content lines and properties that violate the cardinality of the vcard are skipped
+    ///
+    /// A missing `BEGIN:VCARD`, `VERSION` or `END:VCARD` and I/O errors still abort parsing.
+    pub fn parse_vcard_lenient(&mut self) -> Result<(VCard, Vec<VCardError>), VCardError> {
+        let mut errors = Vec::new();
+        let vcard = self
+            .parse_vcard_at_position(Some(&mut errors), None)
+            .map_err(|e| self.at_position(e))?;
+        Ok((vcard, errors))
+    }
+
+    /// Parses the next vcard like `parse_vcard_lenient`, but also reports issues that do not prevent parsing,
+    /// e.g. unknown parameters or `TYPE=pref` in a vCard 4.0.
+    ///
+    /// Recoverable errors are reported as `ParseWarning::PropertyIgnored`. The warnings are ordered by their line.
+    /// A missing `BEGIN:VCARD`, `VERSION` or `END:VCARD` and I/O errors still abort parsing.
+    pub fn parse_vcard_with_warnings(&mut self) -> Result<(VCard, Vec<ParseWarning>), VCardError> {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let vcard = self
+            .parse_vcard_at_position(Some(&mut errors), Some(&mut warnings))
+            .map_err(|e| self.at_position(e))?;
+
+        warnings.extend(errors.into_iter().map(|e| ParseWarning::PropertyIgnored {
+            line: e.position().map_or(self.property_line, |(line, _)| line),
+            reason: e.kind().to_string(),
+        }));
+        warnings.sort_by_key(ParseWarning::line);
+        Ok((vcard, warnings))
+    }
+
+    // checks the LANGUAGE parameters of `line`, see `strict_language_tags`. Empty values are ignored.
+    fn check_language_tags(
+        &self,
+        line: &ContentLine<'_>,
+        warnings: Option<&mut Vec<ParseWarning>>,
+    ) -> Result<(), VCardError> {
+        let invalid = line
+            .params
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("LANGUAGE"))
+            .map(|(_, v)| unquote_param_value(v))
+            .find(|v| !v.is_empty() && !is_language_tag(v));
+        match (invalid, warnings) {
+            (Some(tag), _) if self.strict_language_tags => Err(VCardError::InvalidValue {
+                expected_values: "a language tag as defined by BCP 47".into(),
+                actual_value: tag.into_owned(),
+                raw_line: self.line_buf.clone(),
+            }),
+            (Some(tag), Some(warnings)) => {
+                warnings.push(ParseWarning::InvalidLanguageTag {
+                    line: self.property_line,
+                    property: line.name.to_uppercase(),
+                    value: tag.into_owned(),
+                });
+                Ok(())
+            }
+            _ => Ok(()),
         }
-        let (prop, more) = self.read_property()?;
-        let version = match prop {
-            Property::Version(v) => v,
-            _ => return Err(VCardError::InvalidVersionProperty),
-        };
+    }
 
-        if !more {
-            return Err(VCardError::InvalidEndProperty);
+    // wraps the error with the position of the last property that was read.
+    fn at_position(&self, e: VCardError) -> VCardError {
+        VCardError::At {
+            line: self.property_line,
+            byte_offset: self.property_byte_offset,
+            source: Box::new(e),
         }
+    }
+
+    fn parse_vcard_at_position(
+        &mut self,
+        mut soft_errors: Option<&mut Vec<VCardError>>,
+        mut warnings: Option<&mut Vec<ParseWarning>>,
+    ) -> Result<VCard, VCardError> {
+        if self.begin_consumed {
+            self.begin_consumed = false;
+        } else {
+            self.skip_blank_lines()?;
+            let (prop, more) = self.read_property_at_position()?;
+            match prop {
+                Property::Begin { value } => {
+                    if &value[..] != "VCARD" {
+                        return Err(VCardError::InvalidBeginProperty);
+                    }
+                }
+                _ => return Err(VCardError::InvalidBeginProperty),
+            }
+
+            if !more {
+                return Err(VCardError::InvalidVersionProperty);
+            }
+        }
+        let version = self.find_version()?;
 
         let mut result = VCard {
             version,
             ..Default::default()
         };
 
+        self.properties_in_card = 0;
         loop {
-            let (prop, more) = self.read_property()?;
-            match prop {
-                Property::Version(_) => {
-                    return Err(VCardError::InvalidCardinality {
-                        expected: 1,
-                        property: "VERSION".into(),
-                    })
-                }
-                Property::Begin { value: _ } => {
-                    return Err(VCardError::InvalidCardinality {
-                        expected: 1,
-                        property: "BEGIN".into(),
-                    })
-                }
-                Property::End { value } => {
-                    if &value[..] != "VCARD" || more {
-                        return Err(VCardError::InvalidEndProperty);
+            self.read_line_into_buf()?;
+            self.count_property()?;
+            let line = match ContentLine::parse(&self.line_buf) {
+                Ok(line) => line,
+                Err(e) => match soft_errors.as_deref_mut() {
+                    Some(errors) => {
+                        errors.push(self.at_position(e));
+                        continue;
                     }
-                    return Ok(result);
+                    None => return Err(e),
+                },
+            };
+            if let Some(warnings) = warnings.as_deref_mut() {
+                inspect_content_line(&line, &result.version.value, self.property_line, warnings);
+            }
+            let prop = match self
+                .check_language_tags(&line, warnings.as_deref_mut())
+                .and_then(|()| Property::try_from(&line))
+            {
+                Ok(prop) => prop,
+                Err(e) => match soft_errors.as_deref_mut() {
+                    Some(errors) => {
+                        // keep the raw content so that no data is lost
+                        result
+                            .proprietary_properties
+                            .push(content_line_to_proprietary(&line));
+                        errors.push(self.at_position(e));
+                        continue;
+                    }
+                    None => return Err(e),
+                },
+            };
+            // a nested BEGIN:VCARD most likely means that END:VCARD is missing, which cannot be recovered
+            let is_begin = matches!(prop, Property::Begin { .. });
+            match add_property(&mut result, prop) {
+                Ok(true) => return Ok(result),
+                Ok(false) => {}
+                Err(e) => match soft_errors.as_deref_mut() {
+                    Some(errors) if !is_begin => errors.push(self.at_position(e)),
+                    _ => return Err(e),
+                },
+            }
+        }
+    }
+
+    // reads the VERSION property within the next `version_search_depth` properties.
+    // The lines in front of it are read again by the following calls of `read_line_into_buf`.
+    fn find_version(&mut self) -> Result<Version, VCardError> {
+        let mut skipped = VecDeque::new();
+        for _ in 0..self.version_search_depth {
+            let more = self.read_line_into_buf()?;
+            let line = ContentLine::parse(&self.line_buf).ok();
+            let name = line.as_ref().map_or("", |l| l.name);
+            if let Some(line) = line
+                .as_ref()
+                .filter(|_| name.eq_ignore_ascii_case("VERSION"))
+            {
+                let version = match Property::try_from(line)? {
+                    Property::Version(v) => v,
+                    _ => return Err(VCardError::InvalidVersionProperty),
+                };
+                if !more && skipped.is_empty() {
+                    return Err(VCardError::InvalidEndProperty);
                 }
+                self.replay = skipped;
+                return Ok(version);
+            }
+            // the vcard ends without VERSION
+            if !more || name.eq_ignore_ascii_case("END") || name.eq_ignore_ascii_case("BEGIN") {
+                break;
+            }
+            skipped.push_back((
+                self.line_buf.clone(),
+                more,
+                self.property_line,
+                self.property_byte_offset,
+            ));
+        }
+        Err(VCardError::InvalidVersionProperty)
+    }
 
-                Property::Source(s) => result.source.add_value(s),
-                Property::Kind(k) => add_single_value!(result, kind, k),
-                Property::Xml(x) => result.xml.add_value(x),
-                Property::FN(f) => result.fn_property.add_value(f),
-                Property::N(n) => result.n.add_value(n)?,
-                Property::NickName(n) => result.nickname.add_value(n),
-                Property::Photo(p) => result.photo.add_value(p),
-                Property::BDay(b) => result.bday.add_value(b)?,
-                Property::Anniversary(a) => result.anniversary.add_value(a)?,
-                Property::Gender(g) => add_single_value!(result, gender, g),
-                Property::Adr(a) => result.adr.add_value(a),
-                Property::Tel(t) => result.tel.add_value(t),
-                Property::Email(e) => result.email.add_value(e),
-                Property::Impp(i) => result.impp.add_value(i),
-                Property::Lang(l) => result.lang.add_value(l),
-                Property::Tz(t) => result.tz.add_value(t),
-                Property::Geo(g) => result.geo.add_value(g),
-                Property::Title(t) => result.title.add_value(t),
-                Property::Role(r) => result.role.add_value(r),
-                Property::Logo(l) => result.logo.add_value(l),
-                Property::Org(o) => result.org.add_value(o),
-                Property::Member(m) => result.member.add_value(m),
-                Property::Related(r) => result.related.add_value(r),
-                Property::Categories(c) => result.categories.add_value(c),
-                Property::Note(n) => result.note.add_value(n),
-                Property::ProdId(p) => add_single_value!(result, prodid, p),
-                Property::Rev(r) => add_single_value!(result, rev, r),
-                Property::Sound(s) => result.sound.add_value(s),
-                Property::Uid(u) => add_single_value!(result, uid, u),
-                Property::ClientPidMap(c) => add_single_value!(result, clientpidmap, c),
-                Property::Url(u) => result.url.add_value(u),
-                Property::Key(k) => result.key.add_value(k),
-                Property::FbUrl(f) => result.fburl.add_value(f),
-                Property::CalUri(c) => result.caluri.add_value(c),
-                Property::CalAdUri(c) => result.caladuri.add_value(c),
-                Property::Proprietary(p) => result.proprietary_properties.push(p),
+    // consumes all line breaks and whitespace characters until the next content is reached.
+    // Returns `false` if the end of the input was reached.
+    fn skip_blank_lines(&mut self) -> Result<bool, VCardError> {
+        let mut buf = [0];
+        loop {
+            if let Err(e) = self.inner.read_exact(&mut buf) {
+                match e.kind() {
+                    io::ErrorKind::UnexpectedEof => return Ok(false),
+                    _ => return Err(VCardError::Io(e)),
+                }
+            }
+            match buf[0] {
+                b'\r' | b'\n' | b' ' | b'\t' => continue,
+                b => {
+                    self.inner.return_byte(b);
+                    return Ok(true);
+                }
             }
         }
     }
@@ -173,13 +474,11 @@ impl<R: io::Read> VCardReader<R> {
         match buf[1] {
             b' ' | b'\t' | b'\n' | b'\r' => {
                 self.inner.return_bytes(buf);
-                return Ok(LineInspection::Discard);
+                Ok(LineInspection::Discard)
             }
             _ => {
-                return {
-                    self.inner.return_byte(buf[1]);
-                    Ok(LineInspection::LogicalLine)
-                }
+                self.inner.return_byte(buf[1]);
+                Ok(LineInspection::LogicalLine)
             }
         }
     }
@@ -188,11 +487,86 @@ impl<R: io::Read> VCardReader<R> {
     /// an `VCardError::MaxLineLengthExceeded` will be returned.
     /// see https://datatracker.ietf.org/doc/html/rfc6350#section-3.2 for more information about logical lines.
     pub fn read_property(&mut self) -> Result<(Property, bool), VCardError> {
-        let (line, more) = self.read_logical_line()?;
-        Ok((Property::from_str(&line[..])?, more))
+        self.read_property_at_position()
+            .map_err(|e| self.at_position(e))
+    }
+
+    fn read_property_at_position(&mut self) -> Result<(Property, bool), VCardError> {
+        let more = self.read_line_into_buf()?;
+        self.count_property()?;
+        let line = ContentLine::parse(&self.line_buf)?;
+        Ok((Property::try_from(&line)?, more))
     }
+
+    /// Reads the next content line of this vcard without parsing it into a `Property`.
+    ///
+    /// The returned `ContentLine` borrows from the reader and is valid until the next line is read.
+    /// Use `Property::try_from` to parse it, e.g. after filtering by name.
+    pub fn read_content_line(&mut self) -> Result<(ContentLine<'_>, bool), VCardError> {
+        let more = self
+            .read_line_into_buf()
+            .and_then(|more| self.count_property().map(|()| more))
+            .map_err(|e| self.at_position(e))?;
+        match ContentLine::parse(&self.line_buf) {
+            Ok(line) => Ok((line, more)),
+            Err(e) => Err(self.at_position(e)),
+        }
+    }
+
+    // counts the last logical line against `max_properties_per_vcard`, including lines that cannot be parsed.
+    // BEGIN starts counting again, VERSION and END are not counted.
+    fn count_property(&mut self) -> Result<(), VCardError> {
+        let name = self.line_buf.split([';', ':']).next().unwrap_or_default();
+        let name = name.rsplit('.').next().unwrap_or_default();
+        if name.eq_ignore_ascii_case("BEGIN") {
+            self.properties_in_card = 0;
+            return Ok(());
+        }
+        if name.eq_ignore_ascii_case("VERSION") || name.eq_ignore_ascii_case("END") {
+            return Ok(());
+        }
+        self.properties_in_card += 1;
+        match self.max_properties_per_vcard {
+            Some(max) if self.properties_in_card > max => {
+                Err(VCardError::MaxPropertiesExceeded(max))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn read_line_into_buf(&mut self) -> Result<bool, VCardError> {
+        self.last_line_was_begin = false;
+        if let Some((line, more, property_line, property_byte_offset)) = self.replay.pop_front() {
+            self.line_buf = line;
+            self.property_line = property_line;
+            self.property_byte_offset = property_byte_offset;
+            return Ok(more);
+        }
+        let (mut line, mut more) = self.read_logical_line()?;
+        let (property_line, property_byte_offset) = (self.property_line, self.property_byte_offset);
+        // quoted-printable values of vCard 2.1 continue on the next line if the line ends with a soft line break (=)
+        while more && has_soft_line_break(line.as_bytes()) {
+            line.pop();
+            let (next, next_more) = self.read_logical_line()?;
+            line.push_str(&next);
+            more = next_more;
+            if line.len() as u64 > self.max_logical_line_length {
+                return Err(VCardError::MaxLineLengthExceeded(
+                    self.max_logical_line_length,
+                ));
+            }
+        }
+        self.property_line = property_line;
+        self.property_byte_offset = property_byte_offset;
+        self.last_line_was_begin = is_begin_line(line.as_bytes());
+        self.line_buf = line;
+        Ok(more)
+    }
+
     fn read_logical_line(&mut self) -> Result<(String, bool), VCardError> {
         let mut logical_line_buf = Vec::new();
+        self.property_line = self.inner.consumed_lines + 1;
+        self.property_byte_offset = self.inner.consumed_bytes;
 
         // a logical line always starts with a new property declaration
         let result = self.read_physical_line(&mut logical_line_buf);
@@ -232,10 +606,11 @@ impl<R: io::Read> VCardReader<R> {
         }
     }
     fn discard_line(&mut self) -> Result<(), VCardError> {
-        let rc = Rc::clone(&self.discard_buf.clone());
-        let mut buf = rc.as_ref().borrow_mut();
-        self.read_physical_line(&mut buf)?;
-        Ok(())
+        let mut buf = std::mem::take(&mut self.discard_buf);
+        buf.clear();
+        let result = self.read_physical_line(&mut buf);
+        self.discard_buf = buf;
+        result
     }
 
     fn read_physical_line(&mut self, buf: &mut Vec<u8>) -> Result<(), VCardError> {
@@ -249,29 +624,380 @@ impl<R: io::Read> VCardReader<R> {
             }
             // this should be okay since lines are usually short and we use a bufreader
             self.inner.read_exact(&mut tmp_buf)?;
-            if tmp_buf[0] == b'\r' {
-                // read one more byte to see if it is a \n char
-                self.inner.read_exact(&mut tmp_buf)?;
-                if tmp_buf[0] == b'\n' {
-                    return Ok(());
-                } else {
-                    buf.extend(tmp_buf);
+            let lenient = self.line_ending_mode == LineEndingMode::Lenient;
+            match tmp_buf[0] {
+                b'\r' => {
+                    // read one more byte to see if it is a \n char
+                    if let Err(e) = self.inner.read_exact(&mut tmp_buf) {
+                        if lenient && e.kind() == io::ErrorKind::UnexpectedEof {
+                            return Ok(());
+                        }
+                        return Err(e.into());
+                    }
+                    if tmp_buf[0] == b'\n' {
+                        return Ok(());
+                    } else if lenient {
+                        // a bare \r ends the line, the byte belongs to the next line
+                        self.inner.return_byte(tmp_buf[0]);
+                        self.inner.consumed_lines += 1;
+                        return Ok(());
+                    } else {
+                        buf.push(b'\r');
+                        buf.extend(tmp_buf);
+                    }
+                }
+                b'\n' if lenient => return Ok(()),
+                _ => buf.extend(tmp_buf),
+            }
+        }
+    }
+}
+
+// adds `prop` to `result`. Returns `true` if `prop` is the END:VCARD property.
+pub(crate) fn add_property(result: &mut VCard, prop: Property) -> Result<bool, VCardError> {
+    match prop {
+        Property::Version(_) => {
+            return Err(VCardError::InvalidCardinality {
+                expected: 1,
+                property: "VERSION".into(),
+            })
+        }
+        Property::Begin { value: _ } => {
+            return Err(VCardError::InvalidCardinality {
+                expected: 1,
+                property: "BEGIN".into(),
+            })
+        }
+        Property::End { value } => {
+            if &value[..] != "VCARD" {
+                return Err(VCardError::InvalidEndProperty);
+            }
+            return Ok(true);
+        }
+
+        Property::Source(s) => result.source.add_value(s),
+        Property::Kind(k) => add_single_value!(result, kind, k),
+        Property::Xml(x) => result.xml.add_value(x),
+        Property::FN(f) => result.fn_property.add_value(f),
+        Property::N(n) => result.n.add_value(n)?,
+        Property::NickName(n) => result.nickname.add_value(n),
+        Property::Photo(p) => result.photo.add_value(p),
+        Property::BDay(b) => result.bday.add_value(b)?,
+        Property::Anniversary(a) => result.anniversary.add_value(a)?,
+        Property::Gender(g) => add_single_value!(result, gender, g),
+        Property::Adr(a) => result.adr.add_value(a),
+        Property::Tel(t) => result.tel.add_value(t),
+        Property::Email(e) => result.email.add_value(e),
+        Property::Impp(i) => result.impp.add_value(i),
+        Property::Lang(l) => result.lang.add_value(l),
+        Property::Tz(t) => result.tz.add_value(t),
+        Property::Geo(g) => result.geo.add_value(g),
+        Property::Title(t) => result.title.add_value(t),
+        Property::Role(r) => result.role.add_value(r),
+        Property::Logo(l) => result.logo.add_value(l),
+        Property::Org(o) => result.org.add_value(o),
+        Property::Member(m) => result.member.add_value(m),
+        Property::Related(r) => result.related.add_value(r),
+        Property::Categories(c) => result.categories.add_value(c),
+        Property::Note(n) => result.note.add_value(n),
+        Property::ProdId(p) => add_single_value!(result, prodid, p),
+        Property::Rev(r) => add_single_value!(result, rev, r),
+        Property::Sound(s) => result.sound.add_value(s),
+        Property::Uid(u) => add_single_value!(result, uid, u),
+        Property::ClientPidMap(c) => result.clientpidmap.push(c),
+        Property::Url(u) => result.url.add_value(u),
+        Property::Key(k) => result.key.add_value(k),
+        Property::FbUrl(f) => result.fburl.add_value(f),
+        Property::CalUri(c) => result.caluri.add_value(c),
+        Property::CalAdUri(c) => result.caladuri.add_value(c),
+        Property::Legacy(l) => {
+            if result.version.value == VersionValue::V4 {
+                return Err(VCardError::InvalidName {
+                    actual_name: l.name.as_ref().into(),
+                    raw_line: l.to_string(),
+                });
+            }
+            result.legacy.push(l)
+        }
+        Property::Proprietary(p) => result.proprietary_properties.push(p),
+    }
+    Ok(false)
+}
+
+// converts a content line that could not be parsed into a proprietary property
+pub(crate) fn content_line_to_proprietary(line: &ContentLine<'_>) -> ProprietaryProperty {
+    ProprietaryProperty {
+        name: line.name.into(),
+        group: line.group.map(Into::into),
+        value: line.value.into(),
+        parameters: line
+            .params
+            .iter()
+            .map(|(k, v)| {
+                let v = quote_param_value(&unquote_param_value(v));
+                Parameter::Proprietary(format!("{}={}", k, v))
+            })
+            .collect(),
+    }
+}
+
+// returns true if the line is a quoted-printable value of vCard 2.1 that ends with a soft line break (=),
+// the value continues on the next logical line then
+pub(crate) fn has_soft_line_break(line: &[u8]) -> bool {
+    line.ends_with(b"=") && is_quoted_printable(line)
+}
+
+// returns true if the parameters of the line contain the QUOTED-PRINTABLE encoding
+fn is_quoted_printable(line: &[u8]) -> bool {
+    let params = line.split(|&b| b == b':').next().unwrap_or_default();
+    params
+        .windows(b"QUOTED-PRINTABLE".len())
+        .any(|w| w.eq_ignore_ascii_case(b"QUOTED-PRINTABLE"))
+}
+
+fn is_begin_line(line: &[u8]) -> bool {
+    line.eq_ignore_ascii_case(b"BEGIN:VCARD")
+}
+
+fn is_io_error(e: &VCardError) -> bool {
+    matches!(e.kind(), VCardError::Io(_))
+}
+
+/// Iterates over the properties of the vcards, from `BEGIN:VCARD` to `END:VCARD`.
+///
+/// After `END:VCARD` was returned, `next` returns `None` once, so that a `for` loop stops at the end of the vcard.
+/// Calling `next` again continues with the following vcard. Blank lines between two vcards are skipped.
+/// The iteration stops at the end of the input and after the first I/O error.
+impl<R: io::Read> Iterator for VCardReader<R> {
+    type Item = Result<Property, VCardError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input_exhausted {
+            return None;
+        }
+        if self.card_ended {
+            self.card_ended = false;
+            return None;
+        }
+        if self.begin_consumed {
+            self.begin_consumed = false;
+            self.in_card = true;
+            self.properties_in_card = 0;
+            return Some(Ok(Property::Begin {
+                value: "VCARD".into(),
+            }));
+        }
+        if !self.in_card {
+            match self.skip_blank_lines() {
+                Ok(true) => self.in_card = true,
+                Ok(false) => {
+                    self.input_exhausted = true;
+                    return None;
+                }
+                Err(e) => {
+                    self.input_exhausted = true;
+                    return Some(Err(self.at_position(e)));
                 }
+            }
+        }
+
+        match self.read_property() {
+            Ok((prop, more)) => {
+                if let Property::End { value } = &prop {
+                    if value.eq_ignore_ascii_case("VCARD") {
+                        self.in_card = false;
+                        self.card_ended = true;
+                    }
+                }
+                if !more {
+                    self.input_exhausted = true;
+                }
+                Some(Ok(prop))
+            }
+            Err(e) => {
+                if is_io_error(&e) {
+                    self.input_exhausted = true;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Assembles a vcard from an iterator over its properties, e.g. a `VCardReader`.
+///
+/// The first property has to be `BEGIN:VCARD`, the second one `VERSION` and the last one `END:VCARD`.
+/// Properties after `END:VCARD` are not consumed. The first error of the iterator is returned.
+/// When reading several vcards from one `VCardReader`, the `None` that follows each `END:VCARD` has to be consumed
+/// before the next vcard is parsed.
+pub fn parse_vcard_from_iter<I>(properties: I) -> Result<VCard, VCardError>
+where
+    I: IntoIterator<Item = Result<Property, VCardError>>,
+{
+    let mut properties = properties.into_iter();
+    match properties.next().transpose()? {
+        Some(Property::Begin { value }) if &value[..] == "VCARD" => {}
+        _ => return Err(VCardError::InvalidBeginProperty),
+    }
+    let version = match properties.next().transpose()? {
+        Some(Property::Version(v)) => v,
+        _ => return Err(VCardError::InvalidVersionProperty),
+    };
+
+    let mut result = VCard {
+        version,
+        ..Default::default()
+    };
+    for prop in properties {
+        if add_property(&mut result, prop?)? {
+            return Ok(result);
+        }
+    }
+    Err(VCardError::InvalidEndProperty)
+}
+
+/// An iterator over all vcards of a `VCardReader`, see `VCardReader::vcards`.
+///
+/// After a vcard could not be parsed, the iterator skips to the next `BEGIN:VCARD` line.
+/// The iteration stops after the first I/O error.
+pub struct VCardIterator<R: io::Read> {
+    reader: VCardReader<R>,
+    done: bool,
+    resync: bool,
+}
+
+/// The iterator returned by `VCardReader::vcards`, another name for `VCardIterator`.
+pub type VCardIter<R> = VCardIterator<R>;
+
+impl<R: io::Read> Iterator for VCardIterator<R> {
+    type Item = Result<VCard, VCardError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next_card = if self.resync {
+            self.resync = false;
+            self.reader.skip_to_next_card()
+        } else {
+            self.reader.skip_blank_lines()
+        };
+        match next_card {
+            Ok(true) => {}
+            Ok(false) => {
+                self.done = true;
+                return None;
+            }
+            Err(e) => {
+                self.done = true;
+                return Some(Err(self.reader.at_position(e)));
+            }
+        }
+
+        let result = self.reader.parse_vcard();
+        if let Err(e) = &result {
+            if is_io_error(e) {
+                self.done = true;
             } else {
-                buf.extend(tmp_buf);
+                self.resync = true;
             }
         }
+        Some(result)
     }
 }
 
+impl VCard {
+    /// Parses a single vcard from a string, see `VCardReader::parse_vcard`.
+    ///
+    /// Whitespace and blank lines after `END:VCARD` are ignored, any other content is an error.
+    /// Use `VCard::parse_all` for strings with more than one vcard.
+    ///
+    /// ```
+    /// let card = vcard::VCard::parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nEND:VCARD\r\n")?;
+    /// assert_eq!("Heinrich", card.fn_property.get_prefered_value().unwrap().value);
+    /// # Ok::<(), vcard::VCardError>(())
+    /// ```
+    pub fn parse(input: &str) -> Result<VCard, VCardError> {
+        input.parse()
+    }
+
+    /// Parses all vcards of a string.
+    ///
+    /// Whitespace and blank lines between and after the vcards are ignored.
+    /// Any other content after the last `END:VCARD` is an error.
+    ///
+    /// ```
+    /// let input = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nEND:VCARD\r\n\r\n\
+    ///              BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Judith\r\nEND:VCARD\r\n";
+    /// let cards = vcard::VCard::parse_all(input)?;
+    /// assert_eq!(2, cards.len());
+    /// # Ok::<(), vcard::VCardError>(())
+    /// ```
+    pub fn parse_all(input: &str) -> Result<Vec<VCard>, VCardError> {
+        let mut reader = VCardReader::new(input.as_bytes());
+        let mut result = Vec::new();
+        while reader
+            .skip_blank_lines()
+            .map_err(|e| reader.at_position(e))?
+        {
+            result.push(reader.parse_vcard()?);
+        }
+        Ok(result)
+    }
+}
+
+impl FromStr for VCard {
+    type Err = VCardError;
+
+    /// Parses a single vcard, see `VCard::parse`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_single_vcard(VCardReader::new(io::Cursor::new(s)))
+    }
+}
+
+impl TryFrom<&str> for VCard {
+    type Error = VCardError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<Vec<u8>> for VCard {
+    type Error = VCardError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        parse_single_vcard(VCardReader::new(io::Cursor::new(value)))
+    }
+}
+
+// parses the only vcard of the input, content after its END:VCARD other than blank lines is an error.
+fn parse_single_vcard<R: io::Read>(mut reader: VCardReader<R>) -> Result<VCard, VCardError> {
+    let card = reader.parse_vcard()?;
+    if reader
+        .skip_blank_lines()
+        .map_err(|e| reader.at_position(e))?
+    {
+        return Err(reader.at_position(VCardError::InvalidEndProperty));
+    }
+    Ok(card)
+}
+
 // This reader makes it possible to return a certain amount of bytes back to the reader itself (two to be precise).
 // The use case is the inspection of bytes in order to determine the continuation/end of logical lines in a vcard.
 struct PushbackReader<R> {
     inner: BufReader<R>,
+    // the bytes that were buffered by the reader this one was cloned from, they are read before `inner`.
+    // This is always empty if the reader was not cloned.
+    pending: io::Cursor<Vec<u8>>,
     buf: [u8; 2],
 
     // num_buf_bytes can be 2 at maximum
     num_returned_bytes: usize,
+
+    // the amount of bytes and line breaks (\n) read from this reader, minus the returned ones.
+    consumed_bytes: u64,
+    consumed_lines: u64,
 }
 
 impl<R: io::Read> PushbackReader<R> {
@@ -282,19 +1008,58 @@ impl<R: io::Read> PushbackReader<R> {
         if self.num_returned_bytes >= 2 {
             self.num_returned_bytes = 0;
         }
-        // this is safe because num_retruned_bytes can be at max 1 here.
-        self.buf[self.num_returned_bytes] = b;
-        self.num_returned_bytes = self.num_returned_bytes + 1;
+        self.unconsume(b);
+        // the byte was read before a byte that is still returned, so it has to be read first again.
+        self.buf = [b, self.buf[0]];
+        self.num_returned_bytes += 1;
+    }
+
+    fn unconsume(&mut self, b: u8) {
+        self.consumed_bytes -= 1;
+        if b == b'\n' {
+            self.consumed_lines -= 1;
+        }
     }
 
     fn return_bytes(&mut self, b: [u8; 2]) {
+        self.unconsume(b[0]);
+        self.unconsume(b[1]);
         self.buf = b;
         self.num_returned_bytes = 2;
     }
 }
+// `BufReader` cannot be cloned and a new `BufReader` cannot be created with buffered bytes.
+// The clone reads the bytes that are buffered here from `pending` before it continues with a clone of the input.
+impl<R: io::Read + Clone> Clone for PushbackReader<R> {
+    fn clone(&self) -> Self {
+        let mut pending = self.pending.get_ref()[self.pending.position() as usize..].to_vec();
+        pending.extend_from_slice(self.inner.buffer());
+        Self {
+            inner: BufReader::with_capacity(self.inner.capacity(), self.inner.get_ref().clone()),
+            pending: io::Cursor::new(pending),
+            buf: self.buf,
+            num_returned_bytes: self.num_returned_bytes,
+            consumed_bytes: self.consumed_bytes,
+            consumed_lines: self.consumed_lines,
+        }
+    }
+}
+
 impl<R: io::Read> Read for PushbackReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.read_unconsumed(buf)?;
+        self.consumed_bytes += result as u64;
+        self.consumed_lines += buf[..result].iter().filter(|b| **b == b'\n').count() as u64;
+        Ok(result)
+    }
+}
+
+impl<R: io::Read> PushbackReader<R> {
+    fn read_unconsumed(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         if self.num_returned_bytes == 0 {
+            if (self.pending.position() as usize) < self.pending.get_ref().len() {
+                return self.pending.read(buf);
+            }
             return self.inner.read(buf);
         }
         let first = &self.buf.as_ref()[0..self.num_returned_bytes];
@@ -305,12 +1070,12 @@ impl<R: io::Read> Read for PushbackReader<R> {
         // in case more than one bytes where read, we just invalidate the whole buffer.
         if result == 1 {
             self.buf[0] = self.buf[1];
-            self.num_returned_bytes = self.num_returned_bytes - 1;
+            self.num_returned_bytes -= 1;
         } else {
             self.num_returned_bytes = 0;
         }
 
-        return Ok(result);
+        Ok(result)
     }
 }
 
@@ -329,7 +1094,7 @@ mod tests {
 
         let mut reader = VCardReader::new(&testant[..]);
 
-        let expected = vec![
+        let expected = [
             Property::Begin {
                 value: "VCARD".into(),
             },
@@ -415,6 +1180,7 @@ mod tests {
                 value: vec!["Richter GBR".into()],
             }),
             Property::BDay(BDay {
+                group: None,
                 altid: None,
                 calscale: None,
                 value_data_type: Some(ValueDataType::Date),
@@ -457,6 +1223,7 @@ mod tests {
                 parameters: Vec::new(),
             }),
             Property::Tel(Tel {
+                group: Some("item3".into()),
                 type_param: Some(vec!["CELL".into(), "pref".into(), "VOICE".into()]),
                 value_data_type: None,
                 pid: None,
@@ -464,6 +1231,12 @@ mod tests {
                 altid: None,
                 value: "017610101520".into(),
             }),
+            Property::Proprietary(ProprietaryProperty {
+                name: "X-ABLABEL".into(),
+                group: Some("item3".into()),
+                value: "_$!<Mobile>!$_".into(),
+                parameters: Vec::new(),
+            }),
             Property::Url(Url {
                 group: Some("item2".into()),
                 type_param: Some(vec!["pref".into()]),
@@ -513,4 +1286,612 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_multiple_vcards() -> Result<(), Box<dyn std::error::Error>> {
+        let with_blank_lines = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/multiple.vcf",
+        ));
+        let without_trailing_newline = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/multiple_no_trailing_newline.vcf",
+        ));
+
+        for testant in [&with_blank_lines[..], &without_trailing_newline[..]] {
+            let cards = VCardReader::new(testant)
+                .vcards()
+                .collect::<Result<Vec<VCard>, VCardError>>()?;
+            assert_eq!(3, cards.len());
+
+            let names: Vec<&str> = cards
+                .iter()
+                .map(|c| c.fn_property.get_prefered_value().unwrap().value.as_str())
+                .collect();
+            assert_eq!(
+                vec!["Heinrich vom Tosafjord", "Judith", "Fiona vom Tosafjord"],
+                names
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_position() {
+        let input = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/invalid_line.vcf",
+        ));
+
+        match VCardReader::new(&input[..]).parse_vcard() {
+            Err(VCardError::At {
+                line, byte_offset, ..
+            }) => {
+                assert_eq!(7, line);
+                assert_eq!(101, byte_offset);
+                assert_eq!(b"this line is invalid", &input[101..121]);
+            }
+            other => panic!("expected an error with a position, got {:?}", other),
+        }
+
+        let input = b"BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nNOPE:value\r\nEND:VCARD\r\n";
+        let e = VCardReader::new(&input[..]).parse_vcard().unwrap_err();
+        assert_eq!(Some((4, 39)), e.position());
+        assert!(matches!(
+            e.kind(),
+            VCardError::InvalidName { actual_name, .. } if actual_name == "NOPE"
+        ));
+        assert_eq!(None, e.kind().position());
+    }
+
+    #[test]
+    fn test_parse_warnings() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = b"BEGIN:VCARD\r\n\
+            VERSION:4.0\r\n\
+            FN;X-CUSTOM=1:Heinrich vom Tosafjord\r\n\
+            EMAIL;TYPE=home,pref:heinrich@example.com\r\n\
+            TEL;FOO=bar:tel:+49-176-1010-1520\r\n\
+            BDAY:circa 1800\r\n\
+            UID:1\r\n\
+            UID:2\r\n\
+            END:VCARD\r\n";
+        let (card, warnings) = VCardReader::new(&testant[..]).parse_vcard_with_warnings()?;
+
+        assert_eq!("1", card.uid.unwrap().value);
+        assert!(card.tel.get_prefered_value().is_some());
+        assert_eq!(
+            vec![
+                ParseWarning::DeprecatedSyntax {
+                    line: 4,
+                    property: "EMAIL".into(),
+                    description: "TYPE=pref is replaced by PREF=1 in vCard 4.0".into(),
+                },
+                ParseWarning::UnknownParameter {
+                    line: 5,
+                    property: "TEL".into(),
+                    parameter: "FOO".into(),
+                },
+                ParseWarning::ImpliedValueType {
+                    line: 5,
+                    property: "TEL".into(),
+                    value_type: ValueDataType::Uri,
+                },
+                ParseWarning::ImpliedValueType {
+                    line: 6,
+                    property: "BDAY".into(),
+                    value_type: ValueDataType::Text,
+                },
+                ParseWarning::PropertyIgnored {
+                    line: 8,
+                    reason: "only 1 amount of uid are valid in a vcard".into(),
+                },
+            ],
+            warnings
+        );
+
+        // missing END:VCARD still aborts parsing
+        let testant = b"BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n";
+        assert!(VCardReader::new(&testant[..])
+            .parse_vcard_with_warnings()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict_language_tags_default() {
+        let input = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN;LANGUAGE=de_DE:Heinrich vom Tosafjord\r\n\
+            END:VCARD\r\n";
+        let mut reader = VCardReader::new(input.as_bytes());
+        assert!(reader.strict_language_tags);
+        let e = reader.parse_vcard().unwrap_err();
+        assert_eq!(Some((3, 26)), e.position());
+        assert!(
+            matches!(e.kind(), VCardError::InvalidValue { actual_value, .. } if actual_value == "de_DE")
+        );
+    }
+
+    #[test]
+    fn test_language_tags() -> Result<(), Box<dyn std::error::Error>> {
+        let card = |language: &str| {
+            format!(
+                "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+                NOTE;LANGUAGE={}:hallo\r\nEND:VCARD\r\n",
+                language
+            )
+        };
+        for valid in ["en", "de-AT", "zh-Hans", "\"sr-Latn-RS\"", ""] {
+            assert!(VCard::parse(&card(valid)).is_ok(), "{}", valid);
+        }
+
+        for invalid in ["zz-INVALID-999", "de_DE", "english", "en-"] {
+            let input = card(invalid);
+            let e = VCard::parse(&input).unwrap_err();
+            match e.kind() {
+                VCardError::InvalidValue { actual_value, .. } => {
+                    assert_eq!(invalid, actual_value)
+                }
+                e => panic!("expected an invalid value for {}, got {:?}", invalid, e),
+            }
+
+            let mut reader = VCardReader::new(input.as_bytes());
+            reader.strict_language_tags = false;
+            let (card, warnings) = reader.parse_vcard_with_warnings()?;
+            assert_eq!(
+                Some(invalid),
+                card.note.iter().next().unwrap().language.as_deref()
+            );
+            assert_eq!(
+                vec![ParseWarning::InvalidLanguageTag {
+                    line: 4,
+                    property: "NOTE".into(),
+                    value: invalid.into(),
+                }],
+                warnings
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_return_byte() -> Result<(), Box<dyn std::error::Error>> {
+        let mut reader = VCardReader::new(&b"\r\nBEGIN"[..]).inner;
+        let mut buf = [0; 2];
+        reader.read_exact(&mut buf)?;
+        // the bytes are returned in reverse order, like when a line break is inspected
+        reader.return_byte(b'\n');
+        reader.return_byte(b'\r');
+        let mut actual = String::new();
+        reader.read_to_string(&mut actual)?;
+        assert_eq!("\r\nBEGIN", actual);
+
+        // a vcard can be read after another one with the same reader
+        let testant = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nEND:VCARD\r\n".repeat(2);
+        let mut reader = VCardReader::new(testant.as_bytes());
+        for _ in 0..2 {
+            let card = reader.parse_vcard()?;
+            assert_eq!("Heinrich", card.fn_property.iter().next().unwrap().value);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = b"BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Judith\r\n\
+            NOTE:a long note that is folded\r\n  over two lines\r\nEND:VCARD\r\n";
+        let mut reader = VCardReader::new(&testant[..]);
+        reader.parse_vcard()?;
+
+        let mut clone = reader.clone();
+        let expected = reader.parse_vcard()?;
+        assert_eq!("Judith", expected.fn_property.iter().next().unwrap().value);
+        assert_eq!(expected, clone.parse_vcard()?);
+        assert!(reader.vcards().next().is_none());
+        assert!(clone.clone().vcards().next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcards_io_error() {
+        // returns its input and fails afterwards
+        struct FailingRead<'a>(&'a [u8]);
+        impl io::Read for FailingRead<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(io::Error::other("connection lost"));
+                }
+                self.0.read(buf)
+            }
+        }
+
+        let testant = b"BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Jud";
+        let mut cards = VCardReader::new(FailingRead(&testant[..])).vcards();
+        assert!(cards.next().unwrap().is_ok());
+        let e = cards.next().unwrap().unwrap_err();
+        assert!(matches!(e.kind(), VCardError::Io(_)));
+        assert!(cards.next().is_none());
+        assert!(cards.next().is_none());
+    }
+
+    #[test]
+    fn test_vcards_after_limit_errors() {
+        let testant = b"BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            EMAIL:a@example.com\r\nEMAIL:b@example.com\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Judith\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Jud";
+        let mut cards = VCardReader::new_with_limits(&testant[..], 5000, Some(2)).vcards();
+        let e = cards.next().unwrap().unwrap_err();
+        assert!(matches!(e.kind(), VCardError::MaxPropertiesExceeded(2)));
+        // the limit applies to a single vcard, the next one is read
+        let card = cards.next().unwrap().unwrap();
+        assert_eq!("Judith", card.fn_property.iter().next().unwrap().value);
+        // the input ends inside a vcard
+        assert!(cards.next().unwrap().is_err());
+        assert!(cards.next().is_none());
+    }
+
+    #[test]
+    fn test_max_properties_per_vcard() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = b"BEGIN:VCARD\r\nVERSION:4.0\r\n\
+            FN:Heinrich\r\n\
+            EMAIL:a@example.com\r\n\
+            EMAIL:b@example.com\r\n\
+            END:VCARD\r\n";
+        let card = VCardReader::new_with_limits(&testant[..], 5000, Some(3)).parse_vcard()?;
+        assert_eq!(2, card.count_emails());
+
+        let mut reader = VCardReader::new_with_limits(&testant[..], 5000, Some(2));
+        let e = reader.parse_vcard().unwrap_err();
+        assert_eq!(Some((5, 60)), e.position());
+        assert!(matches!(e.kind(), VCardError::MaxPropertiesExceeded(2)));
+        // the limit is no recoverable error
+        let mut reader = VCardReader::new(&testant[..]);
+        reader.max_properties_per_vcard = Some(1);
+        assert!(reader.parse_vcard_lenient().is_err());
+
+        // lines that cannot be parsed count as well
+        let garbage = format!(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n{}END:VCARD\r\n",
+            "garbage\r\n".repeat(1000)
+        );
+        let e = VCardReader::new_with_limits(garbage.as_bytes(), 5000, Some(5))
+            .parse_vcard_lenient()
+            .unwrap_err();
+        assert!(matches!(e.kind(), VCardError::MaxPropertiesExceeded(5)));
+
+        // the limit applies to the properties returned by the `Iterator` implementation
+        let emails = format!(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n{}END:VCARD\r\n",
+            "EMAIL:a@example.com\r\n".repeat(1000)
+        );
+        let mut reader = VCardReader::new_with_limits(emails.as_bytes(), 5000, Some(5));
+        let e = parse_vcard_from_iter(&mut reader).unwrap_err();
+        assert!(matches!(e.kind(), VCardError::MaxPropertiesExceeded(5)));
+        let mut reader = VCardReader::new_with_limits(testant.as_slice(), 5000, Some(3));
+        assert_eq!(card, parse_vcard_from_iter(&mut reader)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_late_version() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = b"BEGIN:VCARD\r\n\
+            FN:Heinrich vom Tosafjord\r\n\
+            EMAIL;TYPE=home,pref:heinrich@example.com\r\n\
+            VERSION:4.0\r\n\
+            NOTE:hello\r\n\
+            END:VCARD\r\n";
+        let card: VCard = std::str::from_utf8(testant)?.parse()?;
+        assert_eq!(VersionValue::V4, card.version.value);
+        assert_eq!(
+            "Heinrich vom Tosafjord",
+            card.fn_property.iter().next().unwrap().value
+        );
+        assert_eq!(1, card.count_emails());
+        assert_eq!(1, card.count_notes());
+
+        // the lines in front of VERSION keep their position
+        let (_, warnings) = VCardReader::new(&testant[..]).parse_vcard_with_warnings()?;
+        assert_eq!(
+            vec![3],
+            warnings.iter().map(ParseWarning::line).collect::<Vec<_>>()
+        );
+
+        let mut reader = VCardReader::new(&testant[..]);
+        reader.version_search_depth = 2;
+        let e = reader.parse_vcard().unwrap_err();
+        assert!(matches!(e.kind(), VCardError::InvalidVersionProperty));
+        assert!(e.to_string().contains("version_search_depth"), "{}", e);
+
+        let missing_version = b"BEGIN:VCARD\r\nFN:Heinrich\r\nEND:VCARD\r\n";
+        let e = VCardReader::new(&missing_version[..])
+            .parse_vcard()
+            .unwrap_err();
+        assert!(matches!(e.kind(), VCardError::InvalidVersionProperty));
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_recovery() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/corrupt_middle.vcf",
+        ));
+
+        let results: Vec<Result<VCard, VCardError>> =
+            VCardReader::new(&testant[..]).vcards().collect();
+        assert_eq!(3, results.len());
+        assert!(results[1].is_err());
+
+        let names: Vec<&str> = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .map(|c| c.fn_property.get_prefered_value().unwrap().value.as_str())
+            .collect();
+        assert_eq!(vec!["Heinrich vom Tosafjord", "Fiona vom Tosafjord"], names);
+
+        // the END:VCARD line of the first vcard is missing
+        let missing_end = b"BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Judith\r\nBEGIN:VCARD\r\nVERSION:4.0\r\nFN:Fiona\r\nEND:VCARD\r\n";
+        let mut reader = VCardReader::new(&missing_end[..]);
+        assert!(reader.parse_vcard().is_err());
+        assert!(reader.skip_to_next_card()?);
+        let card = reader.parse_vcard()?;
+        assert_eq!(
+            "Fiona",
+            card.fn_property.get_prefered_value().unwrap().value
+        );
+        assert!(!reader.skip_to_next_card()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_vcard_lenient() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = b"BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\nitem1.FOO;TYPE=home:bar\r\ngarbage\r\nUID:1\r\nUID:2\r\nEMAIL:heinrich@example.com\r\nEND:VCARD\r\n";
+
+        assert!(VCardReader::new(&testant[..]).parse_vcard().is_err());
+
+        let (card, errors) = VCardReader::new(&testant[..]).parse_vcard_lenient()?;
+        assert_eq!(3, errors.len());
+        assert!(matches!(errors[0], VCardError::At { line: 4, .. }));
+        assert_eq!("1", card.uid.unwrap().value);
+        assert!(card.email.get_prefered_value().is_some());
+        assert_eq!(
+            vec![ProprietaryProperty {
+                name: "FOO".into(),
+                group: Some("item1".into()),
+                value: "bar".into(),
+                parameters: vec![Parameter::Proprietary("TYPE=home".into())],
+            }],
+            card.proprietary_properties
+        );
+
+        let missing_version = b"BEGIN:VCARD\r\nFN:Heinrich vom Tosafjord\r\nEND:VCARD\r\n";
+        assert!(VCardReader::new(&missing_version[..])
+            .parse_vcard_lenient()
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_endings() -> Result<(), Box<dyn std::error::Error>> {
+        let crlf = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom \r\n Tosafjord\r\nNOTE:a\r\nEND:VCARD\r\n";
+        let expected = VCard::parse(crlf)?;
+        assert_eq!("Heinrich vom Tosafjord", expected.display_name().unwrap());
+
+        for line_break in &["\n", "\r"] {
+            let input = crlf.replace("\r\n", line_break);
+            let mut reader = VCardReader::new(input.as_bytes());
+            assert_eq!(expected, reader.parse_vcard()?, "{:?}", line_break);
+
+            let input = input.replace("END:VCARD", &format!("NOPE:value{}END:VCARD", line_break));
+            let e = VCardReader::new(input.as_bytes())
+                .parse_vcard()
+                .unwrap_err();
+            assert_eq!(
+                Some(6),
+                e.position().map(|(line, _)| line),
+                "{:?}",
+                line_break
+            );
+        }
+
+        let input = crlf.replace("NOTE:a\r\n", "NOTE:a\nb\r\n");
+        let mut reader = VCardReader::new(input.as_bytes());
+        reader.line_ending_mode = LineEndingMode::Strict;
+        let card = reader.parse_vcard()?;
+        assert_eq!("a\nb", card.note.get_prefered_value().unwrap().value);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<VCardReader<std::fs::File>>();
+        assert_send::<VCardIterator<std::fs::File>>();
+        assert_send::<VCardIter<std::fs::File>>();
+    }
+
+    #[test]
+    fn test_from_str() -> Result<(), Box<dyn std::error::Error>> {
+        let card = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .email(Email {
+                value: "heinrich@example.com".into(),
+                ..Default::default()
+            })
+            .build()
+            .map_err(VCardError::from)?;
+
+        let s = card.to_string();
+        let parsed: VCard = s.parse()?;
+        assert_eq!(card, parsed);
+        assert_eq!(card, VCard::try_from(&s[..])?);
+        assert_eq!(card, VCard::try_from(s.into_bytes())?);
+
+        assert!("FN:Heinrich vom Tosafjord".parse::<VCard>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcard_21() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/nokia_21.vcf",
+        ));
+        let card = VCardReader::new(&testant[..]).parse_vcard()?;
+
+        assert_eq!(VersionValue::V21, card.version.value);
+        assert_eq!(vec!["Tosafjord".to_string()], card.n.values()[0].surenames);
+
+        let mut tels: Vec<&Tel> = card.tel.iter().collect();
+        tels.sort_by(|a, b| a.value.as_str().cmp(b.value.as_str()));
+        assert_eq!(2, tels.len());
+        assert_eq!("+49 176 10101520", tels[0].value.as_str());
+        assert_eq!(Some(vec![TelType::Cell]), tels[0].type_param);
+        assert_eq!(
+            Some(vec![TelType::Home, TelType::Voice]),
+            tels[1].type_param
+        );
+
+        let adr = card.adr.get_prefered_value().unwrap();
+        assert_eq!(vec!["Katzenhäusen".to_string()], adr.city);
+        assert_eq!(Some(vec![AdrType::Home]), adr.type_param);
+
+        let note = &card.note.get("").unwrap().values()[0];
+        assert_eq!("Ist eine Katze.\\nMag Fisch.", note.value);
+
+        let email = card.email.get_prefered_value().unwrap();
+        assert_eq!(Some(vec![EmailType::Internet]), email.type_param);
+
+        // soft line breaks must not bypass the line limit
+        let input = format!(
+            "BEGIN:VCARD\r\nVERSION:2.1\r\nNOTE;QUOTED-PRINTABLE:{}Katze\r\nEND:VCARD\r\n",
+            "Katze=\r\n".repeat(20)
+        );
+        let mut reader = VCardReader::new_with_logical_line_limit(input.as_bytes(), 50);
+        let e = reader.parse_vcard().unwrap_err();
+        assert!(matches!(e.kind(), VCardError::MaxLineLengthExceeded(50)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_properties() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/outlook_30.vcf",
+        ));
+        let card = VCardReader::new(&testant[..]).parse_vcard()?;
+
+        let legacy: Vec<(&str, &str)> = card
+            .legacy
+            .iter()
+            .map(|p| (p.name.as_ref(), p.value.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("LABEL", "Am Katzenklo 1\\nKatzenhausen 12345\\nDeutschland"),
+                ("LABEL", "Fischweg 2\\nKatzenhausen 12345\\nDeutschland"),
+                ("SORT-STRING", "Tosafjord"),
+                ("CLASS", "PUBLIC"),
+            ],
+            legacy
+        );
+        assert_eq!(
+            vec![Parameter::Type(vec!["WORK".into(), "PREF".into()])],
+            card.legacy[0].parameters
+        );
+
+        let raw = card.to_string();
+        assert!(raw.contains("LABEL;TYPE=HOME:Fischweg 2\\nKatzenhausen 12345\\nDeutschland\r\n"));
+        let reparsed = VCardReader::new(raw.as_bytes()).parse_vcard()?;
+        assert_eq!(card, reparsed);
+
+        // vCard 4.0 does not know these properties
+        let testant = b"BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nCLASS:PUBLIC\r\nEND:VCARD\r\n";
+        assert!(VCardReader::new(&testant[..]).parse_vcard().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_content_line() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/apple_icloud.vcf",
+        ));
+        let mut reader = VCardReader::new(&testant[..]);
+
+        let mut emails = Vec::new();
+        loop {
+            let (line, more) = reader.read_content_line()?;
+            if line.name.eq_ignore_ascii_case("email") {
+                emails.push(Property::try_from(&line)?);
+            }
+            if !more {
+                break;
+            }
+        }
+        assert_eq!(1, emails.len());
+        match &emails[0] {
+            Property::Email(e) => assert_eq!("heinrich@tosafjord.com", e.value),
+            p => panic!("expected EMAIL but got {:?}", p),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_all() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/multiple.vcf",
+        ));
+        assert_eq!(3, VCard::parse_all(testant)?.len());
+        assert_eq!(3, VCard::parse_all(&format!("{}\r\n \r\n", testant))?.len());
+        assert!(VCard::parse_all(&format!("{}garbage\r\n", testant)).is_err());
+        assert!(VCard::parse_all("")?.is_empty());
+
+        // a single vcard must not be followed by other content
+        let card = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nEND:VCARD\r\n";
+        assert!(VCard::parse(&format!("{}\r\n \r\n", card)).is_ok());
+        let e = VCard::parse(testant).unwrap_err();
+        assert!(matches!(e.kind(), VCardError::InvalidEndProperty));
+        assert!(VCard::try_from(format!("{}garbage\r\n", card).into_bytes()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_property_iterator() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/multiple.vcf",
+        ));
+
+        let mut reader = VCardReader::new(&testant[..]);
+        let emails = reader
+            .by_ref()
+            .filter_map(|r| r.ok())
+            .filter(|p| matches!(p, Property::Email(_)))
+            .count();
+        assert_eq!(1, emails);
+
+        let expected = VCard::parse_all(std::str::from_utf8(testant)?)?;
+        let mut reader = VCardReader::new(&testant[..]);
+        for card in &expected {
+            assert_eq!(*card, parse_vcard_from_iter(&mut reader)?);
+            // the end of each vcard is signaled by `None`
+            assert!(reader.next().is_none());
+        }
+        assert!(reader.next().is_none());
+
+        let incomplete = &b"BEGIN:VCARD\r\nVERSION:4.0\r\n"[..];
+        let result = parse_vcard_from_iter(VCardReader::new(incomplete));
+        assert!(matches!(result, Err(VCardError::InvalidEndProperty)));
+        Ok(())
+    }
 }
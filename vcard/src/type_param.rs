@@ -0,0 +1,108 @@
+//! Helpers for TYPE parameters that are stored as strings, e.g. `Url::type_param`.
+//!
+//! TYPE values are case-insensitive, see https://datatracker.ietf.org/doc/html/rfc6350#section-5.6
+//!
+//! ```
+//! use vcard::{type_param, Url};
+//!
+//! let mut url = Url {
+//!     type_param: Some(vec!["Home".into()]),
+//!     ..Default::default()
+//! };
+//! assert!(type_param::has_type(&url.type_param, "HOME"));
+//!
+//! type_param::add_type(&mut url.type_param, "work");
+//! type_param::add_type(&mut url.type_param, "Work");
+//! assert_eq!(Some(vec!["Home".to_string(), "WORK".to_string()]), url.type_param);
+//!
+//! type_param::remove_type(&mut url.type_param, "home");
+//! type_param::remove_type(&mut url.type_param, "WORK");
+//! assert_eq!(None, url.type_param);
+//! ```
+
+/// returns true if `type_param` contains `t`, ignoring the case.
+pub fn has_type(type_param: &Option<Vec<String>>, t: &str) -> bool {
+    type_param
+        .iter()
+        .flatten()
+        .any(|value| value.eq_ignore_ascii_case(t))
+}
+
+/// adds `t` in uppercase, unless `type_param` already contains it.
+pub fn add_type(type_param: &mut Option<Vec<String>>, t: &str) {
+    if !has_type(type_param, t) {
+        type_param
+            .get_or_insert_with(Vec::new)
+            .push(t.to_uppercase());
+    }
+}
+
+/// removes all values that are equal to `t`, ignoring the case. `type_param` becomes `None` if no value is left.
+///
+/// Returns true if a value was removed.
+pub fn remove_type(type_param: &mut Option<Vec<String>>, t: &str) -> bool {
+    let types = match type_param {
+        Some(types) => types,
+        None => return false,
+    };
+    let len = types.len();
+    types.retain(|value| !value.eq_ignore_ascii_case(t));
+    let removed = types.len() != len;
+    if types.is_empty() {
+        *type_param = None;
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_type() {
+        let types = Some(vec!["Home".to_string(), "x-Private".to_string()]);
+        assert!(has_type(&types, "home"));
+        assert!(has_type(&types, "HOME"));
+        assert!(has_type(&types, "X-PRIVATE"));
+        assert!(!has_type(&types, "work"));
+        assert!(!has_type(&None, "home"));
+        assert!(!has_type(&Some(Vec::new()), "home"));
+    }
+
+    #[test]
+    fn test_add_type() {
+        let mut types = None;
+        add_type(&mut types, "home");
+        assert_eq!(Some(vec!["HOME".to_string()]), types);
+
+        // values that are already present are not added twice, whatever their case
+        add_type(&mut types, "Home");
+        add_type(&mut types, "HOME");
+        assert_eq!(Some(vec!["HOME".to_string()]), types);
+
+        let mut types = Some(vec!["work".to_string()]);
+        add_type(&mut types, "WORK");
+        add_type(&mut types, "voice");
+        assert_eq!(Some(vec!["work".to_string(), "VOICE".to_string()]), types);
+    }
+
+    #[test]
+    fn test_remove_type() {
+        let mut types = Some(vec![
+            "Home".to_string(),
+            "work".to_string(),
+            "HOME".to_string(),
+        ]);
+        assert!(remove_type(&mut types, "home"));
+        assert_eq!(Some(vec!["work".to_string()]), types);
+
+        // removing a missing type changes nothing
+        assert!(!remove_type(&mut types, "home"));
+        assert_eq!(Some(vec!["work".to_string()]), types);
+
+        assert!(remove_type(&mut types, "WORK"));
+        assert_eq!(None, types);
+        assert!(!remove_type(&mut types, "work"));
+        assert_eq!(None, types);
+    }
+}
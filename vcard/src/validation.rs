@@ -0,0 +1,675 @@
+use std::fmt::Display;
+
+use crate::{Alternative, KindValue, PidValue, RelatedType, VCard, VersionValue};
+
+/// How serious a `VCardValidationError` is.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    /// the vcard violates RFC 6350, see `VCard::is_valid`.
+    Error,
+    /// the vcard is valid, but servers or other applications might reject or misinterpret it.
+    Warning,
+}
+
+/// Describes a violated RFC 6350 constraint of a `VCard`.
+#[derive(Debug, PartialEq)]
+pub struct VCardValidationError {
+    /// name of the property the constraint belongs to, e.g. `FN`
+    pub field: String,
+    pub constraint: &'static str,
+    pub value: Option<String>,
+    pub severity: Severity,
+}
+
+impl VCardValidationError {
+    pub(crate) fn error(field: &str, constraint: &'static str, value: Option<String>) -> Self {
+        Self {
+            field: field.into(),
+            constraint,
+            value,
+            severity: Severity::Error,
+        }
+    }
+
+    fn warning(field: &str, constraint: &'static str, value: Option<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(field, constraint, value)
+        }
+    }
+}
+
+impl Display for VCardValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.constraint)?;
+        if let Some(value) = &self.value {
+            write!(f, " (got {})", value)?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! check_pref {
+    ($vcard:expr,$errors:expr,$(($field:ident,$name:literal)),*) => {
+        $(
+        for item in $vcard.$field.iter() {
+            if let Some(pref) = item.pref {
+                if !(1..=100).contains(&pref) {
+                    $errors.push(VCardValidationError::error(
+                        $name,
+                        "PREF must be between 1 and 100",
+                        Some(pref.to_string()),
+                    ));
+                }
+            }
+        }
+        )*
+    };
+}
+
+macro_rules! check_alternatives {
+    ($vcard:expr,$errors:expr,$(($field:ident,$name:literal)),*) => {
+        $(
+        let values = $vcard.$field.values();
+        // the entries are alternatives of the same value only if they all share one ALTID
+        let altid = values.first().map_or("", |v| v.get_alt_id());
+        if values.len() > 1
+            && (altid.is_empty() || values.iter().any(|v| v.get_alt_id() != altid))
+        {
+            $errors.push(VCardValidationError::error(
+                $name,
+                concat!($name, " must not appear more than once without a shared ALTID"),
+                None,
+            ));
+        }
+        )*
+    };
+}
+
+macro_rules! check_pid {
+    ($vcard:expr,$errors:expr,$(($field:ident,$name:literal)),*) => {
+        $(
+        for pid in $vcard.$field.iter().flat_map(|item| item.pid.iter().flatten()) {
+            if let Some(source) = unmapped_pid_source($vcard, pid) {
+                $errors.push(VCardValidationError::error(
+                    $name,
+                    "the source identifier of PID must match a CLIENTPIDMAP",
+                    Some(source.to_string()),
+                ));
+            }
+        }
+        )*
+    };
+}
+
+macro_rules! check_language {
+    ($vcard:expr,$errors:expr,$(($field:ident,$name:literal)),*) => {
+        $(
+        for item in $vcard.$field.iter() {
+            if let Some(language) = item.language.as_ref().filter(|l| !is_language_tag(l)) {
+                $errors.push(VCardValidationError::error(
+                    $name,
+                    "LANGUAGE must be a language tag as defined in RFC 5646",
+                    Some(language.clone()),
+                ));
+            }
+        }
+        )*
+    };
+}
+
+macro_rules! check_mediatype {
+    ($vcard:expr,$errors:expr,$(($field:ident,$name:literal)),*) => {
+        $(
+        for item in $vcard.$field.iter() {
+            if let Some(mediatype) = item.mediatype.as_ref().filter(|m| !m.is_well_formed()) {
+                $errors.push(VCardValidationError::warning(
+                    $name,
+                    "MEDIATYPE must be a media type like image/png as defined in RFC 6838",
+                    Some(mediatype.to_string()),
+                ));
+            }
+        }
+        )*
+    };
+}
+
+impl VCard {
+    // N, BDAY and ANNIVERSARY must not appear more than once unless the entries share an ALTID.
+    // `VCardBuilder::build` checks this in every version, RFC 6350 only restricts N of vCard 4.0.
+    pub(crate) fn validate_alternatives(&self) -> Vec<VCardValidationError> {
+        let mut errors = Vec::new();
+        check_alternatives!(
+            self,
+            errors,
+            (n, "N"),
+            (bday, "BDAY"),
+            (anniversary, "ANNIVERSARY")
+        );
+        errors
+    }
+
+    /// Checks this vcard against the constraints of RFC 6350 and returns all violations.
+    ///
+    /// This is pure inspection, the vcard is never changed. The following constraints are errors:
+    /// - FN must be present at least once, see https://datatracker.ietf.org/doc/html/rfc6350#section-6.2.1
+    /// - PREF must be between 1 and 100, see https://datatracker.ietf.org/doc/html/rfc6350#section-5.3
+    /// - CALSCALE on BDAY and ANNIVERSARY is only allowed in vCard 4.0
+    /// - MEMBER is only allowed if KIND is `group`, see https://datatracker.ietf.org/doc/html/rfc6350#section-6.6.5
+    /// - N must not appear more than once in vCard 4.0 unless the entries share an ALTID
+    /// - the source identifier of a PID parameter must match a CLIENTPIDMAP, see https://datatracker.ietf.org/doc/html/rfc6350#section-5.5
+    /// - LANGUAGE parameters and LANG values must be language tags
+    ///
+    /// The following constraints are warnings:
+    /// - CALSCALE other than `gregorian`, which is the only one defined by RFC 6350
+    /// - properties that only exist in vCard 4.0 (KIND, GENDER, ANNIVERSARY, ...) in a vCard 3.0
+    /// - TYPE values of RELATED that are neither defined by RFC 6350 nor start with `X-`,
+    ///   see https://datatracker.ietf.org/doc/html/rfc6350#section-6.6.6
+    /// - MEDIATYPE parameters that are not media types like `image/png`, see https://datatracker.ietf.org/doc/html/rfc6838#section-4.2
+    ///
+    /// GENDER, KIND, UID, REV and PRODID can only be set once in a `VCard`, `VCardBuilder::build` reports
+    /// if they were set more than once.
+    pub fn validate(&self) -> Vec<VCardValidationError> {
+        let mut errors = Vec::new();
+
+        if self.fn_property.is_empty() {
+            errors.push(VCardValidationError::error(
+                "FN",
+                "FN must be present at least once",
+                None,
+            ));
+        }
+
+        check_pref!(
+            self,
+            errors,
+            (fn_property, "FN"),
+            (nickname, "NICKNAME"),
+            (photo, "PHOTO"),
+            (adr, "ADR"),
+            (tel, "TEL"),
+            (email, "EMAIL"),
+            (impp, "IMPP"),
+            (lang, "LANG"),
+            (tz, "TZ"),
+            (geo, "GEO"),
+            (title, "TITLE"),
+            (role, "ROLE"),
+            (logo, "LOGO"),
+            (org, "ORG"),
+            (member, "MEMBER"),
+            (related, "RELATED"),
+            (categories, "CATEGORIES"),
+            (note, "NOTE"),
+            (sound, "SOUND"),
+            (url, "URL"),
+            (key, "KEY"),
+            (fburl, "FBURL"),
+            (caluri, "CALURI"),
+            (caladuri, "CALADRURI")
+        );
+
+        let calscales = self.bday.iter().map(|b| ("BDAY", &b.calscale)).chain(
+            self.anniversary
+                .iter()
+                .map(|a| ("ANNIVERSARY", &a.calscale)),
+        );
+        for (field, calscale) in calscales {
+            let calscale = match calscale {
+                Some(calscale) => calscale,
+                None => continue,
+            };
+            if self.version.value != VersionValue::V4 {
+                errors.push(VCardValidationError::error(
+                    field,
+                    "CALSCALE is only valid in vCard 4.0",
+                    Some(calscale.clone()),
+                ));
+            } else if !calscale.eq_ignore_ascii_case("gregorian") {
+                errors.push(VCardValidationError::warning(
+                    field,
+                    "CALSCALE other than gregorian is not defined by RFC 6350",
+                    Some(calscale.clone()),
+                ));
+            }
+        }
+
+        let is_group = matches!(self.kind.as_ref().map(|k| &k.value), Some(KindValue::Group));
+        if !is_group && !self.member.is_empty() {
+            errors.push(VCardValidationError::error(
+                "MEMBER",
+                "MEMBER is only allowed if KIND is group",
+                self.kind.as_ref().map(|k| k.value.as_ref().to_string()),
+            ));
+        }
+
+        if self.version.value == VersionValue::V4 {
+            check_alternatives!(self, errors, (n, "N"));
+        }
+
+        check_pid!(
+            self,
+            errors,
+            (source, "SOURCE"),
+            (nickname, "NICKNAME"),
+            (photo, "PHOTO"),
+            (adr, "ADR"),
+            (tel, "TEL"),
+            (email, "EMAIL"),
+            (impp, "IMPP"),
+            (lang, "LANG"),
+            (tz, "TZ"),
+            (geo, "GEO"),
+            (title, "TITLE"),
+            (role, "ROLE"),
+            (logo, "LOGO"),
+            (org, "ORG"),
+            (member, "MEMBER"),
+            (related, "RELATED"),
+            (categories, "CATEGORIES"),
+            (note, "NOTE"),
+            (sound, "SOUND"),
+            (url, "URL"),
+            (key, "KEY"),
+            (fburl, "FBURL"),
+            (caluri, "CALURI"),
+            (caladuri, "CALADRURI")
+        );
+
+        check_language!(
+            self,
+            errors,
+            (fn_property, "FN"),
+            (n, "N"),
+            (nickname, "NICKNAME"),
+            (bday, "BDAY"),
+            (adr, "ADR"),
+            (title, "TITLE"),
+            (role, "ROLE"),
+            (logo, "LOGO"),
+            (org, "ORG"),
+            (related, "RELATED"),
+            (note, "NOTE"),
+            (sound, "SOUND")
+        );
+        for lang in self.lang.iter().filter(|l| !l.value.is_well_formed()) {
+            errors.push(VCardValidationError::error(
+                "LANG",
+                "LANG must be a language tag as defined in RFC 5646",
+                Some(lang.value.to_string()),
+            ));
+        }
+
+        check_mediatype!(
+            self,
+            errors,
+            (source, "SOURCE"),
+            (photo, "PHOTO"),
+            (impp, "IMPP"),
+            (tz, "TZ"),
+            (geo, "GEO"),
+            (logo, "LOGO"),
+            (member, "MEMBER"),
+            (related, "RELATED"),
+            (sound, "SOUND"),
+            (url, "URL"),
+            (key, "KEY"),
+            (fburl, "FBURL"),
+            (caluri, "CALURI"),
+            (caladuri, "CALADRURI")
+        );
+
+        if self.version.value == VersionValue::V3 {
+            // properties that do not exist in vCard 3.0
+            let v4_only = [
+                ("KIND", self.kind.is_some()),
+                ("GENDER", self.gender.is_some()),
+                ("ANNIVERSARY", !self.anniversary.is_empty()),
+                ("MEMBER", !self.member.is_empty()),
+                ("LANG", !self.lang.is_empty()),
+                ("XML", !self.xml.is_empty()),
+                ("CLIENTPIDMAP", !self.clientpidmap.is_empty()),
+            ];
+            for (name, _) in v4_only.iter().filter(|(_, present)| *present) {
+                errors.push(VCardValidationError::warning(
+                    name,
+                    "property only exists in vCard 4.0",
+                    None,
+                ));
+            }
+        }
+
+        let unknown_related_types =
+            self.related
+                .iter()
+                .flat_map(|r| r.types())
+                .filter_map(|t| match t {
+                    RelatedType::Proprietary(t)
+                        if !t.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("X-")) =>
+                    {
+                        Some(t)
+                    }
+                    _ => None,
+                });
+        for t in unknown_related_types {
+            errors.push(VCardValidationError::warning(
+                "RELATED",
+                "TYPE must be defined by RFC 6350 or start with X-",
+                Some(t.clone()),
+            ));
+        }
+
+        errors
+    }
+
+    /// returns true if `validate` does not find any errors. Warnings are ignored.
+    pub fn is_valid(&self) -> bool {
+        self.validate()
+            .iter()
+            .all(|e| e.severity == Severity::Warning)
+    }
+}
+
+// returns the source identifier of `pid` if there is no CLIENTPIDMAP for it
+fn unmapped_pid_source(vcard: &VCard, pid: &PidValue) -> Option<u8> {
+    pid.second_digit
+        .filter(|_| vcard.resolve_pid_source(pid).is_none())
+}
+
+// checks the syntax of a language tag like `de`, `en-US` or `zh-Hant-TW`,
+// see https://datatracker.ietf.org/doc/html/rfc5646#section-2.1
+// The primary language must have two or three letters, so names like `Deutsch` are rejected.
+pub(crate) fn is_language_tag(tag: &str) -> bool {
+    let subtags: Vec<&str> = tag.split('-').collect();
+    let is_subtag = |s: &str, len: std::ops::RangeInclusive<usize>| {
+        len.contains(&s.len()) && s.bytes().all(|b| b.is_ascii_alphanumeric())
+    };
+    let is_alpha = |s: &str, len: std::ops::RangeInclusive<usize>| {
+        is_subtag(s, len) && s.bytes().all(|b| b.is_ascii_alphabetic())
+    };
+    let is_singleton = |s: &str, private_use: bool| {
+        s.len() == 1 && is_subtag(s, 1..=1) && s.eq_ignore_ascii_case("x") == private_use
+    };
+
+    // grandfathered tags like `i-enochian` are not checked further
+    if subtags[0].eq_ignore_ascii_case("i") {
+        return subtags.len() > 1 && subtags[1..].iter().all(|s| is_subtag(s, 1..=8));
+    }
+    let mut i = 0;
+    // tags like `x-klingon` have no language, only a private use part
+    if !is_singleton(subtags[0], true) {
+        if !is_alpha(subtags[0], 2..=3) {
+            return false;
+        }
+        i = 1;
+        let mut extlangs = 0;
+        while i < subtags.len() && extlangs < 3 && is_alpha(subtags[i], 3..=3) {
+            i += 1;
+            extlangs += 1;
+        }
+        // script
+        if i < subtags.len() && is_alpha(subtags[i], 4..=4) {
+            i += 1;
+        }
+        // region
+        if i < subtags.len()
+            && (is_alpha(subtags[i], 2..=2)
+                || (subtags[i].len() == 3 && subtags[i].bytes().all(|b| b.is_ascii_digit())))
+        {
+            i += 1;
+        }
+        // variants like `1996` or `rozaj`
+        while i < subtags.len()
+            && (is_subtag(subtags[i], 5..=8)
+                || (is_subtag(subtags[i], 4..=4) && subtags[i].as_bytes()[0].is_ascii_digit()))
+        {
+            i += 1;
+        }
+        // extensions like `u-co-phonebk`
+        while i < subtags.len() && is_singleton(subtags[i], false) {
+            let start = i + 1;
+            i = start;
+            while i < subtags.len() && is_subtag(subtags[i], 2..=8) {
+                i += 1;
+            }
+            if i == start {
+                return false;
+            }
+        }
+    }
+    // private use like `x-whatever`
+    if i < subtags.len() && is_singleton(subtags[i], true) {
+        let rest = &subtags[i + 1..];
+        return !rest.is_empty() && rest.iter().all(|s| is_subtag(s, 1..=8));
+    }
+    i == subtags.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_language_tag;
+    use crate::*;
+
+    #[test]
+    fn test_validate() -> Result<(), Box<dyn std::error::Error>> {
+        let card = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .build()
+            .map_err(VCardError::from)?;
+        assert!(card.is_valid());
+
+        let card = VCard::new(VersionValue::V3)
+            .email(Email {
+                value: "heinrich@example.com".into(),
+                pref: Some(0),
+                ..Default::default()
+            })
+            .member(Member {
+                value: "urn:uuid:03a0e51f-d1aa-4385-8a53-e29025acd8af".into(),
+                ..Default::default()
+            })
+            .bday(BDay {
+                value: "20180301".into(),
+                calscale: Some("julian".into()),
+                ..Default::default()
+            })?
+            .build_unchecked();
+
+        let fields: Vec<String> = card
+            .validate()
+            .into_iter()
+            .filter(|e| e.severity == Severity::Error)
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(vec!["FN", "EMAIL", "BDAY", "MEMBER"], fields);
+
+        let card = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .n(N {
+                surenames: vec!["vom Tosafjord".into()],
+                ..Default::default()
+            })?
+            .n(N {
+                surenames: vec!["Tosafjord".into()],
+                ..Default::default()
+            })?
+            .build_unchecked();
+        let errors = card.validate();
+        assert_eq!(1, errors.len());
+        assert_eq!("N", errors[0].field);
+
+        // vCard 3.0 does not restrict the number of N properties, but the builder does
+        let builder = || {
+            VCard::new(VersionValue::V3)
+                .fn_property(FN {
+                    value: "Heinrich vom Tosafjord".into(),
+                    ..Default::default()
+                })
+                .n(N {
+                    surenames: vec!["vom Tosafjord".into()],
+                    ..Default::default()
+                })?
+                .n(N {
+                    surenames: vec!["Tosafjord".into()],
+                    ..Default::default()
+                })
+        };
+        assert!(builder()?.build_unchecked().validate().is_empty());
+        let errors = builder()?.build().unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!("N", errors[0].field);
+
+        // alternatives must share one ALTID
+        let n = |altid: Option<&str>| N {
+            surenames: vec!["vom Tosafjord".into()],
+            altid: altid.map(Into::into),
+            ..Default::default()
+        };
+        let mut card = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .build_unchecked();
+        card.n = AltIDContainer::from_vec(vec![n(Some("1")), n(Some("1"))]);
+        assert!(card.validate().is_empty());
+        for altids in [[Some("1"), Some("2")], [Some("1"), None], [None, Some("1")]] {
+            card.n = AltIDContainer::from_vec(altids.iter().map(|a| n(*a)).collect());
+            let errors = card.validate();
+            assert_eq!(1, errors.len());
+            assert_eq!("N", errors[0].field);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_severities() -> Result<(), VCardError> {
+        let mut card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\n\
+            EMAIL;PID=1.1:heinrich@example.com\r\n\
+            TEL;PID=1.2:+49 176 10101520\r\n\
+            LANG:en-US\r\n\
+            BDAY;CALSCALE=julian:20180301\r\n\
+            END:VCARD\r\n",
+        )?;
+        // `VCard::parse` rejects invalid language tags, see `VCardReader::strict_language_tags`
+        card.fn_property
+            .update_values(|f| f.language = Some("de_DE".into()));
+        card.clientpidmap.push(ClientPidMap {
+            group: None,
+            pid_digit: 1,
+            value: "urn:uuid:3df403f4-5924-4bb7-b077-3c711d9eb34b"
+                .parse()
+                .unwrap(),
+        });
+
+        let issues: Vec<(String, Severity, Option<String>)> = card
+            .validate()
+            .into_iter()
+            .map(|e| (e.field, e.severity, e.value))
+            .collect();
+        assert_eq!(
+            vec![
+                ("BDAY".to_string(), Severity::Warning, Some("julian".into())),
+                ("TEL".to_string(), Severity::Error, Some("2".into())),
+                ("FN".to_string(), Severity::Error, Some("de_DE".into())),
+            ],
+            issues
+        );
+        assert!(!card.is_valid());
+
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Heinrich vom Tosafjord\r\n\
+            KIND:individual\r\nEND:VCARD\r\n",
+        )?;
+        let issues = card.validate();
+        assert_eq!(1, issues.len());
+        assert_eq!("KIND", issues[0].field);
+        assert_eq!(Severity::Warning, issues[0].severity);
+        assert!(card.is_valid());
+
+        // quoted parameter values are stored without quotes
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN;LANGUAGE=\"de\":Heinrich vom Tosafjord\r\nEND:VCARD\r\n",
+        )?;
+        assert!(card.validate().is_empty());
+
+        assert!(is_language_tag("zh-Hant-TW"));
+        assert!(is_language_tag("x-klingon"));
+        assert!(!is_language_tag("de_DE"));
+        assert!(!is_language_tag("en-"));
+        assert!(!is_language_tag("Deutsch"));
+        assert!(!is_language_tag("de-DE-x"));
+        assert!(is_language_tag("de-CH-1996"));
+        assert!(is_language_tag("en-US-u-co-phonebk-x-private"));
+        assert!(is_language_tag("es-419"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_related_types() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich vom Tosafjord\r\n\
+            RELATED;TYPE=friend,X-Godparent,godparent:urn:uuid:03a0e51f-d1aa-4385-8a53-e29025acd8af\r\n\
+            END:VCARD\r\n",
+        )?;
+        let related = card.related.iter().next().unwrap();
+        assert_eq!(
+            &[
+                RelatedType::Friend,
+                RelatedType::Proprietary("X-Godparent".into()),
+                RelatedType::Proprietary("godparent".into())
+            ],
+            related.types()
+        );
+
+        let issues = card.validate();
+        assert_eq!(1, issues.len());
+        assert_eq!(Severity::Warning, issues[0].severity);
+        assert_eq!(Some("godparent".to_string()), issues[0].value);
+        assert!(card.is_valid());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mediatype() -> Result<(), VCardError> {
+        let card = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\n\
+            SOUND;MEDIATYPE=\"audio/ogg;codecs=opus\":https://example.com/heinrich.ogg\r\n\
+            PHOTO:data:image/PNG;base64,iVBORw0KGgo=\r\n\
+            LOGO;MEDIATYPE=JPEG:https://example.com/logo.jpg\r\n\
+            END:VCARD\r\n",
+        )?;
+        let sound = card.sound.iter().next().unwrap();
+        let mediatype = sound.mediatype.as_ref().unwrap();
+        assert_eq!("audio", mediatype.type_name());
+        assert_eq!("ogg", mediatype.subtype());
+        assert_eq!(
+            vec![("codecs", "opus")],
+            mediatype.attributes().collect::<Vec<_>>()
+        );
+        assert!(card.to_string().contains(
+            "SOUND;MEDIATYPE=\"audio/ogg;codecs=opus\":https://example.com/heinrich.ogg\r\n"
+        ));
+
+        // the media type of inline photos is taken from the data: URI
+        let photo = card.photo.iter().next().unwrap();
+        assert_eq!(Some(MediaType::from("image/png")), photo.mediatype());
+
+        let issues = card.validate();
+        assert_eq!(1, issues.len());
+        assert_eq!("LOGO", issues[0].field);
+        assert_eq!(Severity::Warning, issues[0].severity);
+        assert!(!MediaType::from("image/").is_well_formed());
+        assert!(!MediaType::from("text/plain;charset").is_well_formed());
+        assert!(MediaType::from("text/plain; charset=\"utf-8\"").is_well_formed());
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt::{self, Display};
+
+use crate::{
+    conversion::is_utc_offset, unquote_param_value, ContentLine, ValueDataType, VersionValue,
+};
+
+// parameters that are defined by RFC 6350, see https://datatracker.ietf.org/doc/html/rfc6350#section-5
+const KNOWN_PARAMETERS: &[&str] = &[
+    "LANGUAGE",
+    "VALUE",
+    "PREF",
+    "ALTID",
+    "PID",
+    "TYPE",
+    "MEDIATYPE",
+    "CALSCALE",
+    "SORT-AS",
+    "GEO",
+    "TZ",
+    "LABEL",
+];
+
+// parameters of vCard 2.1 and 3.0 that were removed in vCard 4.0
+const LEGACY_PARAMETERS: &[&str] = &["ENCODING", "CHARSET"];
+
+// properties whose value is a date-and-or-time by default, but may be given as text with VALUE=text
+const DATE_AND_OR_TIME_PROPERTIES: &[&str] = &["BDAY", "ANNIVERSARY"];
+
+/// A non-fatal issue that was found while parsing a vcard, see `VCardReader::parse_vcard_with_warnings`.
+///
+/// `line` is the line number of the property that caused the warning.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseWarning {
+    /// A parameter that is neither defined by RFC 6350 nor an extension parameter starting with `X-`.
+    UnknownParameter {
+        line: u64,
+        property: String,
+        parameter: String,
+    },
+    /// Syntax that is deprecated in the version of the vcard, e.g. `TYPE=pref` in vCard 4.0.
+    DeprecatedSyntax {
+        line: u64,
+        property: String,
+        description: String,
+    },
+    /// The value type was derived from the value because the VALUE parameter is missing,
+    /// e.g. a `tel:` URI in a TEL property without `VALUE=uri`.
+    ImpliedValueType {
+        line: u64,
+        property: String,
+        value_type: ValueDataType,
+    },
+    /// A LANGUAGE parameter that is no language tag as defined by BCP 47, e.g. `de_DE`.
+    /// Only reported if `VCardReader::strict_language_tags` is false, otherwise parsing the property fails.
+    InvalidLanguageTag {
+        line: u64,
+        property: String,
+        value: String,
+    },
+    /// The property could not be added to the vcard, `reason` describes the underlying error.
+    /// Properties with a valid content line are kept in `VCard::proprietary_properties`, see `VCardReader::parse_vcard_lenient`.
+    PropertyIgnored { line: u64, reason: String },
+}
+
+impl ParseWarning {
+    pub fn line(&self) -> u64 {
+        match self {
+            Self::UnknownParameter { line, .. }
+            | Self::DeprecatedSyntax { line, .. }
+            | Self::ImpliedValueType { line, .. }
+            | Self::InvalidLanguageTag { line, .. }
+            | Self::PropertyIgnored { line, .. } => *line,
+        }
+    }
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownParameter {
+                line,
+                property,
+                parameter,
+            } => write!(
+                f,
+                "line {}: unknown parameter {} of {}",
+                line, parameter, property
+            ),
+            Self::DeprecatedSyntax {
+                line,
+                property,
+                description,
+            } => write!(
+                f,
+                "line {}: deprecated syntax in {}: {}",
+                line, property, description
+            ),
+            Self::ImpliedValueType {
+                line,
+                property,
+                value_type,
+            } => write!(
+                f,
+                "line {}: {} has no VALUE parameter, the value is treated as {}",
+                line, property, value_type
+            ),
+            Self::InvalidLanguageTag {
+                line,
+                property,
+                value,
+            } => write!(
+                f,
+                "line {}: invalid language tag {} in {}",
+                line, value, property
+            ),
+            Self::PropertyIgnored { line, reason } => {
+                write!(f, "line {}: property ignored: {}", line, reason)
+            }
+        }
+    }
+}
+
+// collects the warnings of a single content line of a vcard with the given version.
+pub(crate) fn inspect_content_line(
+    line: &ContentLine<'_>,
+    version: &VersionValue,
+    line_number: u64,
+    warnings: &mut Vec<ParseWarning>,
+) {
+    let property = line.name.to_uppercase();
+    let is_v4 = *version == VersionValue::V4;
+
+    for (k, v) in line.params.iter() {
+        let is_known = |names: &[&str]| names.iter().any(|n| k.eq_ignore_ascii_case(n));
+        if is_known(KNOWN_PARAMETERS) || k.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("X-")) {
+            if is_v4
+                && k.eq_ignore_ascii_case("TYPE")
+                && unquote_param_value(v)
+                    .split(',')
+                    .any(|t| t.eq_ignore_ascii_case("pref"))
+            {
+                warnings.push(ParseWarning::DeprecatedSyntax {
+                    line: line_number,
+                    property: property.clone(),
+                    description: "TYPE=pref is replaced by PREF=1 in vCard 4.0".into(),
+                });
+            }
+        } else if is_known(LEGACY_PARAMETERS) {
+            if is_v4 {
+                warnings.push(ParseWarning::DeprecatedSyntax {
+                    line: line_number,
+                    property: property.clone(),
+                    description: format!("{} does not exist in vCard 4.0", k.to_uppercase()),
+                });
+            }
+        } else {
+            warnings.push(ParseWarning::UnknownParameter {
+                line: line_number,
+                property: property.clone(),
+                parameter: k.to_string(),
+            });
+        }
+    }
+
+    let has_value_type = line
+        .params
+        .iter()
+        .any(|(k, _)| k.eq_ignore_ascii_case("VALUE"));
+    if !is_v4 || has_value_type {
+        return;
+    }
+    let is_property = |name: &str| line.name.eq_ignore_ascii_case(name);
+    let value_type = if is_property("TEL") && url::Url::parse(line.value).is_ok() {
+        // TEL is text by default, but phone numbers are often given as tel: URI without VALUE=uri
+        ValueDataType::Uri
+    } else if is_property("TZ") && is_utc_offset(line.value) {
+        ValueDataType::UtcOffset
+    } else if DATE_AND_OR_TIME_PROPERTIES.iter().any(|n| is_property(n))
+        && !is_date_and_or_time(line.value)
+    {
+        ValueDataType::Text
+    } else {
+        return;
+    };
+    warnings.push(ParseWarning::ImpliedValueType {
+        line: line_number,
+        property,
+        value_type,
+    });
+}
+
+// returns true for values like `19960415`, `--0415` or `T102200Z`
+fn is_date_and_or_time(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b"-+:TZ".contains(&b))
+}
@@ -0,0 +1,316 @@
+use std::io::{self, Write};
+
+use crate::{Property, VCard};
+
+/// A writer that writes vcards to an `std::io::Write`, the counterpart of `VCardReader`.
+///
+/// Lines are terminated with CRLF. Logical lines that are longer than `max_line_length` octets are folded into multiple physical lines.
+/// see https://datatracker.ietf.org/doc/html/rfc6350#section-3.2 for more information about line folding.
+/// Use the `Display` implementation of `VCard` if unfolded output is desired.
+pub struct VCardWriter<W: io::Write> {
+    inner: W,
+    pub max_line_length: usize,
+}
+
+const DEFAULT_MAX_LINE_LENGTH: usize = 75;
+
+impl<W: io::Write> VCardWriter<W> {
+    /// Creates a new `VCardWriter` that folds lines at 75 octets
+    pub fn new(output: W) -> Self {
+        Self::new_with_line_length(output, DEFAULT_MAX_LINE_LENGTH)
+    }
+
+    /// Creates a new `VCardWriter` with a configurable line length
+    pub fn new_with_line_length(output: W, max_line_length: usize) -> Self {
+        Self {
+            inner: output,
+            max_line_length,
+        }
+    }
+
+    /// Writes a complete vcard, from `BEGIN:VCARD` to `END:VCARD`, and flushes the underlying writer.
+    pub fn write_vcard(&mut self, vcard: &VCard) -> io::Result<()> {
+        self.write_unflushed(vcard)?;
+        self.inner.flush()
+    }
+
+    /// Writes all vcards one after another and flushes the underlying writer once at the end.
+    pub fn write_all(&mut self, vcards: &[VCard]) -> io::Result<()> {
+        for vcard in vcards {
+            self.write_unflushed(vcard)?;
+        }
+        self.inner.flush()
+    }
+
+    /// Writes a single property. This can be used to stream a vcard property by property,
+    /// including the `BEGIN:VCARD` and `END:VCARD` properties. The underlying writer is not flushed.
+    pub fn write_property(&mut self, property: &Property) -> io::Result<()> {
+        let mut folding_writer = FoldingWriter::new(&mut self.inner, self.max_line_length);
+        write!(folding_writer, "{}", property)?;
+        folding_writer.finish()
+    }
+
+    fn write_unflushed(&mut self, vcard: &VCard) -> io::Result<()> {
+        let mut folding_writer = FoldingWriter::new(&mut self.inner, self.max_line_length);
+        write!(folding_writer, "{}", vcard)?;
+        folding_writer.finish()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+// Folds every line written to it once it exceeds `max_line_length` octets.
+//
+// The current physical line is buffered so that a line is never split inside a multi-byte UTF-8 sequence
+// and continuation lines never start with a whitespace character (the reader would discard those).
+struct FoldingWriter<'a, W> {
+    inner: &'a mut W,
+    max_line_length: usize,
+    line: Vec<u8>,
+    is_continuation: bool,
+}
+
+impl<'a, W: io::Write> FoldingWriter<'a, W> {
+    fn new(inner: &'a mut W, max_line_length: usize) -> Self {
+        Self {
+            inner,
+            max_line_length,
+            line: Vec::with_capacity(max_line_length + 3),
+            is_continuation: false,
+        }
+    }
+
+    fn push(&mut self, b: u8) -> io::Result<()> {
+        if b == b'\n' && self.line.last() == Some(&b'\r') {
+            self.line.push(b);
+            self.inner.write_all(&self.line)?;
+            self.line.clear();
+            self.is_continuation = false;
+            return Ok(());
+        }
+
+        if b != b'\r' && self.line.len() >= self.max_line_length {
+            self.fold(b)?;
+        }
+        self.line.push(b);
+        Ok(())
+    }
+
+    // folds the current line so that `next` can be appended.
+    // If there is no position to fold at, the line is left as is.
+    fn fold(&mut self, next: u8) -> io::Result<()> {
+        let starts_new_line = |b: u8| (b & 0b1100_0000) != 0b1000_0000 && b != b' ' && b != b'\t';
+
+        // at least one content octet has to remain on the current line
+        let min_position = if self.is_continuation { 2 } else { 1 };
+        let mut position = self.line.len();
+        loop {
+            if position < min_position {
+                return Ok(());
+            }
+            let first_byte = if position == self.line.len() {
+                next
+            } else {
+                self.line[position]
+            };
+            if starts_new_line(first_byte) {
+                break;
+            }
+            position -= 1;
+        }
+
+        let rest = self.line.split_off(position);
+        self.inner.write_all(&self.line)?;
+        self.inner.write_all(b"\r\n")?;
+        self.line.clear();
+        self.line.push(b' ');
+        self.line.extend(rest);
+        self.is_continuation = true;
+        Ok(())
+    }
+
+    // writes the remaining content that was not terminated by a line break.
+    fn finish(self) -> io::Result<()> {
+        self.inner.write_all(&self.line)
+    }
+}
+
+impl<'a, W: io::Write> io::Write for FoldingWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for b in buf {
+            self.push(*b)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_line_folding() -> Result<(), Box<dyn std::error::Error>> {
+        let card = VCard::new(VersionValue::V4)
+            .fn_property(FN {
+                value: "Heinrich vom Tosafjord".into(),
+                ..Default::default()
+            })
+            .note(Note {
+                value: "Ein sehr langer Text über Kätzchen, Knödel und Überraschungen.   "
+                    .repeat(5),
+                ..Default::default()
+            })
+            .build()
+            .map_err(VCardError::from)?;
+
+        for max_line_length in [75, 10] {
+            let mut writer = VCardWriter::new_with_line_length(Vec::new(), max_line_length);
+            writer.write_vcard(&card)?;
+            let output = writer.into_inner();
+
+            for line in std::str::from_utf8(&output)?.split_terminator("\r\n") {
+                assert!(line.len() <= max_line_length, "line too long: {}", line);
+            }
+
+            let actual = VCardReader::new(&output[..]).parse_vcard()?;
+            assert_eq!(card, actual);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_all() -> Result<(), Box<dyn std::error::Error>> {
+        let cards = ["Heinrich vom Tosafjord", "Judith"]
+            .iter()
+            .map(|name| {
+                VCard::new(VersionValue::V4)
+                    .fn_property(FN {
+                        value: name.to_string(),
+                        ..Default::default()
+                    })
+                    .build()
+            })
+            .collect::<Result<Vec<VCard>, Vec<VCardValidationError>>>()
+            .map_err(VCardError::from)?;
+
+        let mut writer = VCardWriter::new(Vec::new());
+        writer.write_all(&cards)?;
+        let output = writer.into_inner();
+        assert!(output.ends_with(b"END:VCARD\r\n"));
+
+        let actual = VCardReader::new(&output[..])
+            .vcards()
+            .collect::<Result<Vec<VCard>, VCardError>>()?;
+        assert_eq!(cards, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_adr_parameters() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/apple_icloud_label.vcf",
+        ));
+        let card = VCardReader::new(&testant[..]).parse_vcard()?;
+        let adr = card.adr.iter().next().unwrap();
+        assert_eq!(
+            Some("am Katzenklo\\n23456 Katzenhausen\\nGermany"),
+            adr.label.as_deref()
+        );
+        assert_eq!(Some("geo:53.55,9.99"), adr.geo.as_deref());
+        assert_eq!(Some("Europe/Berlin"), adr.tz.as_deref());
+
+        // the vcard is written as it was read
+        let mut writer = VCardWriter::new(Vec::new());
+        writer.write_vcard(&card)?;
+        let output = writer.into_inner();
+        let actual = VCardReader::new(&output[..]).parse_vcard()?;
+        assert_eq!(Some(adr), actual.adr.iter().next());
+
+        let v4 = card.to_string_as(VersionValue::V4).replace("\r\n ", "");
+        assert!(
+            v4.contains(
+                "item1.ADR;LABEL=\"am Katzenklo\\n23456 Katzenhausen\\nGermany\";\
+            GEO=\"geo:53.55,9.99\";TZ=Europe/Berlin;TYPE=home;PREF=1:\
+            ;;am Katzenklo;Katzenhausen;;23456;Germany\r\n"
+            ),
+            "{}",
+            v4
+        );
+        let upgraded = VCard::parse(&v4)?;
+        let actual = upgraded.adr.iter().next().unwrap();
+        assert_eq!(
+            (&adr.label, &adr.geo, &adr.tz),
+            (&actual.label, &actual.geo, &actual.tz)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_type_values() -> Result<(), Box<dyn std::error::Error>> {
+        let related =
+            "RELATED;VALUE=uri;TYPE=spouse:urn:uuid:03a0e51f-d1aa-4385-8a53-e29025acd8af\r\n";
+        let v4 = VCard::parse(&format!(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Heinrich\r\nTEL;TYPE=CELL:+49 176 1010-1520\r\n\
+            {}END:VCARD\r\n",
+            related
+        ))?;
+        let mut writer = VCardWriter::new(Vec::new());
+        writer.write_vcard(&v4)?;
+        let raw = String::from_utf8(writer.into_inner())?;
+        assert!(
+            raw.contains("TEL;TYPE=cell:+49 176 1010-1520\r\n"),
+            "{}",
+            raw
+        );
+        assert!(raw.contains(related), "{}", raw);
+
+        let v3 = VCard::parse(
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nN:;Heinrich;;;\r\nFN:Heinrich\r\n\
+            TEL;TYPE=cell;TYPE=x-custom:+49 176 1010-1520\r\nEND:VCARD\r\n",
+        )?;
+        let mut writer = VCardWriter::new(Vec::new());
+        writer.write_vcard(&v3)?;
+        let raw = String::from_utf8(writer.into_inner())?;
+        // proprietary values are written as they are
+        assert!(
+            raw.contains("TEL;TYPE=CELL;TYPE=x-custom:+49 176 1010-1520\r\n"),
+            "{}",
+            raw
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_property() -> Result<(), Box<dyn std::error::Error>> {
+        let testant = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_assets/apple_icloud.vcf",
+        ));
+        let expected = VCardReader::new(&testant[..]).parse_vcard()?;
+
+        let mut reader = VCardReader::new(&testant[..]);
+        let mut writer = VCardWriter::new(Vec::new());
+        loop {
+            let (prop, more) = reader.read_property()?;
+            writer.write_property(&prop)?;
+            if !more {
+                break;
+            }
+        }
+        let output = writer.into_inner();
+
+        let actual = VCardReader::new(&output[..]).parse_vcard()?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+}
@@ -83,7 +83,8 @@ fn test_vcards_from_big_services() -> Result<(), Box<dyn Error>> {
                 group: None,
                 value: "2021-09-23T05:51:29Z".into(),
             })
-            .build(),
+            .build()
+            .map_err(VCardError::from)?,
     );
 
     test_table.insert(
@@ -142,7 +143,8 @@ fn test_vcards_from_big_services() -> Result<(), Box<dyn Error>> {
                 value: vec!["Freunde".into(), "myContacts".into(), "starred".into()],
                 ..Default::default()
             })
-            .build(),
+            .build()
+            .map_err(VCardError::from)?,
     );
 
     test_table.insert(
@@ -264,7 +266,8 @@ fn test_vcards_from_big_services() -> Result<(), Box<dyn Error>> {
                 value: vec!["myContacts".into()],
                 ..Default::default()
             })
-            .build(),
+            .build()
+            .map_err(VCardError::from)?,
     );
 
     for (k, expected) in test_table {
@@ -275,61 +278,49 @@ fn test_vcards_from_big_services() -> Result<(), Box<dyn Error>> {
 
         let actual = reader.parse_vcard()?;
 
-        compare_vcards(&expected, &actual);
+        assert_eq!(expected, actual);
 
         // we test the Serialization by feeding it back into our reader.
         let new_val = expected.to_string();
         let new_card = VCardReader::new(new_val.as_bytes()).parse_vcard()?;
 
-        compare_vcards(&expected, &new_card);
+        assert_eq!(expected, new_card);
     }
 
     Ok(())
 }
 
-fn compare_vcards(expected: &VCard, actual: &VCard) {
-    assert_eq!(expected.version, actual.version);
-    assert_eq!(expected.source, actual.source);
-
-    assert_eq!(expected.kind, actual.kind);
-    assert_eq!(expected.xml, actual.xml);
-    assert_eq!(expected.fn_property, actual.fn_property);
-    assert_eq!(expected.n, actual.n);
-    assert_eq!(expected.nickname, actual.nickname);
-    assert_eq!(expected.photo, actual.photo);
-    assert_eq!(expected.bday, actual.bday);
-    assert_eq!(expected.anniversary, actual.anniversary);
-    assert_eq!(expected.gender, actual.gender);
-
-    assert_eq!(expected.adr, actual.adr);
-    assert_eq!(expected.tel, actual.tel);
-    assert_eq!(expected.email, actual.email);
-    assert_eq!(expected.impp, actual.impp);
-    assert_eq!(expected.lang, actual.lang);
-    assert_eq!(expected.tz, actual.tz);
-    assert_eq!(expected.geo, actual.geo);
-    assert_eq!(expected.title, actual.title);
-    assert_eq!(expected.role, actual.role);
-
-    assert_eq!(expected.logo, actual.logo);
-    assert_eq!(expected.org, actual.org);
-    assert_eq!(expected.member, actual.member);
-    assert_eq!(expected.related, actual.related);
-    assert_eq!(expected.categories, actual.categories);
-    assert_eq!(expected.note, actual.note);
-    assert_eq!(expected.prodid, actual.prodid);
-    assert_eq!(expected.rev, actual.rev);
-    assert_eq!(expected.sound, actual.sound);
-
-    assert_eq!(expected.uid, actual.uid);
-    assert_eq!(expected.clientpidmap, actual.clientpidmap);
-    assert_eq!(expected.url, actual.url);
-    assert_eq!(expected.key, actual.key);
-    assert_eq!(expected.fburl, actual.fburl);
-    assert_eq!(expected.caluri, actual.caluri);
-    assert_eq!(expected.caladuri, actual.caladuri);
-    assert_eq!(
-        expected.proprietary_properties,
-        actual.proprietary_properties
-    );
+#[test]
+fn test_display_names_of_big_services() -> Result<(), Box<dyn Error>> {
+    let path = |name: &str| {
+        format!(
+            "{}/test_assets/good_vcards/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        )
+    };
+    let expected = [
+        (
+            "apple_icloud.vcf",
+            "Heinrich vom Tosafjord",
+            Some("heinrich@tosafjord.com"),
+        ),
+        ("google.vcf", "Judith", Some("test@example.com")),
+        (
+            "google_2.vcf",
+            "Dr. Heinrich Kasper Vom Tosafjord Von und Zu",
+            Some("heinrich@example.com"),
+        ),
+    ];
+    for (file, name, email) in expected.iter() {
+        let card = VCard::parse(&std::fs::read_to_string(path(file))?)?;
+        assert_eq!(Some(name.to_string()), card.display_name(), "{}", file);
+        assert_eq!(
+            *email,
+            card.preferred_email().map(|e| e.value.as_str()),
+            "{}",
+            file
+        );
+    }
+    Ok(())
 }
@@ -19,7 +19,7 @@ where
                     .named
                     .iter()
                     .filter_map(|f| f.ident.as_ref())
-                    .find(|ident| ident.to_owned() == field_name)
+                    .find(|ident| *ident == field_name)
                     .is_some();
                 if !field_present {
                     return quote! {
@@ -30,17 +30,17 @@ where
 
                 let name = &struct_item.ident;
 
-                return callback(name).into();
+                callback(name).into()
             }
             _ => {
-                return quote! {
+                quote! {
                     compile_error!(#error_message);
                 }
                 .into()
             }
         },
         _ => {
-            return quote! {
+            quote! {
                 compile_error!(#error_message);
             }
             .into()
@@ -77,7 +77,7 @@ pub fn pref_derive(input: TokenStream) -> TokenStream {
             quote! {
                 impl Preferable for #ident {
                     fn get_pref(&self) -> u8 {
-                        self.pref.unwrap_or_else(||100)
+                        self.pref.unwrap_or_else(||101)
                     }
 
                 }
@@ -86,15 +86,114 @@ pub fn pref_derive(input: TokenStream) -> TokenStream {
     )
 }
 
+#[proc_macro_derive(Group)]
+pub fn group_derive(input: TokenStream) -> TokenStream {
+    impl_getter_trait_for_type(
+        input,
+        "group",
+        "Group can only be used on structs with a group field",
+        |ident| {
+            quote! {
+                impl GroupedProperty for #ident {
+                    fn get_group(&self) -> Option<&str> {
+                        self.group.as_deref()
+                    }
+                }
+            }
+        },
+    )
+}
+
+// Generates `grouped_properties`, which returns all properties of the fields of `VCard` as `PropertyRef`.
+//
+// Fields whose properties cannot be part of a group, e.g. `version`, are marked with `#[group(skip)]`.
+// All other fields must implement `GroupedFields`, i.e. be a container, an `Option` or a `Vec`
+// of a property type that has a `PropertyRef` variant.
+#[proc_macro_derive(GroupedProperties, attributes(group))]
+pub fn grouped_properties_derive(input: TokenStream) -> TokenStream {
+    let item: syn::DeriveInput = syn::parse(input).expect("failed to parse input");
+    let fields =
+        match &item.data {
+            syn::Data::Struct(syn::DataStruct {
+                fields: Fields::Named(fields),
+                ..
+            }) => fields,
+            _ => return quote! {
+                compile_error!("GroupedProperties can only be used on structs with named fields");
+            }
+            .into(),
+        };
+
+    let mut grouped = Vec::new();
+    for field in fields.named.iter() {
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|a| a.path.is_ident("group")) {
+            match attr.parse_args::<Ident>() {
+                Ok(arg) if arg == "skip" => skip = true,
+                _ => {
+                    return quote_spanned! {
+                        attr.span() =>
+                        compile_error!("expected #[group(skip)]");
+                    }
+                    .into()
+                }
+            }
+        }
+        if !skip {
+            grouped.push(field.ident.as_ref().unwrap());
+        }
+    }
+
+    let name = &item.ident;
+    let output = quote! {
+        impl #name {
+            // returns all properties that can be part of a group in the order of the fields
+            pub(crate) fn grouped_properties(&self) -> Vec<crate::PropertyRef<'_>> {
+                let mut result = Vec::new();
+                #(crate::GroupedFields::collect_grouped(&self.#grouped, &mut result);)*
+                result
+            }
+        }
+    };
+    output.into()
+}
+
 // This macro is intended to ease the repetitive `Display` trait implementation.
+//
+// The value is written depending on the name of the property: ORG, ADR, N, GENDER, CATEGORIES and NICKNAME
+// join their components, CLIENTPIDMAP writes `pid_digit;uri`, VERSION and KIND use `as_ref()`, GEO, TZ,
+// PHOTO, LOGO, SOUND and KEY use the `Display` implementation of their value. All other properties,
+// e.g. SOURCE, MEMBER or URL, fall through to `self.value.as_str()`, so their `value` must be a `String`
+// or a type with an `as_str()` method like `url::Url`. A new property with another value type needs its
+// own arm, see `test_value_as_str` in vcard.
 #[proc_macro_attribute]
-pub fn vcard(_metadata: TokenStream, input: TokenStream) -> TokenStream {
+pub fn vcard(metadata: TokenStream, input: TokenStream) -> TokenStream {
     let item: syn::Item = syn::parse(input).expect("failed to parse input");
 
+    // the property name is the uppercased name of the struct, `#[vcard(name = "CALADRURI")]` overrides it.
+    let name = if metadata.is_empty() {
+        None
+    } else {
+        match syn::parse::<syn::MetaNameValue>(metadata) {
+            Ok(syn::MetaNameValue {
+                path,
+                lit: syn::Lit::Str(name),
+                ..
+            }) if path.is_ident("name") => Some(name.value()),
+            _ => {
+                return quote! {
+                    compile_error!("expected #[vcard] or #[vcard(name = \"NAME\")]");
+                }
+                .into()
+            }
+        }
+    };
+
     match item {
         Item::Struct(ref struct_item) => match &struct_item.fields {
             Fields::Named(fields) => {
-                let struct_name = struct_item.ident.to_string().to_uppercase();
+                let struct_name =
+                    name.unwrap_or_else(|| struct_item.ident.to_string().to_uppercase());
                 let mut grp_stmt = quote! {
                     let name = #struct_name;
                     write!(f,"{}",name)?;
@@ -117,14 +216,14 @@ pub fn vcard(_metadata: TokenStream, input: TokenStream) -> TokenStream {
                         "altid" => {
                             stmts.push(quote! {
                                 if let Some(altid) = self.altid.as_ref() {
-                                    write!(f,";ALTID={}",altid)?;
+                                    write!(f,";ALTID={}",crate::quote_param_value(altid))?;
                                 }
                             });
                         }
                         "language" => {
                             stmts.push(quote! {
                                 if let Some(language) = self.language.as_ref() {
-                                    write!(f,";LANGUAGE={}",language)?;
+                                    write!(f,";LANGUAGE={}",crate::quote_param_value(language))?;
                                 }
                             });
                         }
@@ -145,7 +244,7 @@ pub fn vcard(_metadata: TokenStream, input: TokenStream) -> TokenStream {
                         "pid" => {
                             stmts.push(quote! {
                                 if let Some(p) = self.pid.as_ref() {
-                                    write!(f,";PID={}",p)?;
+                                    write!(f,";PID={}",crate::join_pids(p))?;
                                 }
                             });
                         }
@@ -153,7 +252,9 @@ pub fn vcard(_metadata: TokenStream, input: TokenStream) -> TokenStream {
                             stmts.push(quote! {
                                 if let Some(types) = self.type_param.as_ref() {
                                     for t in types {
-                                        write!(f,";TYPE={}",t)?;
+                                        // the alternate flag is passed on, see `Display for VCard`
+                                        let t = if f.alternate() { format!("{:#}",t) } else { t.to_string() };
+                                        write!(f,";TYPE={}",crate::quote_param_value(&t))?;
                                     }
                                 }
                             });
@@ -161,35 +262,42 @@ pub fn vcard(_metadata: TokenStream, input: TokenStream) -> TokenStream {
                         "mediatype" => {
                             stmts.push(quote! {
                                 if let Some(m) = self.mediatype.as_ref() {
-                                    write!(f,";MEDIATYPE={}",m)?;
+                                    write!(f,";MEDIATYPE={}",crate::quote_param_value(m.as_str()))?;
                                 }
                             });
                         }
                         "calscale" => {
                             stmts.push(quote! {
                                 if let Some(c) = self.calscale.as_ref() {
-                                    write!(f,";CALSCALE={}",c)?;
+                                    write!(f,";CALSCALE={}",crate::quote_param_value(c))?;
                                 }
                             });
                         }
                         "sort_as" => {
                             stmts.push(quote! {
                                 if let Some(s) = self.sort_as.as_ref() {
-                                    write!(f,";SORT-AS=\"{}\"",s.join(","))?;
+                                    write!(f,";SORT-AS={}",crate::quote_param_value(&s.join(",")))?;
                                 }
                             });
                         }
                         "geo" => {
                             stmts.push(quote! {
                                 if let Some(g) = self.geo.as_ref() {
-                                    write!(f,";GEO={}",g)?;
+                                    write!(f,";GEO={}",crate::force_quote_param_value(g))?;
+                                }
+                            });
+                        }
+                        "label" => {
+                            stmts.push(quote! {
+                                if let Some(l) = self.label.as_ref() {
+                                    write!(f,";LABEL={}",crate::force_quote_param_value(l))?;
                                 }
                             });
                         }
                         "tz" => {
                             stmts.push(quote! {
                                 if let Some(t) = self.tz.as_ref() {
-                                    write!(f,";TZ={}",t)?;
+                                    write!(f,";TZ={}",crate::quote_param_value(t))?;
                                 }
                             });
                         }
@@ -201,23 +309,23 @@ pub fn vcard(_metadata: TokenStream, input: TokenStream) -> TokenStream {
                 let value_stmt = match &struct_name[..] {
                     "ORG" => {
                         quote! {
-                            write!(f,":{}\r\n",self.value.join(";"))?;
+                            write!(f,":{}\r\n",crate::escape::join_escaped(&self.value,';'))?;
                         }
                     }
                     "CATEGORIES" | "NICKNAME" => {
                         quote! {
-                            write!(f,":{}\r\n",self.value.join(","))?;
+                            write!(f,":{}\r\n",crate::escape::join_escaped(&self.value,','))?;
                         }
                     }
                     "ADR" => {
                         quote! {
-                            write!(f,":{};{};{};{};{};{};{}\r\n",self.po_box.join(","),self.extended_address.join(","),self.street.join(","),self.city.join(","),self.region.join(","),self.postal_code.join(","),self.country.join(","))?;
+                            write!(f,":{};{};{};{};{};{};{}\r\n",crate::escape::join_escaped(&self.po_box,','),crate::escape::join_escaped(&self.extended_address,','),crate::escape::join_escaped(&self.street,','),crate::escape::join_escaped(&self.city,','),crate::escape::join_escaped(&self.region,','),crate::escape::join_escaped(&self.postal_code,','),crate::escape::join_escaped(&self.country,','))?;
                         }
                     }
 
                     "N" => {
                         quote! {
-                            write!(f,":{};{};{};{};{}\r\n",self.surenames.join(","),self.given_names.join(","),self.additional_names.join(","),self.honorific_prefixes.join(","),self.honorific_suffixes.join(","))?;
+                            write!(f,":{};{};{};{};{}\r\n",crate::escape::join_escaped(&self.surenames,','),crate::escape::join_escaped(&self.given_names,','),crate::escape::join_escaped(&self.additional_names,','),crate::escape::join_escaped(&self.honorific_prefixes,','),crate::escape::join_escaped(&self.honorific_suffixes,','))?;
 
                         }
                     }
@@ -234,11 +342,22 @@ pub fn vcard(_metadata: TokenStream, input: TokenStream) -> TokenStream {
                             write!(f,"\r\n")?;
                         }
                     }
+                    "GEO" | "TZ" | "PHOTO" | "LOGO" | "SOUND" | "KEY" => {
+                        quote! {
+                            write!(f,":{}\r\n",self.value)?;
+                        }
+                    }
+                    "CLIENTPIDMAP" => {
+                        quote! {
+                            write!(f,":{};{}\r\n",self.pid_digit,self.value.as_str())?;
+                        }
+                    }
                     "VERSION" | "KIND" => {
                         quote! {
                             write!(f,":{}\r\n",self.value.as_ref())?;
                         }
                     }
+                    // requires `as_str()` on the value, see the comment on `vcard`
                     _ => quote! {
                         write!(f,":{}\r\n",self.value.as_str())?;
                     },
@@ -260,7 +379,7 @@ pub fn vcard(_metadata: TokenStream, input: TokenStream) -> TokenStream {
                 output.into()
             }
             _ => {
-                return quote_spanned! {
+                quote_spanned! {
                     item.span() =>
                     compile_error!("expected named fields");
                 }
@@ -271,7 +390,7 @@ pub fn vcard(_metadata: TokenStream, input: TokenStream) -> TokenStream {
         // If the attribute was applied to any other kind of item, we want
         // to generate a compiler error.
         _ => {
-            return quote_spanned! {
+            quote_spanned! {
                 item.span() =>
                 compile_error!("expected struct");
             }